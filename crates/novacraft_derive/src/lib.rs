@@ -202,6 +202,18 @@ fn def_registries(input: &DeriveInput, enum_name: syn::Ident) -> TokenStream {
         })
         .collect();
 
+    // The paths to the descriptors of the blocks with custom meshes
+    let cm_fpaths: Vec<syn::Path> = variants
+        .iter()
+        .zip(fpaths.iter())
+        .filter(|(var, _)| {
+            var.attrs
+                .iter()
+                .any(|attr| attr.path().is_ident(CUSTOM_MESH_ATTRIBUTE))
+        })
+        .map(|(_, path)| path.clone())
+        .collect();
+
     let mut def_impls = vec![];
     for i in 0..props_path.len() {
         let path = props_path[i].clone();
@@ -254,14 +266,21 @@ fn def_registries(input: &DeriveInput, enum_name: syn::Ident) -> TokenStream {
             #(#lowercase_vidents: VoxelMesh<Mesh>),*
         }
 
-        impl Default for MeshRegistry {
-            fn default() -> Self {
+        impl MeshRegistry {
+            /// Build the meshes of all the blocks, with the textures where they are on the atlas.
+            pub fn new(atlas: &crate::blocks::atlas::AtlasLayout) -> Self {
                 Self {
-                    #(#lowercase_vidents: #fpaths::#vidents().mesh_builder.into()),*
+                    #(#lowercase_vidents: #fpaths::#vidents().mesh_builder.build(atlas)),*
                 }
             }
         }
 
+        impl Default for MeshRegistry {
+            fn default() -> Self {
+                Self::new(&crate::blocks::atlas::AtlasLayout::default())
+            }
+        }
+
         impl VoxelRegistry for MeshRegistry {
             type Voxel = #enum_name;
 
@@ -278,6 +297,7 @@ fn def_registries(input: &DeriveInput, enum_name: syn::Ident) -> TokenStream {
                     Mesh::ATTRIBUTE_UV_0,
                     Mesh::ATTRIBUTE_COLOR,
                     Mesh::ATTRIBUTE_NORMAL,
+                    crate::blocks::animation::ATTRIBUTE_ANIMATION,
                 ]
             }
 
@@ -289,9 +309,44 @@ fn def_registries(input: &DeriveInput, enum_name: syn::Ident) -> TokenStream {
                 VOXEL_CENTER
             }
 
-            #[allow(unused_variables)]
             fn is_covering(&self, voxel: &#enum_name, side: crate::prelude::Face) -> bool {
-                *voxel as u16 != 0 && *voxel != #enum_name::GREENERY
+                ShapeRegistry::get().get_shape(voxel).covering.covers(side)
+            }
+
+            fn is_tintable(&self, voxel: &#enum_name, side: crate::prelude::Face) -> bool {
+                ShapeRegistry::get().get_shape(voxel).tinted.tints(side)
+            }
+        }
+    };
+
+    let def_shapereg = quote! {
+        /// The shapes of all of the blocks, from their descriptors. It's built once, the first
+        /// time it's used, and can be read from anywhere (including the meshing tasks) through
+        /// [`ShapeRegistry::get`].
+        #[derive(Clone)]
+        pub struct ShapeRegistry {
+            #(#lowercase_vidents: BlockShape),*
+        }
+
+        impl Default for ShapeRegistry {
+            fn default() -> Self {
+                Self {
+                    #(#lowercase_vidents: #fpaths::#vidents().shape()),*
+                }
+            }
+        }
+
+        impl ShapeRegistry {
+            pub fn get() -> &'static Self {
+                static SHAPE_REGISTRY: std::sync::OnceLock<ShapeRegistry> =
+                    std::sync::OnceLock::new();
+                SHAPE_REGISTRY.get_or_init(Self::default)
+            }
+
+            pub fn get_shape(&self, block: &#enum_name) -> &BlockShape {
+                match block {
+                    #(#enum_name::#capitalized_vidents => &self.#lowercase_vidents),*
+                }
             }
         }
     };
@@ -311,9 +366,15 @@ fn def_registries(input: &DeriveInput, enum_name: syn::Ident) -> TokenStream {
                 #(app.init_resource::<BlockPropertyRegistry<#props_path>>();)*
                 app.init_resource::<MeshRegistry>();
 
+                // The meshes are rebuilt with the stitched atlas, before the external meshes are
+                // put in the registry (they need the layout of the atlas too).
                 app.add_systems(OnEnter(AssetLoadingState::Loaded),
-                    put_external_meshes_in_mesh_registry_after_load);
+                    (crate::blocks::atlas::stitch_block_atlas,
+                        apply_deferred,
+                        put_external_meshes_in_mesh_registry_after_load).chain());
 
+                app.add_collection_to_loading_state::<_, crate::blocks::atlas::BlockTextures>(
+                    AssetLoadingState::Loading);
                 app.add_collection_to_loading_state::<_, ExternalMeshes>(AssetLoadingState::Loading);
             }
         }
@@ -329,16 +390,22 @@ fn def_registries(input: &DeriveInput, enum_name: syn::Ident) -> TokenStream {
         fn put_external_meshes_in_mesh_registry_after_load(
             mut meshes: ResMut<Assets<Mesh>>,
             mut mreg: ResMut<MeshRegistry>,
+            mut shared_mreg: ResMut<crate::chunk::SharedMeshRegistry>,
             loaded_meshes: Res<ExternalMeshes>,
+            atlas: Res<crate::blocks::atlas::AtlasLayout>,
         ) {
             bevy::log::info!("All assets have been loaded.");
-            #(mreg.#cm_lowercase_vindents.set(meshes.remove(&loaded_meshes.#cm_lowercase_vindents).unwrap()));*
+            #(mreg.#cm_lowercase_vindents.set(#cm_fpaths::#cm_vindets().prepare_external_mesh(
+                meshes.remove(&loaded_meshes.#cm_lowercase_vindents).unwrap(), &atlas)));*
+            // Shared right away, so the first chunks are meshed with the external meshes too.
+            shared_mreg.0 = std::sync::Arc::new(mreg.clone());
         }
     };
 
     let f = quote! {
         #impl_default
         #def_meshreg
+        #def_shapereg
         #registry_plugin
     };
 
@@ -3,8 +3,10 @@ pub(crate) mod mesh_metadata;
 pub mod mesh_utils;
 pub(crate) mod meshem;
 pub(crate) mod pbs;
+pub(crate) mod tint;
 pub(crate) mod update;
 pub mod util;
+pub(crate) mod visibility;
 pub(crate) mod voxel_mesh;
 
 use bevy::prelude::*;
@@ -15,12 +17,17 @@ pub mod prelude {
     pub use crate::mesh_metadata::*;
     pub use crate::meshem::*;
     pub use crate::pbs::*;
+    pub use crate::tint::*;
     pub use crate::update::*;
     pub use crate::util::vav::*;
     pub use crate::util::*;
+    pub use crate::visibility::*;
     pub use crate::voxel_mesh::*;
     pub use crate::VoxelRegistry;
     pub use crate::*;
+    pub use mesh_utils::custom_mesh::*;
+    pub use mesh_utils::partial_mesh::*;
+    pub use mesh_utils::translucent_mesh::*;
     pub use mesh_utils::xsprite_mesh::*;
     pub(crate) use novacraft_utils::*;
 }
@@ -40,12 +47,26 @@ pub trait VoxelRegistry {
     fn get_voxel_dimensions(&self) -> [f32; 3];
     /// The attributes we are considering while meshing the grid.
     fn all_attributes(&self) -> Vec<MeshVertexAttribute>;
+    /// Should the `side` of this voxel be tinted (see [`TintMap`](prelude::TintMap))? XSprite
+    /// voxels don't have sides, they are tinted if any of their sides is tintable. Partial voxels
+    /// aren't tinted.
+    fn is_tintable(&self, _voxel: &Self::Voxel, _side: prelude::Face) -> bool {
+        false
+    }
 }
 
 #[derive(Clone)]
 pub enum VoxelMesh<T> {
     NormalCube(T),
     XSprite(T),
+    /// Voxels that don't fill their whole space, like slabs and stairs (see
+    /// [`partial_mesh`](mesh_utils::partial_mesh)).
+    Partial(T),
+    /// Voxels that can be seen through, like glass and ice (see
+    /// [`translucent_mesh`](mesh_utils::translucent_mesh)).
+    Translucent(T),
+    /// Voxels with meshes that are loaded from assets, like torches and chests (see
+    /// [`custom_mesh`](mesh_utils::custom_mesh)).
     CustomMesh(T),
     Null,
 }
@@ -55,6 +76,8 @@ impl<T> VoxelMesh<T> {
         match self {
             Self::NormalCube(t) => t,
             Self::XSprite(t) => t,
+            Self::Partial(t) => t,
+            Self::Translucent(t) => t,
             Self::CustomMesh(t) => t,
             Self::Null => panic!("Triead unwrapping a Null VoxelMesh type."),
         }
@@ -64,6 +87,8 @@ impl<T> VoxelMesh<T> {
         match self {
             Self::NormalCube(t) => t,
             Self::XSprite(t) => t,
+            Self::Partial(t) => t,
+            Self::Translucent(t) => t,
             Self::CustomMesh(t) => t,
             Self::Null => panic!("{}", msg),
         }
@@ -73,6 +98,8 @@ impl<T> VoxelMesh<T> {
         match self {
             VoxelMesh::NormalCube(t) => VoxelMesh::NormalCube(t),
             VoxelMesh::XSprite(t) => VoxelMesh::XSprite(t),
+            VoxelMesh::Partial(t) => VoxelMesh::Partial(t),
+            VoxelMesh::Translucent(t) => VoxelMesh::Translucent(t),
             VoxelMesh::CustomMesh(t) => VoxelMesh::CustomMesh(t),
             VoxelMesh::Null => VoxelMesh::Null,
         }
@@ -82,6 +109,8 @@ impl<T> VoxelMesh<T> {
         match self {
             VoxelMesh::NormalCube(t) => *t = new_mesh,
             VoxelMesh::XSprite(t) => *t = new_mesh,
+            VoxelMesh::Partial(t) => *t = new_mesh,
+            VoxelMesh::Translucent(t) => *t = new_mesh,
             VoxelMesh::CustomMesh(t) => *t = new_mesh,
             VoxelMesh::Null => {}
         }
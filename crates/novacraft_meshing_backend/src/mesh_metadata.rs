@@ -5,6 +5,7 @@ use bevy::utils::hashbrown::HashMap;
 // vertex belongs to which voxel. The `vivi` field is the same length as the length of the grid,
 // each element of the field is a vector of its quads (a quad is 2 triangles (indices) made out of
 // 4 vertices to create a square, which )
+#[derive(Clone)]
 pub(crate) struct VIVI {
     pub(crate) vivi: Vec<Vec<u32>>,
     pub(crate) map: HashMap<u32, u32>,
@@ -85,9 +86,11 @@ pub enum VoxelChange {
 
 /// Mesh meta-data struct.
 /// T is the voxel type, it needs to be the same as the voxel registry.
+#[derive(Clone)]
 pub struct MeshMD<T> {
     pub(crate) vivi: VIVI,
     pub(crate) smooth_lighting_params: Option<SmoothLightingParameters>,
+    pub(crate) tint_map: Option<TintMap>,
     /// The dimensions of the 3d grid.
     pub dims: Dimensions,
     // T: the voxel type,
@@ -120,4 +123,46 @@ impl<T> MeshMD<T> {
     pub fn get_sl_params(&self) -> Option<SmoothLightingParameters> {
         self.smooth_lighting_params
     }
+    /// Set the `SmoothLightingParameters`, they will be used the next time Smooth Lighting is
+    /// applied to the mesh.
+    pub fn set_sl_params(&mut self, smooth_lighting_params: Option<SmoothLightingParameters>) {
+        self.smooth_lighting_params = smooth_lighting_params;
+    }
+    /// Get read only of the [`TintMap`] of the mesh.
+    pub fn get_tint_map(&self) -> Option<&TintMap> {
+        self.tint_map.as_ref()
+    }
+    /// Whether there are logged changes that haven't been applied to the mesh yet.
+    pub fn has_changes(&self) -> bool {
+        !self.changed_voxels.is_empty()
+    }
+    /// Replace the meta-data with an updated one (usually one that was taken with
+    /// [`snapshot`](MeshMD::snapshot)). The changes that were logged since the snapshot was taken,
+    /// and the current `SmoothLightingParameters` are kept.
+    /// Returns whether there are changes that still need to be applied.
+    pub fn swap_in(&mut self, mut updated: MeshMD<T>) -> bool {
+        updated.changed_voxels.append(&mut self.changed_voxels);
+        updated.smooth_lighting_params = self.smooth_lighting_params;
+        *self = updated;
+        self.has_changes()
+    }
+}
+
+impl<T: Clone> MeshMD<T> {
+    /// Take a snapshot of the meta-data, to work on a copy of the mesh (on another thread for
+    /// example). If `take_changes` is true, the logged changes are moved into the snapshot,
+    /// otherwise they stay here.
+    pub fn snapshot(&mut self, take_changes: bool) -> MeshMD<T> {
+        MeshMD {
+            vivi: self.vivi.clone(),
+            smooth_lighting_params: self.smooth_lighting_params,
+            tint_map: self.tint_map.clone(),
+            dims: self.dims,
+            changed_voxels: if take_changes {
+                std::mem::take(&mut self.changed_voxels)
+            } else {
+                vec![]
+            },
+        }
+    }
 }
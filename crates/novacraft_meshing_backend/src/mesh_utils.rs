@@ -1 +1,4 @@
+pub mod custom_mesh;
+pub mod partial_mesh;
+pub mod translucent_mesh;
 pub mod xsprite_mesh;
@@ -0,0 +1,275 @@
+//! Custom voxels are voxels whose meshes aren't generated by the backend (models loaded from
+//! assets, like torches, lanterns or chests). All of the custom voxels of a grid are batched into
+//! one mesh, each one translated to its position in the grid. Custom voxels don't have their
+//! sides culled, and the mesh is updated in place: only the vertices of the voxels that changed
+//! are removed or added.
+use crate::prelude::*;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, MeshVertexAttribute, VertexAttributeValues};
+use bevy::render::render_resource::PrimitiveTopology;
+use bevy::utils::hashbrown::HashMap;
+
+/// Where the vertices and indices of a custom voxel are in the batched mesh.
+#[derive(Clone, Copy, Debug)]
+pub struct CustomMeshInstance {
+    pub vertex_start: usize,
+    pub vertex_end: usize,
+    pub index_start: usize,
+    pub index_end: usize,
+}
+
+/// The meta-data of a mesh of custom voxels.
+#[derive(Clone)]
+pub struct CustomMeshMetaData<T> {
+    /// The instances in the mesh, by the index of their voxel in the grid.
+    pub instances: HashMap<BlockIndex, CustomMeshInstance>,
+    pub log: Vec<(VoxelChange, T, BlockPos)>,
+}
+
+impl<T> CustomMeshMetaData<T> {
+    /// Take a snapshot of the meta-data, the logged changes are moved into the snapshot.
+    pub fn snapshot(&mut self) -> CustomMeshMetaData<T> {
+        CustomMeshMetaData {
+            instances: self.instances.clone(),
+            log: std::mem::take(&mut self.log),
+        }
+    }
+
+    /// Replace the meta-data with an updated one, keeping the changes that were logged since the
+    /// snapshot was taken. Returns whether there are changes that still need to be applied.
+    pub fn swap_in(&mut self, mut updated: CustomMeshMetaData<T>) -> bool {
+        updated.log.append(&mut self.log);
+        *self = updated;
+        !self.log.is_empty()
+    }
+}
+
+/// Generate the mesh of all the custom voxels in the grid.
+pub fn meshify_custom_voxels<T: Copy, const N: usize>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    grid: &ChunkGrid<T, N>,
+) -> (Mesh, CustomMeshMetaData<T>) {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    let vertices: Vec<(MeshVertexAttribute, VertexAttributeValues)> = reg
+        .all_attributes()
+        .into_iter()
+        .map(|att| {
+            let vals = VertexAttributeValues::new(att.format);
+            (att, vals)
+        })
+        .collect();
+    for (att, vals) in vertices {
+        mesh.insert_attribute(att, vals);
+    }
+    mesh.set_indices(Some(Indices::U32(vec![])));
+
+    let mut md = CustomMeshMetaData {
+        instances: HashMap::new(),
+        log: vec![],
+    };
+    for (voxel_pos, voxel) in grid.enumerate_blocks() {
+        if let VoxelMesh::CustomMesh(voxel_mesh) = reg.get_mesh(&voxel) {
+            let voxel_index = pos_to_index(voxel_pos, grid.dims).unwrap();
+            add_custom_voxel(reg, &mut mesh, &mut md, voxel_mesh, voxel_index, voxel_pos);
+        }
+    }
+    (mesh, md)
+}
+
+/// Apply the logged changes to the mesh. Voxels that were broken or replaced are removed from it,
+/// and custom voxels that were added are appended to it.
+pub fn update_custom_mesh<T>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    mesh: &mut Mesh,
+    md: &mut CustomMeshMetaData<T>,
+    dims: Dimensions,
+) {
+    let log = std::mem::take(&mut md.log);
+    for (change, voxel, voxel_pos) in log.iter() {
+        let voxel_index = pos_to_index(*voxel_pos, dims).unwrap();
+        remove_custom_voxel(mesh, md, voxel_index);
+        if let (VoxelChange::Added, VoxelMesh::CustomMesh(voxel_mesh)) =
+            (change, reg.get_mesh(voxel))
+        {
+            add_custom_voxel(reg, mesh, md, voxel_mesh, voxel_index, *voxel_pos);
+        }
+    }
+}
+
+/// Append the mesh of a custom voxel, translated to its position, to the batched mesh.
+fn add_custom_voxel<T>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    mesh: &mut Mesh,
+    md: &mut CustomMeshMetaData<T>,
+    voxel_mesh: &Mesh,
+    voxel_index: BlockIndex,
+    voxel_pos: BlockPos,
+) {
+    let position_offset = Vec3::from(reg.get_voxel_dimensions()) * voxel_pos.as_vec3();
+    let vertex_start = mesh.count_vertices();
+    for (att, vals) in mesh.attributes_mut() {
+        let voxel_vals = voxel_mesh
+            .attribute(att)
+            .unwrap_or_else(|| panic!("Custom voxel mesh is missing the attribute {:?}.", att));
+        if att == Mesh::ATTRIBUTE_POSITION.id {
+            vals.extend(&voxel_vals.offset_all(position_offset.into()));
+        } else {
+            vals.extend(voxel_vals);
+        }
+    }
+    let vertex_end = mesh.count_vertices();
+
+    let Some(Indices::U32(indices)) = mesh.indices_mut() else {
+        panic!("Expected U32 indices format");
+    };
+    let index_start = indices.len();
+    match voxel_mesh.indices() {
+        Some(Indices::U32(voxel_indices)) => {
+            indices.extend(voxel_indices.iter().map(|i| *i + vertex_start as u32))
+        }
+        Some(Indices::U16(voxel_indices)) => indices.extend(
+            voxel_indices
+                .iter()
+                .map(|i| *i as u32 + vertex_start as u32),
+        ),
+        None => indices.extend(vertex_start as u32..vertex_end as u32),
+    }
+    let index_end = indices.len();
+
+    md.instances.insert(
+        voxel_index,
+        CustomMeshInstance {
+            vertex_start,
+            vertex_end,
+            index_start,
+            index_end,
+        },
+    );
+}
+
+/// Remove the vertices and indices of a custom voxel from the batched mesh (if it's there), and
+/// move the instances that came after it back.
+fn remove_custom_voxel<T>(
+    mesh: &mut Mesh,
+    md: &mut CustomMeshMetaData<T>,
+    voxel_index: BlockIndex,
+) {
+    let Some(removed) = md.instances.remove(&voxel_index) else {
+        return;
+    };
+    let vertex_count = removed.vertex_end - removed.vertex_start;
+    let index_count = removed.index_end - removed.index_start;
+    for (_, vals) in mesh.attributes_mut() {
+        vals.remove_range(removed.vertex_start..removed.vertex_end);
+    }
+    if let Some(Indices::U32(indices)) = mesh.indices_mut() {
+        indices.drain(removed.index_start..removed.index_end);
+        for index in indices[removed.index_start..].iter_mut() {
+            *index -= vertex_count as u32;
+        }
+    }
+    for instance in md.instances.values_mut() {
+        if instance.vertex_start >= removed.vertex_end {
+            instance.vertex_start -= vertex_count;
+            instance.vertex_end -= vertex_count;
+            instance.index_start -= index_count;
+            instance.index_end -= index_count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIMS: UVec3 = UVec3::new(4, 1, 1);
+    const N: usize = 4;
+    const AIR: u8 = 0;
+    const TORCH: u8 = 1;
+
+    /// Custom voxels are a single triangle.
+    struct TestRegistry {
+        torch: Mesh,
+    }
+
+    impl TestRegistry {
+        fn new() -> Self {
+            let mut torch = Mesh::new(PrimitiveTopology::TriangleList);
+            torch.insert_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                vec![[0.0, 0.0, 0.0], [0.5, 0.0, 0.0], [0.0, 0.5, 0.0]],
+            );
+            torch.set_indices(Some(Indices::U32(vec![0, 1, 2])));
+            TestRegistry { torch }
+        }
+    }
+
+    impl VoxelRegistry for TestRegistry {
+        type Voxel = u8;
+
+        fn get_mesh(&self, voxel: &u8) -> VoxelMesh<&Mesh> {
+            match *voxel {
+                TORCH => VoxelMesh::CustomMesh(&self.torch),
+                _ => VoxelMesh::Null,
+            }
+        }
+
+        fn is_covering(&self, _voxel: &u8, _side: Face) -> bool {
+            false
+        }
+
+        fn get_center(&self) -> [f32; 3] {
+            [0.0; 3]
+        }
+
+        fn get_voxel_dimensions(&self) -> [f32; 3] {
+            [1.0; 3]
+        }
+
+        fn all_attributes(&self) -> Vec<MeshVertexAttribute> {
+            vec![Mesh::ATTRIBUTE_POSITION]
+        }
+    }
+
+    fn ranges(instance: &CustomMeshInstance) -> [usize; 4] {
+        [
+            instance.vertex_start,
+            instance.vertex_end,
+            instance.index_start,
+            instance.index_end,
+        ]
+    }
+
+    #[test]
+    fn removing_an_instance_moves_the_next_ones_back() {
+        let reg = TestRegistry::new();
+        let mut grid = ChunkGrid::new([AIR; N], DIMS);
+        for x in 0..3 {
+            grid.set_block(TORCH, UVec3::new(x, 0, 0)).unwrap();
+        }
+        let (mut mesh, mut md) = meshify_custom_voxels(&reg, &grid);
+        assert_eq!(md.instances.len(), 3);
+        assert_eq!(mesh.count_vertices(), 9);
+
+        let middle = UVec3::new(1, 0, 0);
+        md.log.push((VoxelChange::Broken, AIR, middle));
+        update_custom_mesh(&reg, &mut mesh, &mut md, DIMS);
+
+        assert!(md.log.is_empty());
+        assert_eq!(md.instances.len(), 2);
+        assert_eq!(ranges(&md.instances[&0]), [0, 3, 0, 3]);
+        assert_eq!(ranges(&md.instances[&2]), [3, 6, 3, 6]);
+        let Some(Indices::U32(indices)) = mesh.indices() else {
+            panic!("Expected U32 indices");
+        };
+        assert_eq!(indices, &vec![0, 1, 2, 3, 4, 5]);
+        // The vertices that are left belong to the first and the last voxel.
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("Expected Float32x3 positions");
+        };
+        let xs: Vec<f32> = positions.iter().map(|[x, _, _]| *x).collect();
+        assert_eq!(xs, vec![0.0, 0.5, 0.0, 2.0, 2.5, 2.0]);
+    }
+}
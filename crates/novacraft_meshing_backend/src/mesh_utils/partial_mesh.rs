@@ -0,0 +1,380 @@
+//! Partial voxels are voxels that don't fill their whole space (slabs, stairs, fences, etc.). Their
+//! meshes are made of boxes, and the faces of a box that lie on the side of the voxel are culled
+//! when the adjacent voxel covers that side (see [`VoxelRegistry::is_covering`]).
+use crate::prelude::*;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, MeshVertexAttribute, VertexAttributeValues};
+use bevy::render::render_resource::PrimitiveTopology;
+
+/// The meta-data of a mesh of partial voxels. Partial voxels are few and far between, so instead
+/// of keeping track of each voxel's vertices, the mesh is rebuilt from the grid when the voxels
+/// change. The log only keeps track of whether it needs to be rebuilt.
+#[derive(Clone)]
+pub struct PartialMetaData<T> {
+    pub log: Vec<(VoxelChange, T, BlockPos)>,
+}
+
+impl<T> PartialMetaData<T> {
+    /// Take a snapshot of the meta-data, the logged changes are moved into the snapshot.
+    pub fn snapshot(&mut self) -> PartialMetaData<T> {
+        PartialMetaData {
+            log: std::mem::take(&mut self.log),
+        }
+    }
+
+    /// Replace the meta-data with an updated one, keeping the changes that were logged since the
+    /// snapshot was taken. Returns whether there are changes that still need to be applied.
+    pub fn swap_in(&mut self, mut updated: PartialMetaData<T>) -> bool {
+        updated.log.append(&mut self.log);
+        *self = updated;
+        !self.log.is_empty()
+    }
+}
+
+/// Generate the mesh of all the partial voxels in the grid. Voxels on the edges of the grid don't
+/// have their sides culled.
+pub fn meshify_partial_voxels<T: Copy, const N: usize>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    grid: &ChunkGrid<T, N>,
+) -> (Mesh, PartialMetaData<T>) {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    let mut indices: Vec<u32> = vec![];
+    let mut vertices: Vec<(MeshVertexAttribute, VertexAttributeValues)> = reg
+        .all_attributes()
+        .into_iter()
+        .map(|att| {
+            let vals = VertexAttributeValues::new(att.format);
+            (att, vals)
+        })
+        .collect();
+    let voxel_dims = Vec3::from(reg.get_voxel_dimensions());
+    let center = Vec3::from(reg.get_center());
+
+    for (voxel_pos, voxel) in grid.enumerate_blocks() {
+        let VoxelMesh::Partial(voxel_mesh) = reg.get_mesh(&voxel) else {
+            continue;
+        };
+        // true if the side is not covered
+        let neig: Neighbors = grid
+            .enumerate_neighbors(voxel_pos)
+            .map(|(f, n)| n.map_or(true, |t| !reg.is_covering(&t, f.opposite())));
+        add_partial_voxel(
+            neig,
+            &mut indices,
+            &mut vertices,
+            voxel_mesh,
+            center,
+            voxel_dims,
+            voxel_dims * voxel_pos.as_vec3(),
+        );
+    }
+
+    for (att, vals) in vertices {
+        mesh.insert_attribute(att, vals);
+    }
+    mesh.set_indices(Some(Indices::U32(indices)));
+    (mesh, PartialMetaData { log: vec![] })
+}
+
+/// Apply the logged changes, by rebuilding the mesh from the (already changed) grid.
+pub fn update_partial_mesh<T: Copy, const N: usize>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    mesh: &mut Mesh,
+    md: &mut PartialMetaData<T>,
+    grid: &ChunkGrid<T, N>,
+) {
+    if md.log.is_empty() {
+        return;
+    }
+    (*mesh, *md) = meshify_partial_voxels(reg, grid);
+}
+
+/// Add the triangles of a partial voxel's mesh, except for the ones on the sides that are covered.
+pub(crate) fn add_partial_voxel(
+    neig: Neighbors,
+    indices_main: &mut Vec<u32>,
+    vertices: &mut [(MeshVertexAttribute, VertexAttributeValues)],
+    voxel: &Mesh,
+    center: Vec3,
+    voxel_dims: Vec3,
+    position_offset: Vec3,
+) {
+    let vertices_count = vertices[0].1.len() as u32;
+    let VertexAttributeValues::Float32x3(positions) = voxel
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .expect("couldn't get voxel mesh data")
+    else {
+        panic!("Unexpected vertex format for position attribute, expected Float32x3.");
+    };
+    let Indices::U32(indices) = voxel.indices().expect("couldn't get indices data") else {
+        panic!("Expected U32 indices format");
+    };
+    let min = center - voxel_dims / 2.0;
+    let max = center + voxel_dims / 2.0;
+    // The side of the voxel a vertex lies on, on each axis (if any), as the index of the face.
+    let side_of = |v: [f32; 3], axis: usize| -> Option<usize> {
+        let faces = [
+            (Face::Left, Face::Right),
+            (Face::Bottom, Face::Top),
+            (Face::Forward, Face::Back),
+        ];
+        if (v[axis] - min[axis]).abs() < 1e-4 {
+            Some(faces[axis].0 as usize)
+        } else if (v[axis] - max[axis]).abs() < 1e-4 {
+            Some(faces[axis].1 as usize)
+        } else {
+            None
+        }
+    };
+
+    let mut indices_to_save: Vec<u32> = vec![];
+    // The vertices of the voxel mesh that are used, and their new index.
+    let mut new_index: Vec<Option<u32>> = vec![None; positions.len()];
+    let mut final_vertices: Vec<u32> = vec![];
+    for triangle in indices.chunks(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| positions[i as usize]);
+        let culled = (0..3).any(|axis| {
+            let side = side_of(a, axis);
+            side.is_some()
+                && side == side_of(b, axis)
+                && side == side_of(c, axis)
+                && !neig[side.unwrap()]
+        });
+        if culled {
+            continue;
+        }
+        for &i in triangle {
+            let index = *new_index[i as usize].get_or_insert_with(|| {
+                final_vertices.push(i);
+                final_vertices.len() as u32 - 1
+            });
+            indices_to_save.push(index + vertices_count);
+        }
+    }
+
+    for (att, vals) in vertices.iter_mut() {
+        let mut needed = voxel
+            .attribute(att.id)
+            .expect(format!("Couldn't retrieve voxel mesh attribute {:?}.", att).as_str())
+            .get_needed(&final_vertices);
+        if att.id == Mesh::ATTRIBUTE_POSITION.id {
+            needed = needed.offset_all(position_offset.into());
+        }
+        vals.extend(&needed);
+    }
+    indices_main.extend(indices_to_save);
+}
+
+/// Generate the mesh of a partial voxel out of boxes (relative to the center of the voxel, for a
+/// voxel of size 1). Each side of each box is textured with the part of the texture it covers, so
+/// the texture lines up with full voxels of the same texture.
+pub fn generate_partial_mesh(
+    boxes: &[VoxelBox],
+    voxel_dims: [f32; 3],
+    texture_atlas_dims: [u32; 2],
+    texture: [(Face, [u32; 2]); 6],
+    voxel_center: [f32; 3],
+    padding: f32,
+    default_color_intensity: Option<f32>,
+    alpha: f32,
+) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    let voxel_dims = Vec3::from(voxel_dims);
+    let voxel_center = Vec3::from(voxel_center);
+
+    let u = 1.0 / (texture_atlas_dims[0] as f32);
+    let v = 1.0 / (texture_atlas_dims[1] as f32);
+    let padding_u = padding / (texture_atlas_dims[0] as f32);
+    let padding_v = padding / (texture_atlas_dims[1] as f32);
+    let mut tiles: [[f32; 2]; 6] = [[0.0, 0.0]; 6];
+    texture.iter().for_each(|(f, [a, b])| {
+        tiles[*f as usize] = [*a as f32 * u + padding_u, *b as f32 * v + padding_v]
+    });
+
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut uvs: Vec<[f32; 2]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+    for b in boxes {
+        let [nx, ny, nz] = b.min.to_array();
+        let [x, y, z] = b.max.to_array();
+        // The quads are in the same order, and wound the same way, as the quads of a full voxel.
+        #[rustfmt::skip]
+        let quads: [(Face, [f32; 3], [[f32; 3]; 4]); 6] = [
+            (Face::Top, [0.0, 1.0, 0.0], [[nx, y, z], [x, y, z], [x, y, nz], [nx, y, nz]]),
+            (Face::Bottom, [0.0, -1.0, 0.0], [[nx, ny, nz], [x, ny, nz], [x, ny, z], [nx, ny, z]]),
+            (Face::Right, [1.0, 0.0, 0.0], [[x, y, nz], [x, y, z], [x, ny, z], [x, ny, nz]]),
+            (Face::Left, [-1.0, 0.0, 0.0], [[nx, ny, nz], [nx, ny, z], [nx, y, z], [nx, y, nz]]),
+            (Face::Back, [0.0, 0.0, 1.0], [[x, y, z], [nx, y, z], [nx, ny, z], [x, ny, z]]),
+            (Face::Forward, [0.0, 0.0, -1.0], [[x, ny, nz], [nx, ny, nz], [nx, y, nz], [x, y, nz]]),
+        ];
+        for (face, normal, corners) in quads {
+            let first = positions.len() as u32;
+            for [cx, cy, cz] in corners {
+                // Where the corner is on the texture, from 0.0 to 1.0 on each axis.
+                let (tu, tv) = match face {
+                    Face::Top => (cx + 0.5, 0.5 - cz),
+                    Face::Bottom => (0.5 - cx, 0.5 - cz),
+                    Face::Right => (cz + 0.5, 0.5 - cy),
+                    Face::Left => (0.5 - cz, 0.5 - cy),
+                    Face::Back => (0.5 - cx, 0.5 - cy),
+                    Face::Forward => (cx + 0.5, 0.5 - cy),
+                };
+                let tile = tiles[face as usize];
+                uvs.push([
+                    tile[0] + tu * (u - padding_u * 2.0),
+                    tile[1] + tv * (v - padding_v * 2.0),
+                ]);
+                positions.push((Vec3::new(cx, cy, cz) * voxel_dims + voxel_center).to_array());
+                normals.push(normal);
+            }
+            indices.extend([0, 1, 3, 2, 3, 1].map(|i| first + i));
+        }
+    }
+
+    if let Some(color) = default_color_intensity {
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_COLOR,
+            vec![[color, color, color, alpha]; positions.len()],
+        );
+    }
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIMS: UVec3 = UVec3::new(2, 2, 1);
+    const N: usize = 2 * 2;
+    const AIR: u8 = 0;
+    const CUBE: u8 = 1;
+    const SLAB: u8 = 2;
+    const POST: u8 = 3;
+
+    struct TestRegistry {
+        cube: Mesh,
+        slab: Mesh,
+        post: Mesh,
+    }
+
+    impl TestRegistry {
+        fn new() -> Self {
+            let mesh_of = |boxes: &[VoxelBox]| {
+                generate_partial_mesh(
+                    boxes,
+                    [1.0; 3],
+                    [1, 1],
+                    FACES.map(|face| (face, [0, 0])),
+                    [0.0; 3],
+                    0.0,
+                    None,
+                    1.0,
+                )
+            };
+            TestRegistry {
+                cube: mesh_of(&[VoxelBox::FULL]),
+                slab: mesh_of(&[VoxelBox::new(Vec3::splat(-0.5), Vec3::new(0.5, 0.0, 0.5))]),
+                post: mesh_of(&[VoxelBox::new(
+                    Vec3::new(-0.125, -0.5, -0.125),
+                    Vec3::new(0.125, 0.5, 0.125),
+                )]),
+            }
+        }
+    }
+
+    impl VoxelRegistry for TestRegistry {
+        type Voxel = u8;
+
+        fn get_mesh(&self, voxel: &u8) -> VoxelMesh<&Mesh> {
+            match *voxel {
+                CUBE => VoxelMesh::NormalCube(&self.cube),
+                SLAB => VoxelMesh::Partial(&self.slab),
+                POST => VoxelMesh::Partial(&self.post),
+                _ => VoxelMesh::Null,
+            }
+        }
+
+        fn is_covering(&self, voxel: &u8, side: Face) -> bool {
+            match *voxel {
+                CUBE => true,
+                SLAB => matches!(side, Face::Bottom),
+                _ => false,
+            }
+        }
+
+        fn get_center(&self) -> [f32; 3] {
+            [0.0; 3]
+        }
+
+        fn get_voxel_dimensions(&self) -> [f32; 3] {
+            [1.0; 3]
+        }
+
+        fn all_attributes(&self) -> Vec<MeshVertexAttribute> {
+            vec![
+                Mesh::ATTRIBUTE_POSITION,
+                Mesh::ATTRIBUTE_UV_0,
+                Mesh::ATTRIBUTE_NORMAL,
+            ]
+        }
+    }
+
+    fn grid_with(voxels: &[(UVec3, u8)]) -> ChunkGrid<u8, N> {
+        let mut grid = ChunkGrid::new([AIR; N], DIMS);
+        for (pos, voxel) in voxels {
+            grid.set_block(*voxel, *pos).unwrap();
+        }
+        grid
+    }
+
+    /// The amount of quads in the mesh that face the side `face`.
+    fn quads_facing(mesh: &Mesh, face: Face) -> usize {
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("Expected Float32x3 normals");
+        };
+        let offset = face.offset().as_vec3().to_array();
+        normals.iter().filter(|normal| **normal == offset).count() / 4
+    }
+
+    #[test]
+    fn face_next_to_cube_is_culled() {
+        let grid = grid_with(&[(UVec3::new(0, 0, 0), SLAB), (UVec3::new(1, 0, 0), CUBE)]);
+        let (mesh, _) = meshify_partial_voxels(&TestRegistry::new(), &grid);
+        assert_eq!(quads_facing(&mesh, Face::Right), 0);
+        for face in [
+            Face::Top,
+            Face::Bottom,
+            Face::Left,
+            Face::Back,
+            Face::Forward,
+        ] {
+            assert_eq!(quads_facing(&mesh, face), 1, "{:?}", face);
+        }
+    }
+
+    #[test]
+    fn face_next_to_slab_bottom_is_culled() {
+        // The slab covers the top of the post, but the post doesn't cover the bottom of the slab.
+        let grid = grid_with(&[(UVec3::new(0, 0, 0), POST), (UVec3::new(0, 1, 0), SLAB)]);
+        let (mesh, _) = meshify_partial_voxels(&TestRegistry::new(), &grid);
+        assert_eq!(quads_facing(&mesh, Face::Top), 1);
+        assert_eq!(quads_facing(&mesh, Face::Bottom), 2);
+    }
+
+    #[test]
+    fn face_next_to_slab_side_is_not_culled() {
+        // The side of a bottom slab is only half covered.
+        let grid = grid_with(&[(UVec3::new(0, 0, 0), SLAB), (UVec3::new(1, 0, 0), SLAB)]);
+        let (mesh, _) = meshify_partial_voxels(&TestRegistry::new(), &grid);
+        for face in FACES {
+            assert_eq!(quads_facing(&mesh, face), 2, "{:?}", face);
+        }
+    }
+}
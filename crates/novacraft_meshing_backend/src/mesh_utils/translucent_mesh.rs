@@ -0,0 +1,135 @@
+//! Translucent voxels are voxels that can be seen through (glass, ice, etc.). They are drawn with
+//! alpha blending, so they are in a mesh of their own, and its triangles need to be sorted from
+//! back to front (relative to the camera) to blend correctly (see [`sort_translucent_mesh`]).
+//! A translucent voxel doesn't cover its neighbors, but the faces between two identical
+//! translucent voxels are culled, so a wall of glass doesn't show the glass inside of it.
+use super::partial_mesh::add_partial_voxel;
+use crate::prelude::*;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, MeshVertexAttribute, VertexAttributeValues};
+use bevy::render::render_resource::PrimitiveTopology;
+
+/// The meta-data of a mesh of translucent voxels. Like partial voxels, the mesh is rebuilt from
+/// the grid when the voxels change.
+#[derive(Clone)]
+pub struct TranslucentMetaData<T> {
+    pub log: Vec<(VoxelChange, T, BlockPos)>,
+    /// The position (in the space of the mesh) the triangles were last sorted from, `None` if
+    /// they haven't been sorted since the mesh was built.
+    pub sorted_from: Option<Vec3>,
+}
+
+impl<T> TranslucentMetaData<T> {
+    /// Take a snapshot of the meta-data, the logged changes are moved into the snapshot.
+    pub fn snapshot(&mut self) -> TranslucentMetaData<T> {
+        TranslucentMetaData {
+            log: std::mem::take(&mut self.log),
+            sorted_from: self.sorted_from,
+        }
+    }
+
+    /// Replace the meta-data with an updated one, keeping the changes that were logged since the
+    /// snapshot was taken. Returns whether there are changes that still need to be applied.
+    pub fn swap_in(&mut self, mut updated: TranslucentMetaData<T>) -> bool {
+        updated.log.append(&mut self.log);
+        *self = updated;
+        !self.log.is_empty()
+    }
+}
+
+/// Generate the mesh of all the translucent voxels in the grid. A side of a translucent voxel is
+/// culled if its neighbor covers it, or if its neighbor is the same voxel. Voxels on the edges of
+/// the grid don't have their sides culled.
+pub fn meshify_translucent_voxels<T: Copy + PartialEq, const N: usize>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    grid: &ChunkGrid<T, N>,
+) -> (Mesh, TranslucentMetaData<T>) {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    let mut indices: Vec<u32> = vec![];
+    let mut vertices: Vec<(MeshVertexAttribute, VertexAttributeValues)> = reg
+        .all_attributes()
+        .into_iter()
+        .map(|att| {
+            let vals = VertexAttributeValues::new(att.format);
+            (att, vals)
+        })
+        .collect();
+    let voxel_dims = Vec3::from(reg.get_voxel_dimensions());
+    let center = Vec3::from(reg.get_center());
+
+    for (voxel_pos, voxel) in grid.enumerate_blocks() {
+        let VoxelMesh::Translucent(voxel_mesh) = reg.get_mesh(&voxel) else {
+            continue;
+        };
+        // true if the side is not covered
+        let neig: Neighbors = grid
+            .enumerate_neighbors(voxel_pos)
+            .map(|(f, n)| n.map_or(true, |t| t != voxel && !reg.is_covering(&t, f.opposite())));
+        add_partial_voxel(
+            neig,
+            &mut indices,
+            &mut vertices,
+            voxel_mesh,
+            center,
+            voxel_dims,
+            voxel_dims * voxel_pos.as_vec3(),
+        );
+    }
+
+    for (att, vals) in vertices {
+        mesh.insert_attribute(att, vals);
+    }
+    mesh.set_indices(Some(Indices::U32(indices)));
+    (
+        mesh,
+        TranslucentMetaData {
+            log: vec![],
+            sorted_from: None,
+        },
+    )
+}
+
+/// Apply the logged changes, by rebuilding the mesh from the (already changed) grid.
+pub fn update_translucent_mesh<T: Copy + PartialEq, const N: usize>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    mesh: &mut Mesh,
+    md: &mut TranslucentMetaData<T>,
+    grid: &ChunkGrid<T, N>,
+) {
+    if md.log.is_empty() {
+        return;
+    }
+    (*mesh, *md) = meshify_translucent_voxels(reg, grid);
+}
+
+/// Sort the triangles of the mesh from back to front, as seen from `eye` (in the space of the
+/// mesh), by the distance to their centers.
+pub fn sort_translucent_mesh<T>(mesh: &mut Mesh, md: &mut TranslucentMetaData<T>, eye: Vec3) {
+    md.sorted_from = Some(eye);
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+    let Some(Indices::U32(indices)) = mesh.indices() else {
+        return;
+    };
+    let mut triangles: Vec<(f32, [u32; 3])> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let triangle = [triangle[0], triangle[1], triangle[2]];
+            let center = triangle
+                .iter()
+                .map(|i| Vec3::from(positions[*i as usize]))
+                .sum::<Vec3>()
+                / 3.0;
+            (center.distance_squared(eye), triangle)
+        })
+        .collect();
+    triangles.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    let sorted = triangles
+        .into_iter()
+        .flat_map(|(_, triangle)| triangle)
+        .collect();
+    mesh.set_indices(Some(Indices::U32(sorted)));
+}
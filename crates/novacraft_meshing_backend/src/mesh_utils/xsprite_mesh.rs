@@ -5,14 +5,36 @@ use bevy::render::render_resource::PrimitiveTopology;
 
 pub type XSpriteVIVI = Vec<(usize, usize, u32, u32)>;
 
+#[derive(Clone)]
 pub struct XSpriteMetaData<T> {
     pub vivi: XSpriteVIVI,
+    pub tint_map: Option<TintMap>,
     pub log: Vec<(VoxelChange, T, BlockPos)>,
 }
 
+impl<T> XSpriteMetaData<T> {
+    /// Take a snapshot of the meta-data, the logged changes are moved into the snapshot.
+    pub fn snapshot(&mut self) -> XSpriteMetaData<T> {
+        XSpriteMetaData {
+            vivi: self.vivi.clone(),
+            tint_map: self.tint_map.clone(),
+            log: std::mem::take(&mut self.log),
+        }
+    }
+
+    /// Replace the meta-data with an updated one, keeping the changes that were logged since the
+    /// snapshot was taken. Returns whether there are changes that still need to be applied.
+    pub fn swap_in(&mut self, mut updated: XSpriteMetaData<T>) -> bool {
+        updated.log.append(&mut self.log);
+        *self = updated;
+        !self.log.is_empty()
+    }
+}
+
 pub fn meshify_xsprite_voxels<T: Copy, const N: usize>(
     reg: &impl VoxelRegistry<Voxel = T>,
     grid: &ChunkGrid<T, N>,
+    tint_map: Option<TintMap>,
 ) -> (Mesh, XSpriteMetaData<T>) {
     let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
 
@@ -113,10 +135,26 @@ pub fn meshify_xsprite_voxels<T: Copy, const N: usize>(
     mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
     mesh.set_indices(Some(Indices::U32(indices)));
 
+    if let Some(ref tint_map) = tint_map {
+        for (block_pos, block) in grid.enumerate_blocks() {
+            if is_xsprite_tintable(reg, &block) {
+                let (vertex_start, vertex_end, _, _) =
+                    data_structure[pos_to_index(block_pos, grid.dims).unwrap()];
+                tint_map.tint_vertices(
+                    &mut mesh,
+                    vertex_start..vertex_end,
+                    voxel_dims,
+                    reg.get_center(),
+                );
+            }
+        }
+    }
+
     (
         mesh,
         XSpriteMetaData {
             vivi: data_structure.to_vec(),
+            tint_map,
             log: vec![],
         },
     )
@@ -131,14 +169,27 @@ pub fn update_xsprite_mesh<T>(
     for (change, block, block_pos) in md.log.iter() {
         let block_index: BlockIndex = pos_to_index(*block_pos, dims).unwrap();
         match change {
-            VoxelChange::Added => add_xsprite_voxel(
-                mesh,
-                &mut md.vivi,
-                *block_pos,
-                reg.get_mesh(block).unwrap(),
-                reg.get_voxel_dimensions().into(),
-                dims,
-            ),
+            VoxelChange::Added => {
+                add_xsprite_voxel(
+                    mesh,
+                    &mut md.vivi,
+                    *block_pos,
+                    reg.get_mesh(block).unwrap(),
+                    reg.get_voxel_dimensions().into(),
+                    dims,
+                );
+                if let Some(ref tint_map) = md.tint_map {
+                    if is_xsprite_tintable(reg, block) {
+                        let (vertex_start, vertex_end, _, _) = md.vivi[block_index];
+                        tint_map.tint_vertices(
+                            mesh,
+                            vertex_start..vertex_end,
+                            reg.get_voxel_dimensions(),
+                            reg.get_center(),
+                        );
+                    }
+                }
+            }
             VoxelChange::Broken => {
                 remove_xsprite_voxel(mesh, &mut md.vivi, block_index);
             }
@@ -148,6 +199,11 @@ pub fn update_xsprite_mesh<T>(
     md.log.clear();
 }
 
+/// XSprites don't have sides, they are tinted if any of their sides is tintable.
+fn is_xsprite_tintable<T>(reg: &impl VoxelRegistry<Voxel = T>, voxel: &T) -> bool {
+    FACES.into_iter().any(|face| reg.is_tintable(voxel, face))
+}
+
 fn remove_xsprite_voxel(mesh: &mut Mesh, md: &mut XSpriteVIVI, block_index: BlockIndex) {
     let (vertex_start, vertex_end, index_start, index_end) = md[block_index];
     let last = vertex_end == mesh.count_vertices();
@@ -25,6 +25,8 @@ pub enum MeshingAlgorithm {
 /// - ['sl']: Enable Smooth Lighting (Some ..) or not (None). Smooth Lighting is a technique often used in
 ///     voxel based games that resembles Ambient Occlusion, but it is static- which means the
 ///     shadows are computed only once, when the mesh is generated (or updated).
+/// - ['tint_map']: The tint of the columns of the grid (see [`TintMap`]), the faces the registry
+///     says are tintable are tinted by it. None => nothing is tinted.
 ///
 /// Return:
 /// - The first mesh is the mesh of the full, normal cube voxels. (for example, the stone blocks)
@@ -36,6 +38,7 @@ pub fn meshify_cubic_voxels<T: Copy, const N: usize>(
     reg: &impl VoxelRegistry<Voxel = T>,
     meshing_algorithm: MeshingAlgorithm,
     smooth_lighting_params: Option<SmoothLightingParameters>,
+    tint_map: Option<TintMap>,
 ) -> Option<(Mesh, MeshMD<T>)> {
     let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
     let total_voxels = grid.len();
@@ -90,9 +93,16 @@ pub fn meshify_cubic_voxels<T: Copy, const N: usize>(
     }
     mesh.set_indices(Some(Indices::U32(indices)));
 
+    if let Some(ref tint_map) = tint_map {
+        tint_quads_from(&mut mesh, &vivi, reg, tint_map, 0, |voxel_index, _| {
+            grid.get_block(index_to_pos(voxel_index, grid.dims)?)
+        });
+    }
+
     let d_mesh = MeshMD {
         dims: grid.dims,
         smooth_lighting_params,
+        tint_map,
         vivi,
         changed_voxels: vec![],
     };
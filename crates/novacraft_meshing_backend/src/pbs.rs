@@ -6,7 +6,7 @@ use crate::util::direction::Direction;
 use bevy::math::Vec3;
 use bevy::render::mesh::{Mesh, VertexAttributeValues};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 /// Parameters for Smooth Lighting
 pub struct SmoothLightingParameters {
     /// How intense the shadow is. 0.0 - 1.0
@@ -29,7 +29,9 @@ pub(crate) fn apply_sl_quad(
     face: Face,
     surrounding_blocks: [bool; 3 * 3 * 3],
     slparams: SmoothLightingParameters,
+    tint_map: Option<&TintMap>,
     voxel_dims: [f32; 3],
+    center: [f32; 3],
     dims: Dimensions,
 ) {
     let quad = vivi
@@ -102,7 +104,38 @@ pub(crate) fn apply_sl_quad(
         total = total.min(2.0);
         let color = total * slparams.intensity;
         let color = (1.0 - color.min(1.0).powf(slparams.smoothing)).max(1.0 - slparams.max);
-        colors[ver as usize] = [color, color, color, 1.0]
+        // The shadow replaces the color of the vertex, so the tint is applied again on top of it.
+        let [r, g, b] = tint_map.map_or([1.0; 3], |tint_map| {
+            tint_map.tint_at(positions[i as usize].into(), voxel_dims, center)
+        });
+        colors[ver as usize] = [color * r, color * g, color * b, 1.0]
+    }
+}
+
+/// Remove the Smooth Lighting from a mesh, by resetting the color of all of its vertices (this
+/// removes the tint too, see [`remove_smooth_lighting_keep_tint`]).
+pub fn remove_smooth_lighting(mesh: &mut Mesh, color: [f32; 4]) {
+    if let Some(VertexAttributeValues::Float32x4(colors)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
+    {
+        colors.iter_mut().for_each(|c| *c = color);
+    }
+}
+
+/// Remove the Smooth Lighting from a cube mesh (like [`remove_smooth_lighting`]), and tint its
+/// tintable faces again by the [`TintMap`] in its metadata.
+pub fn remove_smooth_lighting_keep_tint<T: Copy, const N: usize>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    mesh: &mut Mesh,
+    metadata: &MeshMD<T>,
+    grid: &ChunkGrid<T, N>,
+    color: [f32; 4],
+) {
+    remove_smooth_lighting(mesh, color);
+    if let Some(tint_map) = metadata.get_tint_map() {
+        tint_quads_from(mesh, &metadata.vivi, reg, tint_map, 0, |voxel_index, _| {
+            grid.get_block(index_to_pos(voxel_index, grid.dims)?)
+        });
     }
 }
 
@@ -326,6 +359,11 @@ pub fn apply_smooth_lighting_with_connected_chunks<'a, T: Copy, const N: usize>(
                         }
                     }
                 }
+                let tint_map = metadata.tint_map.as_ref().filter(|_| {
+                    this_chunk
+                        .get_block(block_pos)
+                        .is_some_and(|voxel| reg.is_tintable(&voxel, face))
+                });
                 apply_sl_quad(
                     mesh,
                     &metadata.vivi,
@@ -333,7 +371,9 @@ pub fn apply_smooth_lighting_with_connected_chunks<'a, T: Copy, const N: usize>(
                     face,
                     surrounding_blocks,
                     sl,
+                    tint_map,
                     reg.get_voxel_dimensions(),
+                    reg.get_center(),
                     dims,
                 )
             }
@@ -0,0 +1,112 @@
+//! Tinting is used to color voxels by where they are (grass and foliage that change color from
+//! biome to biome for example). The tint of each column of the grid is given in a [`TintMap`],
+//! and the colors of the faces the [`VoxelRegistry`] says are tintable (see
+//! [`VoxelRegistry::is_tintable`]) are multiplied by it.
+use crate::prelude::*;
+use bevy::math::Vec3;
+use bevy::render::mesh::{Mesh, VertexAttributeValues};
+
+/// The tint of a grid, sampled at the corners of its columns (a grid `x` wide and `z` long has
+/// `(x + 1) * (z + 1)` corners). Each vertex is tinted by interpolating the corners around it, so
+/// two grids next to each other that sample their shared corners from the same source are tinted
+/// seamlessly across the border.
+#[derive(Clone, Debug)]
+pub struct TintMap {
+    width: usize,
+    length: usize,
+    corners: Vec<[f32; 3]>,
+}
+
+impl TintMap {
+    /// Build the tint map of a grid with dimensions `dims`. `tint_at_corner` returns the tint
+    /// (linear RGB) of the corner at (x, z), from (0, 0) to (dims.x, dims.z).
+    pub fn new(dims: Dimensions, tint_at_corner: impl Fn(u32, u32) -> [f32; 3]) -> Self {
+        let mut corners = Vec::with_capacity(((dims.x + 1) * (dims.z + 1)) as usize);
+        for z in 0..=dims.z {
+            for x in 0..=dims.x {
+                corners.push(tint_at_corner(x, z));
+            }
+        }
+        Self {
+            width: dims.x as usize + 1,
+            length: dims.z as usize + 1,
+            corners,
+        }
+    }
+
+    fn corner(&self, x: usize, z: usize) -> Vec3 {
+        let x = x.min(self.width - 1);
+        let z = z.min(self.length - 1);
+        Vec3::from(self.corners[x + z * self.width])
+    }
+
+    /// The tint at a position in the grid (the same space as the vertices of its mesh).
+    pub fn tint_at(&self, position: [f32; 3], voxel_dims: [f32; 3], center: [f32; 3]) -> [f32; 3] {
+        // The position in corners, the corner (0, 0) is the lowest corner of the first voxel.
+        let x = ((position[0] - center[0]) / voxel_dims[0] + 0.5).max(0.0);
+        let z = ((position[2] - center[2]) / voxel_dims[2] + 0.5).max(0.0);
+        let (x0, z0) = (x.floor() as usize, z.floor() as usize);
+        let (fx, fz) = (x.fract(), z.fract());
+        let near = self.corner(x0, z0).lerp(self.corner(x0 + 1, z0), fx);
+        let far = self
+            .corner(x0, z0 + 1)
+            .lerp(self.corner(x0 + 1, z0 + 1), fx);
+        near.lerp(far, fz).into()
+    }
+
+    /// Multiply the colors of the vertices in `vertices` by the tint at their positions.
+    pub(crate) fn tint_vertices(
+        &self,
+        mesh: &mut Mesh,
+        vertices: std::ops::Range<usize>,
+        voxel_dims: [f32; 3],
+        center: [f32; 3],
+    ) {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return;
+        };
+        let tints: Vec<[f32; 3]> = positions[vertices.clone()]
+            .iter()
+            .map(|position| self.tint_at(*position, voxel_dims, center))
+            .collect();
+        let Some(VertexAttributeValues::Float32x4(colors)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
+        else {
+            return;
+        };
+        for (color, tint) in colors[vertices].iter_mut().zip(tints) {
+            color[0] *= tint[0];
+            color[1] *= tint[1];
+            color[2] *= tint[2];
+        }
+    }
+}
+
+/// Tint the quads (of a cube mesh) from vertex `from` onwards. `voxel_of_quad` returns the voxel a
+/// quad belongs to, by the index of the voxel and the face of the quad.
+pub(crate) fn tint_quads_from<T>(
+    mesh: &mut Mesh,
+    vivi: &VIVI,
+    reg: &impl VoxelRegistry<Voxel = T>,
+    tint_map: &TintMap,
+    from: usize,
+    voxel_of_quad: impl Fn(BlockIndex, Face) -> Option<T>,
+) {
+    for quad in (from..mesh.count_vertices()).step_by(4) {
+        let Some(encoded) = vivi.map.get(&(quad as u32)) else {
+            continue;
+        };
+        let face = face_from_u32(encoded & REVERSE_OFFSET_CONST);
+        let voxel_index = (encoded & OFFSET_CONST) as BlockIndex;
+        if voxel_of_quad(voxel_index, face).is_some_and(|voxel| reg.is_tintable(&voxel, face)) {
+            tint_map.tint_vertices(
+                mesh,
+                quad..quad + 4,
+                reg.get_voxel_dimensions(),
+                reg.get_center(),
+            );
+        }
+    }
+}
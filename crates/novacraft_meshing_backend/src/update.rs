@@ -3,7 +3,7 @@ use crate::prelude::*;
 use bevy::render::mesh::{Indices, VertexAttributeValues};
 
 /// The function updates the mesh according to the change log in the mesh meta data.
-pub fn update_mesh<T: std::fmt::Debug>(
+pub fn update_mesh<T: std::fmt::Debug + Copy>(
     mesh: &mut Mesh,
     metadata: &mut MeshMD<T>,
     reg: &impl VoxelRegistry<Voxel = T>,
@@ -77,8 +77,9 @@ pub fn update_mesh<T: std::fmt::Debug>(
 
         match *change {
             VoxelChange::Added => {
+                remove_voxel(mesh, &mut metadata.vivi, block_index, [true; 6]);
                 if let VoxelMesh::NormalCube(voxel_mesh) = reg.get_mesh(voxel) {
-                    remove_voxel(mesh, &mut metadata.vivi, block_index, [true; 6]);
+                    let from = mesh.count_vertices();
                     add_voxel_after_gen(
                         neig,
                         mesh,
@@ -88,17 +89,24 @@ pub fn update_mesh<T: std::fmt::Debug>(
                         reg.get_center(),
                         position_offset,
                     );
-                    remove_quads_facing(
-                        mesh,
-                        &mut metadata.vivi,
-                        block_index,
-                        metadata.dims,
-                        covering,
-                    );
+                    if let Some(ref tint_map) = metadata.tint_map {
+                        tint_quads_from(mesh, &metadata.vivi, reg, tint_map, from, |_, _| {
+                            Some(*voxel)
+                        });
+                    }
                 }
+                // Voxels that aren't cubes (partial voxels) can cover some of their sides too.
+                remove_quads_facing(
+                    mesh,
+                    &mut metadata.vivi,
+                    block_index,
+                    metadata.dims,
+                    covering,
+                );
             }
             VoxelChange::Broken => {
                 remove_voxel(mesh, &mut metadata.vivi, block_index, [true; 6]);
+                let from = mesh.count_vertices();
                 add_quads_facing(
                     mesh,
                     &mut metadata.vivi,
@@ -108,6 +116,12 @@ pub fn update_mesh<T: std::fmt::Debug>(
                     reg.get_voxel_dimensions(),
                     metadata.dims,
                 );
+                // The quads that were added belong to the neighbors, and face the broken voxel.
+                if let Some(ref tint_map) = metadata.tint_map {
+                    tint_quads_from(mesh, &metadata.vivi, reg, tint_map, from, |_, face| {
+                        neighbors[face.opposite() as usize]
+                    });
+                }
             }
             VoxelChange::CullFaces => {
                 remove_voxel(
@@ -124,6 +138,7 @@ pub fn update_mesh<T: std::fmt::Debug>(
             }
             VoxelChange::AddFaces => {
                 if let VoxelMesh::NormalCube(voxel_mesh) = reg.get_mesh(voxel) {
+                    let from = mesh.count_vertices();
                     add_voxel_after_gen(
                         neig,
                         mesh,
@@ -133,6 +148,11 @@ pub fn update_mesh<T: std::fmt::Debug>(
                         reg.get_center(),
                         position_offset,
                     );
+                    if let Some(ref tint_map) = metadata.tint_map {
+                        tint_quads_from(mesh, &metadata.vivi, reg, tint_map, from, |_, _| {
+                            Some(*voxel)
+                        });
+                    }
                 }
             }
         }
@@ -171,7 +191,7 @@ fn remove_quads_facing(
         };
         quad_to_remove = [false; 6];
         quad_to_remove[face.opposite() as usize] = true;
-        if covering[face.opposite() as usize] {
+        if covering[face as usize] {
             remove_voxel(mesh, vivi, n, quad_to_remove);
         }
     }
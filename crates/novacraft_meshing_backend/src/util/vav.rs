@@ -3,6 +3,7 @@
 //! readabillity of main API.
 #![allow(unused_variables)]
 use bevy::render::{mesh::VertexAttributeValues, render_resource::VertexFormat};
+use std::ops::Range;
 pub trait VAVutils {
     fn extend(&mut self, t: &Self);
     fn filter_bool_array(&self, index_filter: Vec<bool>) -> Self;
@@ -13,6 +14,8 @@ pub trait VAVutils {
     fn get_needed(&self, needed_values: &Vec<u32>) -> VertexAttributeValues;
     fn swap_remove(&mut self, index: usize);
     fn remove(&mut self, index: usize);
+    // removes the values in the range (the ones that exist) in one pass.
+    fn remove_range(&mut self, range: Range<usize>);
 }
 
 impl VAVutils for VertexAttributeValues {
@@ -174,6 +177,25 @@ impl VAVutils for VertexAttributeValues {
         }
     }
 
+    fn remove_range(&mut self, range: Range<usize>) {
+        macro_rules! remove_range {
+            ($($variant:ident),*) => {
+                match self {
+                    $(VertexAttributeValues::$variant(ref mut vals) => {
+                        let end = range.end.min(vals.len());
+                        vals.drain(range.start.min(end)..end);
+                    })*
+                }
+            };
+        }
+        remove_range!(
+            Float32, Sint32, Uint32, Float32x2, Sint32x2, Uint32x2, Float32x3, Sint32x3, Uint32x3,
+            Float32x4, Sint32x4, Uint32x4, Sint16x2, Snorm16x2, Uint16x2, Unorm16x2, Sint16x4,
+            Snorm16x4, Uint16x4, Unorm16x4, Sint8x2, Snorm8x2, Uint8x2, Unorm8x2, Sint8x4,
+            Snorm8x4, Uint8x4, Unorm8x4
+        );
+    }
+
     fn remove(&mut self, index: usize) {
         match self {
             VertexAttributeValues::Float32(ref mut vals) => {
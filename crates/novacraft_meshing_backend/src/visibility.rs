@@ -0,0 +1,160 @@
+//! The visibility graph of a grid tells which of its sides can see each other through it, it's used
+//! to cull grids that are hidden behind other grids (occlusion culling). Two sides can see each
+//! other if there is a path between them through voxels that don't cover all of their sides (air,
+//! sprites, glass, slabs, etc.).
+use crate::prelude::*;
+
+/// Which sides of a grid can see each other through it, indexed like [`FACES`].
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct VisibilityGraph(u64);
+
+impl VisibilityGraph {
+    /// A graph where all of the sides see each other (an empty grid, for example).
+    pub const ALL: VisibilityGraph = VisibilityGraph((1 << 36) - 1);
+    /// A graph where none of the sides see each other (a solid grid, for example).
+    pub const NONE: VisibilityGraph = VisibilityGraph(0);
+
+    fn bit(a: Face, b: Face) -> u64 {
+        1 << (a as usize * 6 + b as usize)
+    }
+
+    /// Mark that the two sides can see each other.
+    pub fn connect(&mut self, a: Face, b: Face) {
+        self.0 |= Self::bit(a, b) | Self::bit(b, a);
+    }
+
+    /// Can the two sides see each other?
+    pub fn connects(&self, a: Face, b: Face) -> bool {
+        self.0 & Self::bit(a, b) != 0
+    }
+}
+
+/// Compute the visibility graph of the grid. Each group of connected voxels that can be seen
+/// through is flood filled, and the sides it touches can all see each other.
+pub fn compute_visibility_graph<T: Copy, const N: usize>(
+    reg: &impl VoxelRegistry<Voxel = T>,
+    grid: &ChunkGrid<T, N>,
+) -> VisibilityGraph {
+    let dims = grid.dims;
+    let mut graph = VisibilityGraph::NONE;
+    // The voxels that are opaque, or have been visited.
+    let mut visited: Vec<bool> = grid
+        .enumerate_blocks()
+        .map(|(_, voxel)| FACES.iter().all(|face| reg.is_covering(&voxel, *face)))
+        .collect();
+    let mut stack = vec![];
+    for start in 0..visited.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(index_to_pos(start, dims).unwrap());
+        let mut sides = [false; 6];
+        while let Some(voxel_pos) = stack.pop() {
+            for face in FACES {
+                if is_on_side(voxel_pos, face, dims) {
+                    sides[face as usize] = true;
+                    continue;
+                }
+                let neighbor_pos = neighbor_pos(voxel_pos, face, dims).unwrap();
+                let neighbor_index = pos_to_index(neighbor_pos, dims).unwrap();
+                if !visited[neighbor_index] {
+                    visited[neighbor_index] = true;
+                    stack.push(neighbor_pos);
+                }
+            }
+        }
+        for a in FACES.into_iter().filter(|a| sides[*a as usize]) {
+            for b in FACES.into_iter().filter(|b| sides[*b as usize]) {
+                graph.connect(a, b);
+            }
+        }
+    }
+    graph
+}
+
+// Checked before moving to the neighbor, so the position doesn't underflow on the lower sides.
+fn is_on_side(voxel_pos: BlockPos, face: Face, dims: Dimensions) -> bool {
+    match face {
+        Face::Top => voxel_pos.y + 1 == dims.y,
+        Face::Bottom => voxel_pos.y == 0,
+        Face::Right => voxel_pos.x + 1 == dims.x,
+        Face::Left => voxel_pos.x == 0,
+        Face::Back => voxel_pos.z + 1 == dims.z,
+        Face::Forward => voxel_pos.z == 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::*;
+    use bevy::render::mesh::MeshVertexAttribute;
+
+    const DIMS: UVec3 = UVec3::new(4, 4, 4);
+    const N: usize = 4 * 4 * 4;
+    const AIR: u8 = 0;
+    const STONE: u8 = 1;
+
+    /// Only the visibility of the voxels matters, they don't have meshes.
+    struct TestRegistry;
+
+    impl VoxelRegistry for TestRegistry {
+        type Voxel = u8;
+
+        fn get_mesh(&self, _voxel: &u8) -> VoxelMesh<&Mesh> {
+            VoxelMesh::Null
+        }
+
+        fn is_covering(&self, voxel: &u8, _side: Face) -> bool {
+            *voxel == STONE
+        }
+
+        fn get_center(&self) -> [f32; 3] {
+            [0.0; 3]
+        }
+
+        fn get_voxel_dimensions(&self) -> [f32; 3] {
+            [1.0; 3]
+        }
+
+        fn all_attributes(&self) -> Vec<MeshVertexAttribute> {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn empty_grid_sees_everything() {
+        let grid = ChunkGrid::new([AIR; N], DIMS);
+        assert_eq!(
+            compute_visibility_graph(&TestRegistry, &grid),
+            VisibilityGraph::ALL
+        );
+    }
+
+    #[test]
+    fn solid_grid_sees_nothing() {
+        let grid = ChunkGrid::new([STONE; N], DIMS);
+        assert_eq!(
+            compute_visibility_graph(&TestRegistry, &grid),
+            VisibilityGraph::NONE
+        );
+    }
+
+    #[test]
+    fn horizontal_wall_separates_top_from_bottom() {
+        let mut grid = ChunkGrid::new([AIR; N], DIMS);
+        for x in 0..DIMS.x {
+            for z in 0..DIMS.z {
+                grid.set_block(STONE, UVec3::new(x, 1, z)).unwrap();
+            }
+        }
+        let graph = compute_visibility_graph(&TestRegistry, &grid);
+        assert!(!graph.connects(Face::Top, Face::Bottom));
+        assert!(!graph.connects(Face::Bottom, Face::Top));
+        // Both sides of the wall still see the horizontal sides.
+        assert!(graph.connects(Face::Top, Face::Right));
+        assert!(graph.connects(Face::Bottom, Face::Left));
+        assert!(graph.connects(Face::Back, Face::Forward));
+    }
+}
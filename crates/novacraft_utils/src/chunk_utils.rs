@@ -3,6 +3,7 @@ use bevy_math::prelude::*;
 
 pub type Dimensions = UVec3;
 pub type ChunkCords = IVec2;
+#[derive(Clone)]
 pub struct ChunkGrid<T: Copy + Sized, const N: usize> {
     pub dims: Dimensions,
     grid: [T; N],
@@ -160,11 +161,11 @@ pub fn iter_blocks_on_edge(edge: Face, dims: Dimensions) -> impl Iterator<Item =
 pub fn is_block_pos_on_edge(mut block_pos: BlockPos, edge: Face, dims: Dimensions) -> bool {
     match edge {
         Face::Top => block_pos.y += 1,
-        Face::Bottom => block_pos.y -= 1,
+        Face::Bottom => block_pos.y = block_pos.y.wrapping_sub(1),
         Face::Right => block_pos.x += 1,
-        Face::Left => block_pos.x -= 1,
+        Face::Left => block_pos.x = block_pos.x.wrapping_sub(1),
         Face::Back => block_pos.z += 1,
-        Face::Forward => block_pos.z -= 1,
+        Face::Forward => block_pos.z = block_pos.z.wrapping_sub(1),
     }
     !pos_in_bounds(block_pos, dims)
 }
@@ -201,14 +202,16 @@ pub const fn pos_to_index(block_pos: UVec3, dims: Dimensions) -> Option<BlockInd
     }
 }
 
+/// The index of the neighbor of the block on the given side, [`None`] if it's out of bounds (a
+/// position below 0 wraps around, so it's out of bounds too).
 pub fn neighbor_index(mut block_pos: BlockPos, face: Face, dims: Dimensions) -> Option<BlockIndex> {
     match face {
         Face::Top => block_pos.y += 1,
-        Face::Bottom => block_pos.y -= 1,
+        Face::Bottom => block_pos.y = block_pos.y.wrapping_sub(1),
         Face::Right => block_pos.x += 1,
-        Face::Left => block_pos.x -= 1,
+        Face::Left => block_pos.x = block_pos.x.wrapping_sub(1),
         Face::Back => block_pos.z += 1,
-        Face::Forward => block_pos.z -= 1,
+        Face::Forward => block_pos.z = block_pos.z.wrapping_sub(1),
     }
     pos_to_index(block_pos, dims)
 }
@@ -216,11 +219,11 @@ pub fn neighbor_index(mut block_pos: BlockPos, face: Face, dims: Dimensions) ->
 pub fn neighbor_pos(mut block_pos: BlockPos, face: Face, dims: Dimensions) -> Option<BlockPos> {
     match face {
         Face::Top => block_pos.y += 1,
-        Face::Bottom => block_pos.y -= 1,
+        Face::Bottom => block_pos.y = block_pos.y.wrapping_sub(1),
         Face::Right => block_pos.x += 1,
-        Face::Left => block_pos.x -= 1,
+        Face::Left => block_pos.x = block_pos.x.wrapping_sub(1),
         Face::Back => block_pos.z += 1,
-        Face::Forward => block_pos.z -= 1,
+        Face::Forward => block_pos.z = block_pos.z.wrapping_sub(1),
     }
     if pos_in_bounds(block_pos, dims) {
         Some(block_pos)
@@ -284,6 +287,34 @@ pub fn global_block_pos_to_block_trans(
         )
 }
 
+/// The position of a block in the world (counted in blocks, not in units) from its global position.
+pub fn global_block_pos_to_world(global_pos: BlockGlobalPos, dims: Dimensions) -> IVec3 {
+    IVec3::new(
+        global_pos.chunk_cords.x * dims.x as i32 + global_pos.pos.x as i32,
+        global_pos.pos.y as i32,
+        global_pos.chunk_cords.y * dims.z as i32 + global_pos.pos.z as i32,
+    )
+}
+
+/// The global position of a block from its position in the world (counted in blocks). The position
+/// is not valid if it's out of the chunks' height.
+pub fn world_to_global_block_pos(world_pos: IVec3, dims: Dimensions) -> BlockGlobalPos {
+    let dims_i = dims.as_ivec3();
+    BlockGlobalPos {
+        pos: UVec3::new(
+            world_pos.x.rem_euclid(dims_i.x) as u32,
+            world_pos.y.clamp(0, dims_i.y - 1) as u32,
+            world_pos.z.rem_euclid(dims_i.z) as u32,
+        ),
+        chunk_cords: [
+            world_pos.x.div_euclid(dims_i.x),
+            world_pos.z.div_euclid(dims_i.z),
+        ]
+        .into(),
+        valid: world_pos.y >= 0 && world_pos.y < dims_i.y,
+    }
+}
+
 pub fn global_enumerate_neighboring_blocks(
     global_pos: BlockGlobalPos,
     dims: Dimensions,
@@ -0,0 +1,196 @@
+use crate::{chunk_utils::*, voxel_box::*};
+use bevy_math::prelude::*;
+
+/// The boxes the voxels of a grid collide with, relative to the grid (voxels are centered around
+/// their position in the grid). `shape_of` returns the boxes a voxel collides with, relative to its
+/// center. Adjacent voxels that are whole ([`VoxelBox::FULL`]) are merged into as few boxes as
+/// possible, first along the x axis, then the z axis, then the y axis, so large flat areas don't
+/// have internal edges to snag on.
+pub fn merge_collision_boxes<T: Copy + Sized, const N: usize>(
+    grid: &ChunkGrid<T, N>,
+    shape_of: impl Fn(T) -> &'static [VoxelBox],
+) -> Vec<VoxelBox> {
+    let dims = grid.dims;
+    let index = |x: u32, y: u32, z: u32| pos_to_index(UVec3::new(x, y, z), dims).unwrap();
+    let mut boxes = vec![];
+    // Whether the voxel is whole, and wasn't merged into a box yet.
+    let mut mergeable = vec![false; grid.len()];
+    for (pos, voxel) in grid.enumerate_blocks() {
+        let shape = shape_of(voxel);
+        if shape == [VoxelBox::FULL] {
+            mergeable[index(pos.x, pos.y, pos.z)] = true;
+        } else {
+            boxes.extend(shape.iter().map(|b| b.translated(pos.as_vec3())));
+        }
+    }
+    for y in 0..dims.y {
+        for z in 0..dims.z {
+            for x in 0..dims.x {
+                if !mergeable[index(x, y, z)] {
+                    continue;
+                }
+                let mut end = UVec3::new(x, y, z);
+                while end.x + 1 < dims.x && mergeable[index(end.x + 1, y, z)] {
+                    end.x += 1;
+                }
+                while end.z + 1 < dims.z && (x..=end.x).all(|x| mergeable[index(x, y, end.z + 1)]) {
+                    end.z += 1;
+                }
+                while end.y + 1 < dims.y
+                    && (z..=end.z).all(|z| (x..=end.x).all(|x| mergeable[index(x, end.y + 1, z)]))
+                {
+                    end.y += 1;
+                }
+                for y in y..=end.y {
+                    for z in z..=end.z {
+                        for x in x..=end.x {
+                            mergeable[index(x, y, z)] = false;
+                        }
+                    }
+                }
+                boxes.push(VoxelBox::new(
+                    UVec3::new(x, y, z).as_vec3() + VoxelBox::FULL.min,
+                    end.as_vec3() + VoxelBox::FULL.max,
+                ));
+            }
+        }
+    }
+    boxes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIMS: UVec3 = UVec3::new(4, 3, 4);
+    const N: usize = 4 * 3 * 4;
+    const AIR: u8 = 0;
+    const FULL: u8 = 1;
+    const SLAB: u8 = 2;
+
+    fn shape_of(voxel: u8) -> &'static [VoxelBox] {
+        const FULL_SHAPE: [VoxelBox; 1] = [VoxelBox::FULL];
+        const SLAB_SHAPE: [VoxelBox; 1] =
+            [VoxelBox::new(Vec3::splat(-0.5), Vec3::new(0.5, 0.0, 0.5))];
+        match voxel {
+            FULL => &FULL_SHAPE,
+            SLAB => &SLAB_SHAPE,
+            _ => &[],
+        }
+    }
+
+    fn grid_with(voxels: &[(UVec3, u8)]) -> ChunkGrid<u8, N> {
+        let mut grid = ChunkGrid::new([AIR; N], DIMS);
+        for (pos, voxel) in voxels {
+            grid.set_block(*voxel, *pos).unwrap();
+        }
+        grid
+    }
+
+    fn contains(b: &VoxelBox, point: Vec3) -> bool {
+        point.cmpgt(b.min).all() && point.cmplt(b.max).all()
+    }
+
+    /// Every full voxel is covered by exactly one box, and no box covers anything else.
+    fn assert_covers_exactly(grid: &ChunkGrid<u8, N>, boxes: &[VoxelBox]) {
+        for (pos, voxel) in grid.enumerate_blocks() {
+            let covering = boxes.iter().filter(|b| contains(b, pos.as_vec3())).count();
+            let expected = if voxel == FULL { 1 } else { 0 };
+            assert_eq!(
+                covering, expected,
+                "voxel {:?} is covered {} times",
+                pos, covering
+            );
+        }
+    }
+
+    #[test]
+    fn empty_grid_has_no_boxes() {
+        assert!(merge_collision_boxes(&grid_with(&[]), shape_of).is_empty());
+    }
+
+    #[test]
+    fn full_grid_is_one_box() {
+        let grid = ChunkGrid::new([FULL; N], DIMS);
+        let boxes = merge_collision_boxes(&grid, shape_of);
+        assert_eq!(
+            boxes,
+            [VoxelBox::new(Vec3::splat(-0.5), DIMS.as_vec3() - 0.5)]
+        );
+    }
+
+    #[test]
+    fn touching_edges_are_not_merged() {
+        // The voxels only share an edge, a box around both would cover the air next to them.
+        let grid = grid_with(&[(UVec3::new(0, 0, 0), FULL), (UVec3::new(1, 0, 1), FULL)]);
+        let boxes = merge_collision_boxes(&grid, shape_of);
+        assert_eq!(boxes.len(), 2);
+        assert_covers_exactly(&grid, &boxes);
+    }
+
+    #[test]
+    fn l_shape_is_two_boxes() {
+        let grid = grid_with(&[
+            (UVec3::new(0, 0, 0), FULL),
+            (UVec3::new(1, 0, 0), FULL),
+            (UVec3::new(2, 0, 0), FULL),
+            (UVec3::new(0, 0, 1), FULL),
+        ]);
+        let boxes = merge_collision_boxes(&grid, shape_of);
+        assert_eq!(
+            boxes,
+            [
+                VoxelBox::new(Vec3::splat(-0.5), Vec3::new(2.5, 0.5, 0.5)),
+                VoxelBox::new(Vec3::new(-0.5, -0.5, 0.5), Vec3::new(0.5, 0.5, 1.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_merge_vertically() {
+        let grid = grid_with(&[
+            (UVec3::new(3, 0, 3), FULL),
+            (UVec3::new(3, 1, 3), FULL),
+            (UVec3::new(3, 2, 3), FULL),
+        ]);
+        let boxes = merge_collision_boxes(&grid, shape_of);
+        assert_eq!(
+            boxes,
+            [VoxelBox::new(
+                Vec3::new(2.5, -0.5, 2.5),
+                Vec3::new(3.5, 2.5, 3.5)
+            )]
+        );
+    }
+
+    #[test]
+    fn partial_shapes_are_kept_as_is() {
+        let grid = grid_with(&[(UVec3::new(1, 0, 0), FULL), (UVec3::new(2, 0, 0), SLAB)]);
+        let boxes = merge_collision_boxes(&grid, shape_of);
+        assert_eq!(boxes.len(), 2);
+        assert!(boxes.contains(&VoxelBox::new(
+            Vec3::new(1.5, -0.5, -0.5),
+            Vec3::new(2.5, 0.0, 0.5)
+        )));
+        assert!(boxes.contains(&VoxelBox::new(
+            Vec3::new(0.5, -0.5, -0.5),
+            Vec3::new(1.5, 0.5, 0.5)
+        )));
+    }
+
+    #[test]
+    fn scattered_voxels_are_covered_exactly_once() {
+        let mut grid = grid_with(&[]);
+        for y in 0..DIMS.y {
+            for z in 0..DIMS.z {
+                for x in 0..DIMS.x {
+                    if (x * 7 + y * 3 + z * 5) % 4 != 0 {
+                        grid.set_block(FULL, UVec3::new(x, y, z)).unwrap();
+                    }
+                }
+            }
+        }
+        let boxes = merge_collision_boxes(&grid, shape_of);
+        assert_covers_exactly(&grid, &boxes);
+    }
+}
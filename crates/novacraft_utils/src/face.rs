@@ -1,3 +1,5 @@
+use bevy_math::IVec3;
+
 #[derive(Copy, Clone, Debug)]
 /// This enum represents all the faces of a cubic voxel.
 pub enum Face {
@@ -69,6 +71,18 @@ impl Face {
             Face::Forward => Face::Back,
         }
     }
+
+    /// The unit offset from a voxel to its neighbor on this `Face`.
+    pub fn offset(&self) -> IVec3 {
+        match *self {
+            Face::Top => IVec3::Y,
+            Face::Bottom => IVec3::NEG_Y,
+            Face::Right => IVec3::X,
+            Face::Left => IVec3::NEG_X,
+            Face::Back => IVec3::Z,
+            Face::Forward => IVec3::NEG_Z,
+        }
+    }
 }
 
 impl Into<usize> for Face {
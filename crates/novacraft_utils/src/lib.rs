@@ -1,5 +1,11 @@
 mod chunk_utils;
+mod collision;
 mod face;
+mod raycast;
+mod voxel_box;
 
 pub use chunk_utils::*;
+pub use collision::*;
 pub use face::*;
+pub use raycast::*;
+pub use voxel_box::*;
@@ -0,0 +1,183 @@
+use crate::{face::*, voxel_box::*};
+use bevy_math::prelude::*;
+
+/// A voxel hit by [`raycast_voxels`].
+#[derive(Clone, Copy, Debug)]
+pub struct VoxelHit {
+    /// The position of the voxel.
+    pub pos: IVec3,
+    /// The face the ray entered through. `None` if the ray started inside the voxel's shape.
+    pub face: Option<Face>,
+    /// The distance from the origin of the ray to the hit.
+    pub distance: f32,
+}
+
+/// Walk the voxels along a ray, in order (the DDA traversal of Amanatides & Woo), and return the
+/// first one whose [`VoxelBox`] the ray hits within `max_distance`. Voxels are of size 1 and
+/// centered around their position. `shape_at` returns the shape of the voxel at a position, or
+/// `None` if the ray should pass through it.
+pub fn raycast_voxels(
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+    mut shape_at: impl FnMut(IVec3) -> Option<VoxelBox>,
+) -> Option<VoxelHit> {
+    let direction = direction.try_normalize()?;
+    // Move the origin so the voxels span from their position to their position + 1.
+    let start = origin + Vec3::splat(0.5);
+    let mut pos = start.floor().as_ivec3();
+    let step = IVec3::new(
+        if direction.x > 0.0 { 1 } else { -1 },
+        if direction.y > 0.0 { 1 } else { -1 },
+        if direction.z > 0.0 { 1 } else { -1 },
+    );
+    // The distance along the ray to cross a whole voxel on each axis.
+    let t_delta = direction.recip().abs();
+    // The distance along the ray to the next voxel border on each axis.
+    let mut t_max = Vec3::ZERO;
+    for axis in 0..3 {
+        t_max[axis] = if direction[axis] > 0.0 {
+            (pos[axis] as f32 + 1.0 - start[axis]) / direction[axis]
+        } else if direction[axis] < 0.0 {
+            (start[axis] - pos[axis] as f32) / -direction[axis]
+        } else {
+            f32::INFINITY
+        };
+    }
+    loop {
+        if let Some(shape) = shape_at(pos) {
+            if let Some((distance, face)) = shape.intersect_ray(pos.as_vec3(), origin, direction) {
+                if distance <= max_distance {
+                    return Some(VoxelHit {
+                        pos,
+                        face,
+                        distance,
+                    });
+                }
+            }
+        }
+        let axis = if t_max.x < t_max.y && t_max.x < t_max.z {
+            0
+        } else if t_max.y < t_max.z {
+            1
+        } else {
+            2
+        };
+        if t_max[axis] > max_distance {
+            return None;
+        }
+        pos[axis] += step[axis];
+        t_max[axis] += t_delta[axis];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLAB: VoxelBox = VoxelBox::new(Vec3::splat(-0.5), Vec3::new(0.5, 0.0, 0.5));
+
+    /// A world with full voxels at the given positions.
+    fn solid_at(solid: &[IVec3]) -> impl FnMut(IVec3) -> Option<VoxelBox> + '_ {
+        |pos| solid.contains(&pos).then_some(VoxelBox::FULL)
+    }
+
+    #[test]
+    fn hits_the_first_voxel_at_negative_cords() {
+        let solid = [IVec3::new(-1, 0, -4), IVec3::new(1, 0, -4)];
+        let hit =
+            raycast_voxels(Vec3::new(-3.2, 0.1, -4.0), Vec3::X, 10.0, solid_at(&solid)).unwrap();
+        assert_eq!(hit.pos, IVec3::new(-1, 0, -4));
+        assert!(matches!(hit.face, Some(Face::Left)));
+        assert!((hit.distance - 1.7).abs() < 1e-5);
+    }
+
+    #[test]
+    fn hits_the_top_face_going_down() {
+        let solid = [IVec3::new(2, -3, 5)];
+        let hit = raycast_voxels(
+            Vec3::new(2.3, 1.0, 4.8),
+            Vec3::NEG_Y,
+            10.0,
+            solid_at(&solid),
+        )
+        .unwrap();
+        assert_eq!(hit.pos, IVec3::new(2, -3, 5));
+        assert!(matches!(hit.face, Some(Face::Top)));
+        assert!((hit.distance - 3.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn stops_at_the_max_distance() {
+        let solid = [IVec3::new(5, 0, 0)];
+        assert!(raycast_voxels(Vec3::ZERO, Vec3::X, 4.0, solid_at(&solid)).is_none());
+        assert!(raycast_voxels(Vec3::ZERO, Vec3::X, 4.5, solid_at(&solid)).is_some());
+    }
+
+    #[test]
+    fn starting_inside_a_voxel_has_no_face() {
+        let solid = [IVec3::ZERO];
+        let hit = raycast_voxels(Vec3::splat(0.2), Vec3::X, 5.0, solid_at(&solid)).unwrap();
+        assert_eq!(hit.pos, IVec3::ZERO);
+        assert!(hit.face.is_none());
+        assert_eq!(hit.distance, 0.0);
+    }
+
+    #[test]
+    fn starting_on_a_boundary() {
+        // Between the voxels at x = 0 and x = 1, facing the voxel at x = 1.
+        let solid = [IVec3::ZERO, IVec3::new(1, 0, 0)];
+        let hit = raycast_voxels(Vec3::new(0.5, 0.0, 0.0), Vec3::X, 5.0, solid_at(&solid)).unwrap();
+        assert_eq!(hit.pos, IVec3::new(1, 0, 0));
+        assert!(matches!(hit.face, Some(Face::Left)));
+        assert_eq!(hit.distance, 0.0);
+
+        // Facing away from the voxel at x = 1, into the voxel at x = 0.
+        let hit =
+            raycast_voxels(Vec3::new(0.5, 0.0, 0.0), Vec3::NEG_X, 5.0, solid_at(&solid)).unwrap();
+        assert_eq!(hit.pos, IVec3::ZERO);
+        assert!(matches!(hit.face, Some(Face::Right)));
+
+        // Facing away from the voxel at x = 1, with nothing behind.
+        let solid = [IVec3::new(1, 0, 0)];
+        assert!(
+            raycast_voxels(Vec3::new(0.5, 0.0, 0.0), Vec3::NEG_X, 5.0, solid_at(&solid)).is_none()
+        );
+    }
+
+    #[test]
+    fn passes_over_partial_shapes() {
+        let shape_at = |pos: IVec3| match pos.x {
+            1 => Some(SLAB),
+            3 => Some(VoxelBox::FULL),
+            _ => None,
+        };
+        // Above the slab.
+        let hit = raycast_voxels(Vec3::new(0.0, 0.25, 0.0), Vec3::X, 10.0, shape_at).unwrap();
+        assert_eq!(hit.pos, IVec3::new(3, 0, 0));
+        // Into the side of the slab.
+        let hit = raycast_voxels(Vec3::new(0.0, -0.25, 0.0), Vec3::X, 10.0, shape_at).unwrap();
+        assert_eq!(hit.pos, IVec3::new(1, 0, 0));
+        assert!(matches!(hit.face, Some(Face::Left)));
+    }
+
+    #[test]
+    fn diagonal_rays_visit_every_voxel_on_the_way() {
+        let mut visited = vec![];
+        raycast_voxels(Vec3::ZERO, Vec3::new(1.0, 0.0, 0.3), 3.0, |pos| {
+            visited.push(pos);
+            None
+        });
+        for pair in visited.windows(2) {
+            // Each step moves to an adjacent voxel.
+            assert_eq!((pair[1] - pair[0]).abs().dot(IVec3::ONE), 1);
+        }
+        assert_eq!(visited.first(), Some(&IVec3::ZERO));
+        assert!(visited.contains(&IVec3::new(2, 0, 1)));
+    }
+
+    #[test]
+    fn zero_direction_hits_nothing() {
+        assert!(raycast_voxels(Vec3::ZERO, Vec3::ZERO, 5.0, |_| Some(VoxelBox::FULL)).is_none());
+    }
+}
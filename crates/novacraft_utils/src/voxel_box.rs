@@ -0,0 +1,83 @@
+use crate::face::*;
+use bevy_math::prelude::*;
+
+/// An axis-aligned box, relative to the center of a voxel. Blocks are selected by (and collide
+/// with) boxes. A whole voxel (of size 1) is [`VoxelBox::FULL`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VoxelBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl VoxelBox {
+    pub const FULL: VoxelBox = VoxelBox::new(Vec3::splat(-0.5), Vec3::splat(0.5));
+
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// The box, moved by `offset`.
+    pub fn translated(&self, offset: Vec3) -> Self {
+        Self::new(self.min + offset, self.max + offset)
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// The distance along the ray to where it enters the shape of the voxel at `voxel_pos`, and the
+    /// face it enters through (`None` if the origin of the ray is inside the shape).
+    /// `direction` must be normalized.
+    pub fn intersect_ray(
+        &self,
+        voxel_pos: Vec3,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Option<(f32, Option<Face>)> {
+        let min = voxel_pos + self.min;
+        let max = voxel_pos + self.max;
+        let mut t_enter = f32::NEG_INFINITY;
+        let mut t_exit = f32::INFINITY;
+        let mut face = None;
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (min[axis] - origin[axis]) / direction[axis];
+            let t2 = (max[axis] - origin[axis]) / direction[axis];
+            let (near, far) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+            if near > t_enter {
+                t_enter = near;
+                face = Some(entry_face(axis, direction[axis] > 0.0));
+            }
+            t_exit = t_exit.min(far);
+        }
+        // A ray that starts on the surface of the shape and leaves it doesn't hit it.
+        if t_enter > t_exit || t_exit <= 0.0 {
+            return None;
+        }
+        if t_enter < 0.0 {
+            return Some((0.0, None));
+        }
+        Some((t_enter, face))
+    }
+}
+
+/// The face a ray enters a voxel through, when it moves along the axis (0: x, 1: y, 2: z).
+fn entry_face(axis: usize, positive_direction: bool) -> Face {
+    match (axis, positive_direction) {
+        (0, true) => Face::Left,
+        (0, false) => Face::Right,
+        (1, true) => Face::Bottom,
+        (1, false) => Face::Top,
+        (_, true) => Face::Forward,
+        (_, false) => Face::Back,
+    }
+}
@@ -97,9 +97,8 @@ fn sort_actions(
         if matches!(prime_action.action_type, ActionType::Start)
             && target_block.ignore_flag == false
         {
-            break_block_global_sender.send(BreakBlockGlobalEvent::from_entity_and_pos(
-                target_block.block_pos,
-                target_block.target_entity,
+            break_block_global_sender.send(BreakBlockGlobalEvent::from_global_pos(
+                BlockGlobalPos::new(target_block.block_pos, target_block.chunk_cords),
             ));
         }
     }
@@ -1,8 +1,13 @@
 // REFACTORED
 
-use crate::chunk::{ChunkCords, ChunkMap, ToApplySL, CHUNK_DIMS, CHUNK_TOTAL_BLOCKS_USIZE};
+use crate::chunk::{
+    ChunkCords, ParentChunk, PartialChild, ToApplySL, TranslucentChild, VoxelWorld, CHUNK_DIMS,
+    CHUNK_TOTAL_BLOCKS_USIZE,
+};
 use crate::prelude::notical;
 
+use super::meshreg::MeshRegistry;
+
 use super::*;
 
 // Helper function (not system) that inserts component `ToApplySL` to adj chunks
@@ -10,24 +15,21 @@ pub(super) fn apply_smooth_lighting_util(
     commands: &mut Commands,
     block_pos: BlockPos,
     cords: ChunkCords,
-    chunk_map: &ChunkMap,
+    voxel_world: &VoxelWorld,
     num_of_events_per_frame: usize,
 ) {
     for face in FACES {
         if is_block_pos_on_edge(block_pos, face, CHUNK_DIMS) {
             let change = to_cords(Some(notical::Direction::from(face)));
             let new_cords = [cords[0] + change[0], cords[1] + change[1]];
-            if let Some(adj_chunk_entity) = chunk_map.pos_to_ent.get(&ChunkCords::from(new_cords)) {
-                if *adj_chunk_entity == Entity::PLACEHOLDER {
-                    continue;
-                }
+            if let Some(adj_chunk_entity) = voxel_world.chunk_entity(ChunkCords::from(new_cords)) {
                 if num_of_events_per_frame == 1 {
-                    commands.entity(*adj_chunk_entity).insert(ToApplySL(
+                    commands.entity(adj_chunk_entity).insert(ToApplySL(
                         block_pos.wrapping_sub(UVec3::Y * 2),
                         block_pos.wrapping_add(UVec3::Y * 2),
                     ));
                 } else {
-                    commands.entity(*adj_chunk_entity).insert(ToApplySL(
+                    commands.entity(adj_chunk_entity).insert(ToApplySL(
                         index_to_pos(0, CHUNK_DIMS).unwrap(),
                         index_to_pos(CHUNK_TOTAL_BLOCKS_USIZE, CHUNK_DIMS).unwrap(),
                     ));
@@ -56,3 +58,45 @@ pub fn send_world_updates_surrounding_blocks(
         block_update: Some(block_update),
     });
 }
+
+/// The subchunks of the chunks next to a block that have to be rebuilt when the block changes.
+/// Partial and translucent blocks are culled by the blocks next to them, and the ones in other
+/// chunks aren't
+/// updated by the chunk's own update. Returns the subchunk, and the position (in its chunk) and the
+/// block of the neighbor.
+pub(super) fn neighboring_culled_subchunks(
+    world_pos: IVec3,
+    chunk_cords: ChunkCords,
+    voxel_world: &VoxelWorld,
+    mreg: &MeshRegistry,
+    culled_children: &Query<(Option<&PartialChild>, Option<&TranslucentChild>), With<ParentChunk>>,
+) -> Vec<(Entity, BlockPos, Block)> {
+    let mut subchunks = vec![];
+    for (_, neighbor_world_pos, neighbor_block) in voxel_world.neighbors(world_pos) {
+        let neighbor = VoxelWorld::to_global(neighbor_world_pos);
+        // Neighbors in the same chunk are handled by the chunk's own update.
+        if neighbor.chunk_cords == chunk_cords {
+            continue;
+        }
+        let (Some(neighbor_block), Some(neighbor_chunk)) = (
+            neighbor_block,
+            voxel_world.chunk_entity(neighbor.chunk_cords),
+        ) else {
+            continue;
+        };
+        let Ok((partial_child, translucent_child)) = culled_children.get(neighbor_chunk) else {
+            continue;
+        };
+        let subchunk = match mreg.get_mesh(&neighbor_block) {
+            VoxelMesh::Partial(_) => partial_child.map(|PartialChild(subchunk)| *subchunk),
+            VoxelMesh::Translucent(_) => {
+                translucent_child.map(|TranslucentChild(subchunk)| *subchunk)
+            }
+            _ => None,
+        };
+        if let Some(subchunk) = subchunk {
+            subchunks.push((subchunk, neighbor.pos, neighbor_block));
+        }
+    }
+    subchunks
+}
@@ -1,9 +1,11 @@
 // REFACTORED
 
+use super::meshreg::MeshRegistry;
 use super::*;
 use crate::chunk::{
     chunkmd::{MetaData, SubChunkMD},
-    ChunkMap, Cords, CubeChild, CubeSubChunk, Grid, ParentChunk, ToUpdate, CHUNK_DIMS,
+    Cords, CubeChild, CubeSubChunk, ParentChunk, PartialChild, ToUpdate, TranslucentChild,
+    VoxelWorld, CHUNK_DIMS,
 };
 
 /// The final event in the block-breaking pipeline. The modular design of the pipeline
@@ -62,9 +64,12 @@ pub fn global_block_breaker(
     mut global_block_break_events: EventReader<BreakBlockGlobalEvent>,
     mut world_block_update_sender: EventWriter<WorldBlockUpdate>,
     mut commands: Commands,
-    chunk_map: Res<ChunkMap>,
-    parent_chunks: Query<(&Grid, &Cords, &Children, &CubeChild), With<ParentChunk>>,
+    voxel_world: VoxelWorld,
+    // Chunks that aren't at full detail don't have a cube subchunk (see `LodLevel`).
+    parent_chunks: Query<(&Cords, &Children, Option<&CubeChild>), With<ParentChunk>>,
     chunk_metadata: Query<(&SubChunkMD, &Parent, Has<CubeSubChunk>)>,
+    culled_children: Query<(Option<&PartialChild>, Option<&TranslucentChild>), With<ParentChunk>>,
+    mreg: Res<MeshRegistry>,
 ) {
     let len = global_block_break_events.len();
     for global_block_break in global_block_break_events.read() {
@@ -75,7 +80,7 @@ pub fn global_block_breaker(
         } = *global_block_break;
         // Get the parent chunk using the entity or the cords.
         if let Some(parent_chunk) = chunk_entity.map_or(
-            chunk_cords.map(|cords| chunk_map.pos_to_ent.get(&cords).copied().unwrap()),
+            chunk_cords.and_then(|cords| voxel_world.chunk_entity(cords)),
             |e| {
                 chunk_metadata
                     .get(e)
@@ -83,8 +88,10 @@ pub fn global_block_breaker(
                     .map(|(_, parent, _)| parent.get())
             },
         ) {
-            let (Grid(chunk_grid), &Cords(chunk_cords), subchunks, _) =
-                parent_chunks.get(parent_chunk).unwrap();
+            let (&Cords(chunk_cords), subchunks, _) = parent_chunks.get(parent_chunk).unwrap();
+            let Some(chunk_grid) = voxel_world.grid(chunk_cords) else {
+                continue;
+            };
             let _ = chunk_grid.write().unwrap().set_block(Block::AIR, block_pos);
 
             for subchunk in subchunks {
@@ -105,38 +112,39 @@ pub fn global_block_breaker(
                         &mut commands,
                         block_pos,
                         chunk_cords,
-                        &chunk_map,
+                        &voxel_world,
                         len,
                     );
 
-                    // Add faces (uncull quads) facing the broken block from other chunks.
-                    // This is only done in cube sub-chunks, because no other sub-chunk type
-                    // requires culling & unculling.
+                    // Add faces (uncull quads) facing the broken block from other chunks. The
+                    // partial and translucent sub-chunks of the other chunks are rebuilt below.
                     if cube_chunk {
-                        for (face, neighboring_block_pos) in
-                            enumerate_neighbors_across_chunks(block_pos, CHUNK_DIMS)
+                        let world_pos =
+                            VoxelWorld::to_world(BlockGlobalPos::new(block_pos, chunk_cords));
+                        for (_, neighbor_world_pos, neighbor_block) in
+                            voxel_world.neighbors(world_pos)
                         {
-                            let new_cords = IVec2::from(to_cords(Some(Direction::from(face))))
-                                + IVec2::from(chunk_cords);
-                            if let Ok((Grid(neighbor_chunk_grid), _, _, CubeChild(n_cube_chunk))) =
-                                parent_chunks.get(
-                                    *chunk_map
-                                        .pos_to_ent
-                                        .get(&new_cords)
-                                        .unwrap_or(&Entity::PLACEHOLDER),
-                                )
+                            let neighbor = VoxelWorld::to_global(neighbor_world_pos);
+                            // Neighbors in the same chunk are handled by the chunk's own update.
+                            if neighbor.chunk_cords == chunk_cords {
+                                continue;
+                            }
+                            let (Some(neighbor_block), Some(neighbor_chunk)) = (
+                                neighbor_block,
+                                voxel_world.chunk_entity(neighbor.chunk_cords),
+                            ) else {
+                                continue;
+                            };
+                            if let Ok((_, _, Some(CubeChild(n_cube_chunk)))) =
+                                parent_chunks.get(neighbor_chunk)
                             {
                                 let (neighboring_metadata, _, _) =
                                     chunk_metadata.get(*n_cube_chunk).unwrap();
                                 match &mut *neighboring_metadata.0.write().unwrap() {
                                     MetaData::CubeMD(ref mut metadata) => metadata.log(
                                         VoxelChange::AddFaces,
-                                        neighboring_block_pos,
-                                        neighbor_chunk_grid
-                                            .read()
-                                            .unwrap()
-                                            .get_block(neighboring_block_pos)
-                                            .unwrap(),
+                                        neighbor.pos,
+                                        neighbor_block,
                                         [Some(Block::AIR); 6],
                                     ),
                                     _ => {}
@@ -147,6 +155,25 @@ pub fn global_block_breaker(
                     }
                 }
             }
+            // The partial and translucent blocks next to it in other chunks are culled again.
+            let world_pos = VoxelWorld::to_world(BlockGlobalPos::new(block_pos, chunk_cords));
+            for (subchunk, neighbor_pos, neighbor_block) in neighboring_culled_subchunks(
+                world_pos,
+                chunk_cords,
+                &voxel_world,
+                &mreg,
+                &culled_children,
+            ) {
+                if let Ok((subchunk_md, _, _)) = chunk_metadata.get(subchunk) {
+                    subchunk_md
+                        .0
+                        .write()
+                        .unwrap()
+                        .log_neighbor_change(neighbor_pos, neighbor_block);
+                    commands.entity(subchunk).insert(ToUpdate);
+                }
+            }
+
             // Send a world update event that a block has been broken.
             send_world_updates_surrounding_blocks(
                 block_pos,
@@ -3,12 +3,14 @@
 use bevy_xpbd_3d::prelude::contact_query::contact;
 use bevy_xpbd_3d::prelude::Collider;
 
-use crate::blocks::BlockPropertyRegistry;
+use crate::blocks::{BlockPropertyRegistry, ShapeRegistry};
 use crate::chunk::{
-    chunkmd::SubChunkMD, ChunkCords, ChunkMap, Cords, Grid, ParentChunk, ToUpdate, CHUNK_DIMS,
+    chunkmd::SubChunkMD, ChunkCords, Cords, ParentChunk, PartialChild, ToUpdate, TranslucentChild,
+    VoxelWorld, CHUNK_DIMS,
+};
+use crate::chunk::{
+    CubeSubChunk, CustomSubChunk, PartialSubChunk, Subchunk, TranslucentSubChunk, XSpriteSubChunk,
 };
-use crate::chunk::{CubeSubChunk, Subchunk, XSpriteSubChunk};
-use crate::prelude::notical;
 
 use super::existence_conditions::ExistenceConditionSolverData;
 use super::meshreg::MeshRegistry;
@@ -55,103 +57,98 @@ impl PlaceBlockGlobalEvent {
 /// will not be checked before placing the block.
 pub(super) fn handle_place_block_event(
     mut place_block_event_reader: EventReader<BlockPlaceEvent>,
-    mut global_block_place_event_sender: EventWriter<PlaceBlockGlobalEvent>,
+    mut voxel_world: VoxelWorld,
     child_chunk_query: Query<&Parent, With<Subchunk>>,
     dyn_preg: Res<BlockPropertyRegistry<DynamicProperty>>,
-    parent_chunk_query: Query<(&Cords, &Grid)>,
+    parent_chunk_query: Query<&Cords>,
     player_q: Query<(&Transform, &Collider), With<PhysicalPlayer>>,
     blocks_q: Query<(&Block, &Collider, &Transform)>,
 ) {
     'event_loop: for place_block_event in place_block_event_reader.read() {
         let BlockPlaceEvent(subchunk_entity, block_pos, face, block_to_place) = place_block_event;
-        if let Ok(parent) = child_chunk_query.get(*subchunk_entity) {
-            if let Ok((Cords(chunk_cords), Grid(chunk_grid))) = parent_chunk_query.get(parent.get())
-            {
-                // Get the position and chunk cords of the actual place to put the block in. This
-                // is calculated by seeing which side of the block the player was aiming at.
-                let (block_pos, chunk_cords) = {
-                    if let Some(neighbor) = neighbor_pos(*block_pos, *face, CHUNK_DIMS) {
-                        (neighbor, *chunk_cords)
-                    } else {
-                        let neighbor =
-                            neighbor_across_chunk(*block_pos, *face, CHUNK_DIMS).unwrap();
-                        let change = to_cords(Some(notical::Direction::from(*face)));
-                        let new_cords = [chunk_cords[0] + change[0], chunk_cords[1] + change[1]];
-                        (neighbor, new_cords.into())
-                    }
-                };
-
-                let global_pos = BlockGlobalPos::new(block_pos, chunk_cords);
-                let block_translation =
-                    global_block_pos_to_block_trans(global_pos, VOXEL_DIMS.into(), CHUNK_DIMS);
+        let Ok(parent) = child_chunk_query.get(*subchunk_entity) else {
+            continue;
+        };
+        let Ok(Cords(chunk_cords)) = parent_chunk_query.get(parent.get()) else {
+            continue;
+        };
+        // Get the position of the actual place to put the block in. This is calculated by seeing
+        // which side of the block the player was aiming at.
+        let world_pos =
+            VoxelWorld::to_world(BlockGlobalPos::new(*block_pos, *chunk_cords)) + face.offset();
+        let global_pos = VoxelWorld::to_global(world_pos);
+        if !global_pos.valid {
+            continue;
+        }
+        let block_translation =
+            global_block_pos_to_block_trans(global_pos, VOXEL_DIMS.into(), CHUNK_DIMS);
 
-                // check if the to-be placed block overlaps with the player
-                if BlockPropertyRegistry::is_collidable(block_to_place) {
-                    let (transform, collider) = player_q.get_single().unwrap();
-                    if contact(
-                        collider,
-                        transform.translation,
-                        Quat::IDENTITY,
-                        &Collider::cuboid(0.99, 0.85, 0.99),
-                        block_translation,
-                        Quat::IDENTITY,
-                        0.0,
-                    )
-                    .unwrap()
-                    .is_some()
-                    {
-                        info!("Attempt to place block that overlaps with player was stopped.");
-                        continue 'event_loop;
-                    }
-                }
+        // check if the to-be placed block (any of its collision boxes) overlaps with the player
+        let (transform, collider) = player_q.get_single().unwrap();
+        for collision_box in ShapeRegistry::get()
+            .get_shape(block_to_place)
+            .collision
+            .iter()
+        {
+            let size = collision_box.size() * Vec3::new(0.99, 0.85, 0.99);
+            if contact(
+                collider,
+                transform.translation,
+                Quat::IDENTITY,
+                &Collider::cuboid(size.x, size.y, size.z),
+                block_translation + collision_box.center(),
+                Quat::IDENTITY,
+                0.0,
+            )
+            .unwrap()
+            .is_some()
+            {
+                info!("Attempt to place block that overlaps with player was stopped.");
+                continue 'event_loop;
+            }
+        }
 
-                // check if the to-be placed block overlaps with any current out-of-chunk blocks
-                for (_block, collider, transform) in blocks_q.iter() {
-                    // In the future, this might be a condition about the block itself.
-                    if true {
-                        if contact(
-                            collider,
-                            transform.translation,
-                            Quat::IDENTITY,
-                            &Collider::cuboid(0.99, 0.99, 0.99),
-                            block_translation,
-                            Quat::IDENTITY,
-                            0.0,
-                        )
-                        .unwrap()
-                        .is_some()
-                        {
-                            info!("Attempt to place block that overlaps with another block was stopped.");
-                            continue 'event_loop;
-                        }
-                    }
+        // check if the to-be placed block overlaps with any current out-of-chunk blocks
+        for (_block, collider, transform) in blocks_q.iter() {
+            // In the future, this might be a condition about the block itself.
+            if true {
+                if contact(
+                    collider,
+                    transform.translation,
+                    Quat::IDENTITY,
+                    &Collider::cuboid(0.99, 0.99, 0.99),
+                    block_translation,
+                    Quat::IDENTITY,
+                    0.0,
+                )
+                .unwrap()
+                .is_some()
+                {
+                    info!("Attempt to place block that overlaps with another block was stopped.");
+                    continue 'event_loop;
                 }
+            }
+        }
 
-                // Check if the to-be placed block can even exist in the given place
-                // (based on the defined DynamicProperty::ExistenceCondition)
-                let solver_data = ExistenceConditionSolverData {
-                    surrounding_blocks: chunk_grid.read().unwrap().get_neighbors(block_pos),
-                };
-                for dynamic_property in dyn_preg.get_properties(block_to_place) {
-                    match dynamic_property {
-                        DynamicProperty::ExistenceCondition(cond) => {
-                            if !cond.solve(solver_data) {
-                                info!("Attemp to place block in a position that it can't exist in was stopped");
-                                continue 'event_loop;
-                            }
-                        }
-                        _ => {}
+        // Check if the to-be placed block can even exist in the given place
+        // (based on the defined DynamicProperty::ExistenceCondition)
+        let solver_data = ExistenceConditionSolverData {
+            surrounding_blocks: voxel_world.get_neighbors(world_pos),
+        };
+        for dynamic_property in dyn_preg.get_properties(block_to_place) {
+            match dynamic_property {
+                DynamicProperty::ExistenceCondition(cond) => {
+                    if !cond.solve(solver_data) {
+                        info!("Attemp to place block in a position that it can't exist in was stopped");
+                        continue 'event_loop;
                     }
                 }
-
-                // send the global block place event
-                global_block_place_event_sender.send(PlaceBlockGlobalEvent {
-                    block: *block_to_place,
-                    chunk_cords,
-                    block_pos,
-                });
+                _ => {}
             }
         }
+
+        // place the block (sends the global block place event)
+        voxel_world.set_block(world_pos, *block_to_place);
     }
 }
 
@@ -164,9 +161,17 @@ pub fn global_block_placer(
     mut break_block_global_sender: EventWriter<BreakBlockGlobalEvent>,
     mut commands: Commands,
     mreg: Res<MeshRegistry>,
-    chunk_map: Res<ChunkMap>,
-    parent_chunks: Query<(&Grid, &Children), With<ParentChunk>>,
-    chunk_metadata: Query<(&SubChunkMD, Has<CubeSubChunk>, Has<XSpriteSubChunk>)>,
+    voxel_world: VoxelWorld,
+    parent_chunks: Query<&Children, With<ParentChunk>>,
+    culled_children: Query<(Option<&PartialChild>, Option<&TranslucentChild>), With<ParentChunk>>,
+    chunk_metadata: Query<(
+        &SubChunkMD,
+        Has<CubeSubChunk>,
+        Has<XSpriteSubChunk>,
+        Has<PartialSubChunk>,
+        Has<TranslucentSubChunk>,
+        Has<CustomSubChunk>,
+    )>,
 ) {
     let len = global_block_place_events.len();
     for &PlaceBlockGlobalEvent {
@@ -183,31 +188,67 @@ pub fn global_block_placer(
             ));
             continue;
         }
-        if let (Some(Grid(chunk_grid)), subchunks) = chunk_map.pos_to_ent.get(&chunk_cords).map_or(
-            (None, [Entity::PLACEHOLDER].iter()),
-            |e| {
-                parent_chunks
-                    .get(*e)
-                    .map(|(g, c)| (Some(g), c.iter()))
-                    .unwrap_or((None, [Entity::PLACEHOLDER].iter()))
-            },
+        if let (Some(chunk_grid), Some(subchunks)) = (
+            voxel_world.grid(chunk_cords),
+            voxel_world
+                .chunk_entity(chunk_cords)
+                .and_then(|e| parent_chunks.get(e).ok()),
         ) {
-            for subchunk in subchunks {
-                if let Ok((subchunk_md, cube_chunk, xsprite_chunk)) = chunk_metadata.get(*subchunk)
+            let neighbors = chunk_grid.read().unwrap().get_neighbors(block_pos);
+            // Partial and translucent blocks are culled by their neighbors, so their mesh changes
+            // too.
+            let partial_neighbor = neighbors
+                .iter()
+                .flatten()
+                .any(|n| matches!(mreg.get_mesh(n), VoxelMesh::Partial(_)));
+            let translucent_neighbor = neighbors
+                .iter()
+                .flatten()
+                .any(|n| matches!(mreg.get_mesh(n), VoxelMesh::Translucent(_)));
+            for subchunk in subchunks.iter() {
+                if let Ok((
+                    subchunk_md,
+                    cube_chunk,
+                    xsprite_chunk,
+                    partial_chunk,
+                    translucent_chunk,
+                    custom_chunk,
+                )) = chunk_metadata.get(*subchunk)
                 {
-                    // make sure we update the metadata of the right subchunk
-                    match mreg.get_mesh(&block) {
-                        VoxelMesh::NormalCube(_) if xsprite_chunk => continue,
-                        VoxelMesh::XSprite(_) if cube_chunk => continue,
-                        VoxelMesh::Null => continue,
-                        _ => {}
+                    // make sure we update the metadata of the right subchunk(s). Partial blocks
+                    // are also logged in the cube subchunk, to cull the faces they cover.
+                    // Translucent blocks don't cover anything, but they are culled by the blocks
+                    // next to them.
+                    // Custom blocks are in their own subchunk, and are logged in the cube
+                    // subchunk like partial blocks.
+                    let right_subchunk = match mreg.get_mesh(&block) {
+                        VoxelMesh::NormalCube(_) => {
+                            cube_chunk
+                                || partial_chunk && partial_neighbor
+                                || translucent_chunk && translucent_neighbor
+                        }
+                        VoxelMesh::XSprite(_) => xsprite_chunk,
+                        VoxelMesh::Partial(_) => {
+                            cube_chunk || partial_chunk || translucent_chunk && translucent_neighbor
+                        }
+                        VoxelMesh::Translucent(_) => translucent_chunk,
+                        VoxelMesh::CustomMesh(_) => {
+                            cube_chunk
+                                || custom_chunk
+                                || partial_chunk && partial_neighbor
+                                || translucent_chunk && translucent_neighbor
+                        }
+                        VoxelMesh::Null => false,
+                    };
+                    if !right_subchunk {
+                        continue;
                     }
                     // Update the metadata
-                    subchunk_md.0.write().unwrap().log_place(
-                        block_pos,
-                        block,
-                        chunk_grid.read().unwrap().get_neighbors(block_pos),
-                    );
+                    subchunk_md
+                        .0
+                        .write()
+                        .unwrap()
+                        .log_place(block_pos, block, neighbors);
 
                     // Insert marker components and apply smooth lighting.
                     commands.entity(*subchunk).insert(ToUpdate);
@@ -215,12 +256,31 @@ pub fn global_block_placer(
                         &mut commands,
                         block_pos,
                         chunk_cords,
-                        &chunk_map,
+                        &voxel_world,
                         len,
                     );
                 }
             }
 
+            // The partial and translucent blocks next to it in other chunks are culled again.
+            let world_pos = VoxelWorld::to_world(BlockGlobalPos::new(block_pos, chunk_cords));
+            for (subchunk, neighbor_pos, neighbor_block) in neighboring_culled_subchunks(
+                world_pos,
+                chunk_cords,
+                &voxel_world,
+                &mreg,
+                &culled_children,
+            ) {
+                if let Ok((subchunk_md, ..)) = chunk_metadata.get(subchunk) {
+                    subchunk_md
+                        .0
+                        .write()
+                        .unwrap()
+                        .log_neighbor_change(neighbor_pos, neighbor_block);
+                    commands.entity(subchunk).insert(ToUpdate);
+                }
+            }
+
             // Set the new block in the grid, broadcast a world update.
             let _ = chunk_grid.write().unwrap().set_block(block, block_pos);
             send_world_updates_surrounding_blocks(
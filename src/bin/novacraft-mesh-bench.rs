@@ -0,0 +1,273 @@
+//! Headless chunk meshing benchmark. Generates chunks with the terrain generator, meshes them with
+//! each [`MeshingAlgorithm`], with and without smooth lighting, and reports how long it took and
+//! how big the meshes are. It doesn't need a window or a GPU, so it can run in CI.
+//! The meshes can be hashed into a golden file (`--write-golden`), and checked against it later
+//! (`--check-golden`) to catch meshing regressions. The meshes are built with the default
+//! (unstitched) atlas, so the hashes don't depend on the block textures, and floats are rounded
+//! before they are hashed, so they don't depend on the platform. CI checks them against
+//! `tests/golden/mesh-bench.txt`, which is written with the default arguments.
+use clap::Parser;
+use noise::Perlin;
+use nova_craft::blocks::meshreg::MeshRegistry;
+use nova_craft::chunk::{ChunkCords, ChunkGrid, CHUNK_DIMS, DEFAULT_SL, NOISE_SEED};
+use nova_craft::prelude::*;
+use nova_craft::terrain::{
+    generate_chunk, generate_tint_map, NOISE_FACTOR_CONT, NOISE_FACTOR_SCALE,
+};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+/// Floats are hashed in units of 1/`FLOAT_PRECISION`.
+const FLOAT_PRECISION: f32 = 1e4;
+
+/// Mesh generated chunks without running the game
+#[derive(Parser)]
+#[command(name = "novacraft-mesh-bench")]
+struct Args {
+    /// The seed of the terrain noise
+    #[arg(long, default_value_t = NOISE_SEED)]
+    seed: u32,
+    /// Mesh the chunks from -radius to radius (in chunks) on both axes
+    #[arg(long, default_value_t = 2)]
+    radius: i32,
+    /// How many times to mesh each chunk, the fastest time is reported
+    #[arg(long, default_value_t = 3)]
+    runs: u32,
+    /// Write the hashes of the meshes to this file
+    #[arg(long)]
+    write_golden: Option<String>,
+    /// Compare the hashes of the meshes to this file, and fail if any of them changed
+    #[arg(long)]
+    check_golden: Option<String>,
+}
+
+/// A meshing configuration to benchmark.
+#[derive(Clone, Copy)]
+struct Config {
+    algorithm: MeshingAlgorithm,
+    smooth_lighting: bool,
+}
+
+impl Config {
+    const ALL: [Config; 4] = [
+        Config {
+            algorithm: MeshingAlgorithm::Naive,
+            smooth_lighting: false,
+        },
+        Config {
+            algorithm: MeshingAlgorithm::Naive,
+            smooth_lighting: true,
+        },
+        Config {
+            algorithm: MeshingAlgorithm::Culling,
+            smooth_lighting: false,
+        },
+        Config {
+            algorithm: MeshingAlgorithm::Culling,
+            smooth_lighting: true,
+        },
+    ];
+
+    fn name(&self) -> String {
+        let algorithm = match self.algorithm {
+            MeshingAlgorithm::Naive => "naive",
+            MeshingAlgorithm::Culling => "culling",
+        };
+        let smooth_lighting = if self.smooth_lighting { "sl" } else { "no-sl" };
+        format!("{}/{}", algorithm, smooth_lighting)
+    }
+
+    /// The smooth lighting of the game, applied as the chunk is meshed (in the game it's applied
+    /// later, when the chunks around it are known).
+    fn smooth_lighting_params(&self) -> Option<SmoothLightingParameters> {
+        DEFAULT_SL
+            .filter(|_| self.smooth_lighting)
+            .map(|sl| SmoothLightingParameters {
+                apply_at_gen: true,
+                ..sl
+            })
+    }
+}
+
+/// Mesh a chunk like the game does at full detail, except with the given configuration. Returns
+/// the mesh of each type of subchunk.
+fn mesh_chunk(
+    grid: &ChunkGrid,
+    cords: ChunkCords,
+    mreg: &MeshRegistry,
+    config: Config,
+) -> Vec<Mesh> {
+    let tint_map = generate_tint_map(cords, 1);
+    let mut meshes = Vec::with_capacity(5);
+    if let Some((cube, _)) = meshify_cubic_voxels(
+        &[Face::Bottom],
+        grid,
+        mreg,
+        config.algorithm,
+        config.smooth_lighting_params(),
+        Some(tint_map.clone()),
+    ) {
+        meshes.push(cube);
+    }
+    meshes.push(meshify_xsprite_voxels(mreg, grid, Some(tint_map)).0);
+    meshes.push(meshify_partial_voxels(mreg, grid).0);
+    meshes.push(meshify_translucent_voxels(mreg, grid).0);
+    meshes.push(meshify_custom_voxels(mreg, grid).0);
+    meshes
+}
+
+/// FNV-1a, it's stable across platforms and Rust versions (unlike the std hasher).
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// The bytes of the values of an attribute, floats are rounded to 1e-4 first. The smooth lighting
+/// and the tints go through `powf`, which isn't bit for bit the same on every platform.
+fn quantized_bytes(values: &VertexAttributeValues) -> Vec<u8> {
+    let floats: &[f32] = match values {
+        VertexAttributeValues::Float32(values) => values,
+        VertexAttributeValues::Float32x2(values) => values.as_flattened(),
+        VertexAttributeValues::Float32x3(values) => values.as_flattened(),
+        VertexAttributeValues::Float32x4(values) => values.as_flattened(),
+        _ => return values.get_bytes().to_vec(),
+    };
+    floats
+        .iter()
+        .flat_map(|value| ((value * FLOAT_PRECISION).round() as i32).to_le_bytes())
+        .collect()
+}
+
+/// Hash the vertices and indices of the meshes of a chunk.
+fn hash_meshes(meshes: &[Mesh]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    for mesh in meshes {
+        for (id, values) in mesh.attributes() {
+            hash = fnv1a(hash, format!("{:?}", id).as_bytes());
+            hash = fnv1a(hash, &quantized_bytes(values));
+        }
+        for index in mesh
+            .indices()
+            .into_iter()
+            .flat_map(|indices| indices.iter())
+        {
+            hash = fnv1a(hash, &(index as u32).to_le_bytes());
+        }
+    }
+    hash
+}
+
+/// Parse a golden file, each line is `<config> <x> <z> <hash>`.
+fn read_golden(path: &str) -> std::io::Result<BTreeMap<String, String>> {
+    let golden = std::fs::read_to_string(path)?;
+    Ok(golden
+        .lines()
+        .filter_map(|line| line.rsplit_once(' '))
+        .map(|(key, hash)| (key.to_string(), hash.to_string()))
+        .collect())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let noise = Perlin::new(args.seed);
+    let mreg = MeshRegistry::default();
+    let chunks: Vec<_> = (-args.radius..=args.radius)
+        .flat_map(|x| (-args.radius..=args.radius).map(move |z| ChunkCords::new(x, z)))
+        .map(|cords| {
+            let grid = generate_chunk(
+                cords,
+                args.seed,
+                &noise,
+                NOISE_FACTOR_CONT,
+                NOISE_FACTOR_SCALE,
+            );
+            (cords, ChunkGrid::new(grid, CHUNK_DIMS))
+        })
+        .collect();
+
+    println!(
+        "meshing {} chunks (seed {}, fastest of {} runs)",
+        chunks.len(),
+        args.seed,
+        args.runs.max(1)
+    );
+    println!(
+        "{:<16}{:>14}{:>14}{:>12}{:>12}",
+        "config", "total (ms)", "chunk (ms)", "vertices", "indices"
+    );
+    let mut hashes = BTreeMap::new();
+    for config in Config::ALL {
+        let mut total_time = Duration::ZERO;
+        let mut vertices = 0;
+        let mut indices = 0;
+        for (cords, grid) in &chunks {
+            let mut fastest = Duration::MAX;
+            let mut meshes = Vec::new();
+            for _ in 0..args.runs.max(1) {
+                let start = Instant::now();
+                meshes = mesh_chunk(grid, *cords, &mreg, config);
+                fastest = fastest.min(start.elapsed());
+            }
+            total_time += fastest;
+            vertices += meshes.iter().map(Mesh::count_vertices).sum::<usize>();
+            indices += meshes
+                .iter()
+                .map(|mesh| mesh.indices().map_or(0, |indices| indices.len()))
+                .sum::<usize>();
+            hashes.insert(
+                format!("{} {} {}", config.name(), cords.x, cords.y),
+                format!("{:016x}", hash_meshes(&meshes)),
+            );
+        }
+        let total_ms = total_time.as_secs_f64() * 1000.0;
+        println!(
+            "{:<16}{:>14.2}{:>14.3}{:>12}{:>12}",
+            config.name(),
+            total_ms,
+            total_ms / chunks.len().max(1) as f64,
+            vertices,
+            indices
+        );
+    }
+
+    if let Some(path) = &args.write_golden {
+        let mut golden = String::new();
+        for (key, hash) in &hashes {
+            let _ = writeln!(golden, "{} {}", key, hash);
+        }
+        if let Err(err) = std::fs::write(path, golden) {
+            eprintln!("couldn't write the golden file {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+        println!("wrote {} hashes to {}", hashes.len(), path);
+    }
+
+    if let Some(path) = &args.check_golden {
+        let golden = match read_golden(path) {
+            Ok(golden) => golden,
+            Err(err) => {
+                eprintln!("couldn't read the golden file {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        };
+        let mismatches: Vec<_> = hashes
+            .iter()
+            .filter(|(key, hash)| golden.get(*key) != Some(hash))
+            .collect();
+        for (key, hash) in &mismatches {
+            let expected = golden.get(*key).map_or("missing", String::as_str);
+            eprintln!("mismatch {}: expected {}, got {}", key, expected, hash);
+        }
+        if !mismatches.is_empty() {
+            eprintln!("{} of {} meshes changed", mismatches.len(), hashes.len());
+            return ExitCode::FAILURE;
+        }
+        println!("all {} meshes match {}", hashes.len(), path);
+    }
+    ExitCode::SUCCESS
+}
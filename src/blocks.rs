@@ -1,5 +1,7 @@
 use crate::{chunk::ChunkCords, prelude::*};
 
+pub mod animation;
+pub mod atlas;
 mod block_defs;
 mod block_descriptor;
 pub mod dynamic_property;
@@ -7,6 +9,7 @@ pub mod existence_conditions;
 #[allow(non_snake_case)]
 pub mod init_blocks;
 pub mod meshreg;
+pub mod packed;
 pub mod properties;
 pub use init_blocks::*;
 
@@ -56,8 +59,6 @@ pub struct BlocksPlugin;
 mod consts_to_generate_voxel_mesh {
     pub const VOXEL_DIMS: [f32; 3] = [1.0, 1.0, 1.0];
     pub const VOXEL_CENTER: [f32; 3] = [0.0, 0.0, 0.0];
-    pub const TEXTURE_ATLAS_DIMS: [u32; 2] = [10, 10];
-    pub const PADDING: f32 = 0.0;
     pub const COLOR_INTENSITY: f32 = 1.0;
     pub const ALPHA: f32 = 1.0;
     pub const XSPRITE_SCALE: f32 = 0.85;
@@ -67,6 +68,10 @@ pub(super) use consts_to_generate_voxel_mesh::*;
 impl Plugin for BlocksPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<WorldBlockUpdate>();
-        app.add_plugins(BlockRegistriesPlugin);
+        app.add_plugins((
+            BlockRegistriesPlugin,
+            animation::BlockAnimationPlugin,
+            packed::PackedBlockPlugin,
+        ));
     }
 }
@@ -0,0 +1,139 @@
+//! Animated block textures. The frames of an animated texture are in consecutive cells of the atlas
+//! (see [`atlas`](super::atlas)), and each vertex of a block mesh has an [`ATTRIBUTE_ANIMATION`]
+//! with the amount of frames and the time of each frame of its texture. The vertex shader of the
+//! [`AnimatedBlockMaterial`] moves the UVs to the cell of the current frame, so the meshes never need to be
+//! rebuilt for the textures to animate.
+use super::atlas::{AtlasLayout, TextureName};
+use crate::prelude::*;
+use bevy::asset::load_internal_asset;
+use bevy::pbr::{
+    ExtendedMaterial, MaterialExtension, MaterialExtensionKey, MaterialExtensionPipeline,
+};
+use bevy::render::mesh::MeshVertexBufferLayout;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError, VertexFormat,
+};
+
+/// The animation of the texture of a vertex: `[frames, frame_time]`. Textures with one frame (or a
+/// frame time of 0) don't animate.
+pub const ATTRIBUTE_ANIMATION: MeshVertexAttribute = MeshVertexAttribute::new(
+    "BlockAnimation",
+    0x6a1f_04c3_92d7_5e18,
+    VertexFormat::Float32x2,
+);
+
+/// The animation of a texture that doesn't animate.
+pub const NO_ANIMATION: [f32; 2] = [1.0, 0.0];
+
+const BLOCK_SHADER: Handle<Shader> =
+    Handle::weak_from_u128(0x2e84_b0f7_1c65_4d39_8a0e_f5d2_67c1_9b43);
+
+/// The material of the blocks.
+pub type AnimatedBlockMaterial = ExtendedMaterial<StandardMaterial, BlockAnimation>;
+
+/// The part of the [`AnimatedBlockMaterial`] that animates the textures.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct BlockAnimation {
+    /// The dimensions of the atlas (in cells), to find the cells of the next frames.
+    #[uniform(100)]
+    pub atlas_dims: Vec2,
+}
+
+impl MaterialExtension for BlockAnimation {
+    fn vertex_shader() -> ShaderRef {
+        BLOCK_SHADER.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialExtensionPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialExtensionKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // The prepass uses its own vertex shader, and meshes without the attribute (xsprites)
+        // aren't animated.
+        if descriptor.vertex.shader != BLOCK_SHADER || !layout.contains(ATTRIBUTE_ANIMATION) {
+            return Ok(());
+        }
+        descriptor.vertex.buffers = vec![layout.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+            Mesh::ATTRIBUTE_UV_0.at_shader_location(2),
+            Mesh::ATTRIBUTE_COLOR.at_shader_location(5),
+            ATTRIBUTE_ANIMATION.at_shader_location(8),
+        ])?];
+        descriptor.vertex.shader_defs.push("BLOCK_ANIMATION".into());
+        Ok(())
+    }
+}
+
+/// A texture of a block, by name. It can be animated, if it has more than one frame on the atlas.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockTexture {
+    pub name: TextureName,
+    /// How long each frame of the texture is shown (in seconds), `None` to show only the first one.
+    pub frame_time: Option<f32>,
+}
+
+impl BlockTexture {
+    pub const fn new(name: TextureName) -> Self {
+        Self {
+            name,
+            frame_time: None,
+        }
+    }
+
+    pub const fn animated(name: TextureName, frame_time: f32) -> Self {
+        Self {
+            name,
+            frame_time: Some(frame_time),
+        }
+    }
+
+    /// The value of the [`ATTRIBUTE_ANIMATION`] of the vertices with this texture.
+    pub fn animation(&self, atlas: &AtlasLayout) -> [f32; 2] {
+        match self.frame_time {
+            Some(frame_time) => [atlas.frames(self.name) as f32, frame_time],
+            None => NO_ANIMATION,
+        }
+    }
+}
+
+impl From<TextureName> for BlockTexture {
+    fn from(name: TextureName) -> Self {
+        Self::new(name)
+    }
+}
+
+/// Give the vertices of a mesh made of quads (4 vertices each) the animations of their textures,
+/// `animation_of_quad` returns the animation of the quad at an index.
+pub fn insert_animation(mesh: &mut Mesh, animation_of_quad: impl Fn(usize) -> [f32; 2]) {
+    let animations: Vec<[f32; 2]> = (0..mesh.count_vertices())
+        .map(|vertex| animation_of_quad(vertex / 4))
+        .collect();
+    mesh.insert_attribute(ATTRIBUTE_ANIMATION, animations);
+}
+
+/// Keep the atlas dimensions of the block materials up to date with the atlas.
+fn update_atlas_dims(
+    atlas: Res<AtlasLayout>,
+    mut materials: ResMut<Assets<AnimatedBlockMaterial>>,
+) {
+    let [x, y] = atlas.dims();
+    for (_, material) in materials.iter_mut() {
+        material.extension.atlas_dims = Vec2::new(x as f32, y as f32);
+    }
+}
+
+pub(super) struct BlockAnimationPlugin;
+
+impl Plugin for BlockAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, BLOCK_SHADER, "block.wgsl", Shader::from_wgsl);
+        app.add_plugins(MaterialPlugin::<AnimatedBlockMaterial>::default());
+        app.add_systems(
+            Update,
+            update_atlas_dims.run_if(resource_exists_and_changed::<AtlasLayout>()),
+        );
+    }
+}
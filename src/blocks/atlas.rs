@@ -0,0 +1,236 @@
+//! The texture atlas of the blocks. Every block texture is its own image in
+//! `assets/textures/blocks/`, and the blocks refer to them by name (the file name, without the
+//! extension). When the textures are loaded, they are stitched into one atlas, and the meshes of
+//! the blocks are built with the [`AtlasLayout`] of the atlas.
+//! A texture can also be a sequence of frames, stacked from top to bottom in one image (an image
+//! that is a whole number of squares high). The frames are stitched in consecutive cells, so they
+//! can be animated by moving to the next cells (see [`animation`](super::animation)).
+use super::meshreg::MeshRegistry;
+use crate::chunk::SharedMeshRegistry;
+use crate::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::utils::HashMap;
+use bevy_asset_loader::prelude::*;
+use std::sync::Arc;
+
+/// The name of a block texture, its file name in `assets/textures/blocks/` without the extension.
+pub type TextureName = &'static str;
+
+/// The coordniates of a texture on the texture atlas, in cells.
+pub type AtlasCords = [u32; 2];
+
+/// The name of the texture used for blocks that don't have one. Any texture that can't be found
+/// falls back to it.
+pub const MISSING_TEXTURE: TextureName = "missing";
+const MISSING_TEXTURE_COLOR: [u8; 4] = [244, 0, 255, 255];
+
+/// The size (in pixels) of the textures (or frames) in the atlas. Textures of a different size are
+/// resized.
+pub const TEXTURE_SIZE: u32 = 16;
+/// Each texture in the atlas is surrounded by a gutter of its own edge pixels, so sampling near
+/// the edges of a texture (or from its mips) doesn't bleed into the textures next to it.
+pub const TEXTURE_GUTTER: u32 = 2;
+
+/// The handle of the stitched atlas, it's filled in once the block textures are loaded.
+pub const BLOCK_ATLAS_IMAGE: Handle<Image> =
+    Handle::weak_from_u128(0x5d3c_4a1e_9b27_4f80_a6d1_2c8e_73b5_0f49);
+
+#[derive(AssetCollection, Resource)]
+pub(super) struct BlockTextures {
+    #[asset(path = "textures/blocks", collection(typed))]
+    textures: Vec<Handle<Image>>,
+}
+
+/// Where each block texture is on the atlas. Until the atlas is stitched, every texture is the
+/// missing texture.
+#[derive(Resource, Clone)]
+pub struct AtlasLayout {
+    dims: [u32; 2],
+    /// The cell of the first frame, and the amount of frames of each texture.
+    cells: HashMap<String, (AtlasCords, u32)>,
+    missing: AtlasCords,
+}
+
+impl Default for AtlasLayout {
+    fn default() -> Self {
+        Self {
+            dims: [1, 1],
+            cells: HashMap::new(),
+            missing: [0, 0],
+        }
+    }
+}
+
+impl AtlasLayout {
+    /// The dimensions of the atlas, in cells.
+    pub fn dims(&self) -> [u32; 2] {
+        self.dims
+    }
+
+    /// An atlas with the dimensions, and only the missing texture.
+    #[cfg(test)]
+    pub(crate) fn with_dims(dims: [u32; 2]) -> Self {
+        Self {
+            dims,
+            ..Default::default()
+        }
+    }
+
+    /// The part of each cell (on each side) that is gutter and not texture.
+    pub fn padding(&self) -> f32 {
+        TEXTURE_GUTTER as f32 / (TEXTURE_SIZE + 2 * TEXTURE_GUTTER) as f32
+    }
+
+    /// The cell of the texture, or the cell of the missing texture if there isn't one with that
+    /// name.
+    pub fn cords(&self, name: TextureName) -> AtlasCords {
+        self.cells.get(name).map_or_else(
+            || {
+                if name != MISSING_TEXTURE {
+                    warn!("Couldn't find the block texture \"{name}\", using the missing texture.");
+                }
+                self.missing
+            },
+            |(cords, _)| *cords,
+        )
+    }
+
+    /// The amount of frames the texture has, 1 if it isn't animated (or can't be found).
+    pub fn frames(&self, name: TextureName) -> u32 {
+        self.cells.get(name).map_or(1, |(_, frames)| *frames)
+    }
+}
+
+/// Stitch the loaded block textures into the atlas ([`BLOCK_ATLAS_IMAGE`]), and rebuild the
+/// meshes of the blocks with its layout.
+pub(super) fn stitch_block_atlas(
+    mut commands: Commands,
+    block_textures: Res<BlockTextures>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut mreg: ResMut<MeshRegistry>,
+    mut shared_mreg: ResMut<SharedMeshRegistry>,
+) {
+    let mut textures: Vec<(String, Image)> = block_textures
+        .textures
+        .iter()
+        .filter_map(|handle| {
+            let name = asset_server
+                .get_path(handle.id())?
+                .path()
+                .file_stem()?
+                .to_string_lossy()
+                .into_owned();
+            let image = images.get(handle)?;
+            match image.texture_descriptor.format {
+                TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => {
+                    Some((name, image.clone()))
+                }
+                format => match image.convert(TextureFormat::Rgba8UnormSrgb) {
+                    Some(image) => Some((name, image)),
+                    None => {
+                        warn!("Block texture \"{name}\" has an unsupported format ({format:?}).");
+                        None
+                    }
+                },
+            }
+        })
+        .collect();
+    // The order of the textures on the atlas doesn't depend on the order they were loaded in.
+    textures.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    // The frames of each texture, a texture that isn't animated has one frame.
+    let frames: Vec<u32> = textures
+        .iter()
+        .map(|(_, image)| {
+            let size = image.texture_descriptor.size;
+            if size.width > 0 && size.height > size.width && size.height % size.width == 0 {
+                size.height / size.width
+            } else {
+                1
+            }
+        })
+        .collect();
+    // The missing texture takes the last cell.
+    let cell_count = frames.iter().sum::<u32>() + 1;
+    let columns = (cell_count as f32).sqrt().ceil() as u32;
+    let dims = [columns, (cell_count + columns - 1) / columns];
+    let stride = TEXTURE_SIZE + 2 * TEXTURE_GUTTER;
+    let width = dims[0] * stride;
+    let height = dims[1] * stride;
+    let mut data = vec![0; (width * height * 4) as usize];
+    let mut cells = HashMap::new();
+
+    let cell_of = |i: u32| [i % dims[0], i / dims[0]];
+    let mut next_cell = 0;
+    for ((name, image), frames) in textures.iter().zip(frames) {
+        let size = image.texture_descriptor.size;
+        // The size of each frame
+        let (w, h) = (size.width.max(1), (size.height / frames).max(1));
+        if w != TEXTURE_SIZE || h != TEXTURE_SIZE {
+            warn!("Block texture \"{name}\" is {w}x{h}, resizing it to {TEXTURE_SIZE}x{TEXTURE_SIZE}.");
+        }
+        cells.insert(name.clone(), (cell_of(next_cell), frames));
+        for frame in 0..frames {
+            write_cell(&mut data, width, cell_of(next_cell), |x, y| {
+                let i =
+                    ((frame * h + y * h / TEXTURE_SIZE) * w + x * w / TEXTURE_SIZE) as usize * 4;
+                image.data[i..i + 4].try_into().unwrap()
+            });
+            next_cell += 1;
+        }
+    }
+    let missing = cell_of(cell_count - 1);
+    write_cell(&mut data, width, missing, |_, _| MISSING_TEXTURE_COLOR);
+
+    info!(
+        "Stitched {} block textures into a {}x{} atlas.",
+        textures.len(),
+        dims[0],
+        dims[1]
+    );
+    images.insert(
+        BLOCK_ATLAS_IMAGE.id(),
+        Image::new(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        ),
+    );
+    let layout = AtlasLayout {
+        dims,
+        cells,
+        missing,
+    };
+    *mreg = MeshRegistry::new(&layout);
+    // The chunks that are meshed this frame need the new meshes too, `share_mesh_registry` would
+    // only share them next frame.
+    *shared_mreg = SharedMeshRegistry(Arc::new(mreg.clone()));
+    commands.insert_resource(layout);
+}
+
+/// Write a texture into its cell of the atlas. `pixel_at` returns the pixel of the texture at a
+/// position (from 0 to [`TEXTURE_SIZE`] on each axis), the gutter repeats the edge pixels.
+fn write_cell(
+    data: &mut [u8],
+    width: u32,
+    cell: AtlasCords,
+    pixel_at: impl Fn(u32, u32) -> [u8; 4],
+) {
+    let stride = TEXTURE_SIZE + 2 * TEXTURE_GUTTER;
+    for y in 0..stride {
+        for x in 0..stride {
+            let pixel = pixel_at(
+                x.saturating_sub(TEXTURE_GUTTER).min(TEXTURE_SIZE - 1),
+                y.saturating_sub(TEXTURE_GUTTER).min(TEXTURE_SIZE - 1),
+            );
+            let i = ((cell[1] * stride + y) * width + cell[0] * stride + x) as usize * 4;
+            data[i..i + 4].copy_from_slice(&pixel);
+        }
+    }
+}
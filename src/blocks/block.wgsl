@@ -0,0 +1,74 @@
+// The vertex shader of the blocks (see `animation.rs`). It's the vertex shader of the standard
+// material, that also moves the UVs of animated textures to the cell of the current frame.
+#import bevy_pbr::{
+    mesh_functions,
+    forward_io::VertexOutput,
+    mesh_view_bindings::globals,
+    view_transformations::position_world_to_clip,
+}
+
+@group(1) @binding(100) var<uniform> atlas_dims: vec2<f32>;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+#ifdef VERTEX_NORMALS
+    @location(1) normal: vec3<f32>,
+#endif
+#ifdef VERTEX_UVS
+    @location(2) uv: vec2<f32>,
+#endif
+#ifdef VERTEX_COLORS
+    @location(5) color: vec4<f32>,
+#endif
+#ifdef BLOCK_ANIMATION
+    // [frames, frame_time]
+    @location(8) animation: vec2<f32>,
+#endif
+};
+
+// The frames of a texture are in consecutive cells of the atlas (left to right, top to bottom).
+fn animated_uv(uv: vec2<f32>, animation: vec2<f32>) -> vec2<f32> {
+    let frames = u32(animation.x);
+    if frames <= 1u || animation.y <= 0.0 {
+        return uv;
+    }
+    let frame = u32(globals.time / animation.y) % frames;
+    let columns = u32(atlas_dims.x);
+    // The UVs are padded, so they are always inside the cell of their texture.
+    let cell = vec2<u32>(floor(uv * atlas_dims));
+    let index = cell.y * columns + cell.x + frame;
+    let frame_cell = vec2<u32>(index % columns, index / columns);
+    return uv + (vec2<f32>(frame_cell) - vec2<f32>(cell)) / atlas_dims;
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let model = mesh_functions::get_model_matrix(vertex.instance_index);
+#ifdef VERTEX_NORMALS
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        vertex.normal,
+        vertex.instance_index
+    );
+#endif
+    out.world_position = mesh_functions::mesh_position_local_to_world(
+        model,
+        vec4<f32>(vertex.position, 1.0)
+    );
+    out.position = position_world_to_clip(out.world_position.xyz);
+#ifdef VERTEX_UVS
+#ifdef BLOCK_ANIMATION
+    out.uv = animated_uv(vertex.uv, vertex.animation);
+#else
+    out.uv = vertex.uv;
+#endif
+#endif
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+    return out;
+}
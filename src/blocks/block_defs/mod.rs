@@ -8,12 +8,15 @@ use super::{
     properties::{DynamicProperty, PassiveProperty, PhysicalProperty},
     Block, Face,
 };
+use crate::prelude::{Vec3, VoxelBox};
 
 // Describe each block
 #[allow(non_snake_case)]
 impl BlockDescriptor {
     pub fn Air() -> Self {
         Self {
+            collision_shape: CollisionShape::None,
+            covering: FaceCovering::NONE,
             passive: PropertyCollection::<PassiveProperty>::from_property(
                 PassiveProperty::YieldToFallingBlock,
             ),
@@ -24,11 +27,12 @@ impl BlockDescriptor {
     pub fn Grass() -> Self {
         BlockDescriptor {
             mesh_builder: MeshBuilder::Cube(
-                CubeTextureCords::uniform([1, 0])
-                    .with_face(Face::Top, [0, 0])
-                    .with_face(Face::Bottom, [2, 0])
+                CubeTextures::uniform("grass_side")
+                    .with_face(Face::Top, "grass_top")
+                    .with_face(Face::Bottom, "dirt")
                     .into(),
             ),
+            tinted: TintedFaces::only(&[Face::Top]),
             dynamic: PropertyCollection::<DynamicProperty>::from_property(
                 DynamicProperty::BlockTransformIf(
                     ExistenceCondition::BlockToTheSideMust(
@@ -49,21 +53,28 @@ impl BlockDescriptor {
 
     pub fn Stone() -> Self {
         BlockDescriptor {
-            mesh_builder: MeshBuilder::Cube(CubeTextureCords::uniform([3, 0]).into()),
+            mesh_builder: MeshBuilder::Cube(CubeTextures::uniform("stone").into()),
             ..Default::default()
         }
     }
 
     pub fn Dirt() -> Self {
         BlockDescriptor {
-            mesh_builder: MeshBuilder::Cube(CubeTextureCords::uniform([2, 0]).into()),
+            mesh_builder: MeshBuilder::Cube(CubeTextures::uniform("dirt").into()),
             ..Default::default()
         }
     }
 
     pub fn Greenery() -> Self {
         BlockDescriptor {
-            mesh_builder: MeshBuilder::XSprite(XSpriteTextureCords::uniform([4, 0]).into()),
+            mesh_builder: MeshBuilder::XSprite(XSpriteTextures::uniform("greenery").into()),
+            tinted: TintedFaces::ALL,
+            collision_shape: CollisionShape::None,
+            selection_shape: SelectionShape::Box(VoxelBox::new(
+                Vec3::new(-0.35, -0.5, -0.35),
+                Vec3::new(0.35, 0.35, 0.35),
+            )),
+            covering: FaceCovering::NONE,
             passive: PropertyCollection::<PassiveProperty>::from_property(
                 PassiveProperty::YieldToFallingBlock,
             ),
@@ -79,11 +90,92 @@ impl BlockDescriptor {
 
     pub fn Sand() -> Self {
         BlockDescriptor {
-            mesh_builder: MeshBuilder::Cube(CubeTextureCords::uniform([6, 0]).into()),
+            mesh_builder: MeshBuilder::Cube(CubeTextures::uniform("sand").into()),
             physical: PropertyCollection::<PhysicalProperty>::from_property(
                 PhysicalProperty::AffectedByGravity,
             ),
             ..Default::default()
         }
     }
+
+    pub fn StoneSlab() -> Self {
+        BlockDescriptor::partial(
+            PartialShape::Slab { top: false },
+            CubeTextures::uniform("stone"),
+        )
+    }
+
+    pub fn StoneStairsRight() -> Self {
+        BlockDescriptor::partial(
+            PartialShape::Stairs(HorizontalFace::Right),
+            CubeTextures::uniform("stone"),
+        )
+    }
+
+    pub fn StoneStairsLeft() -> Self {
+        BlockDescriptor::partial(
+            PartialShape::Stairs(HorizontalFace::Left),
+            CubeTextures::uniform("stone"),
+        )
+    }
+
+    pub fn StoneStairsBack() -> Self {
+        BlockDescriptor::partial(
+            PartialShape::Stairs(HorizontalFace::Back),
+            CubeTextures::uniform("stone"),
+        )
+    }
+
+    pub fn StoneStairsForward() -> Self {
+        BlockDescriptor::partial(
+            PartialShape::Stairs(HorizontalFace::Forward),
+            CubeTextures::uniform("stone"),
+        )
+    }
+
+    pub fn StoneTopSlab() -> Self {
+        BlockDescriptor::partial(
+            PartialShape::Slab { top: true },
+            CubeTextures::uniform("stone"),
+        )
+    }
+
+    pub fn StonePost() -> Self {
+        BlockDescriptor::partial(PartialShape::FencePost, CubeTextures::uniform("stone"))
+    }
+
+    pub fn GlassPane() -> Self {
+        BlockDescriptor::partial(
+            PartialShape::Pane { along_z: false },
+            CubeTextures::uniform("glass"),
+        )
+    }
+
+    pub fn Magma() -> Self {
+        BlockDescriptor {
+            mesh_builder: MeshBuilder::Cube(CubeTextures::animated("magma", 0.25).into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn Glass() -> Self {
+        BlockDescriptor::translucent(CubeTextures::uniform("glass"))
+    }
+
+    pub fn Ice() -> Self {
+        BlockDescriptor::translucent(CubeTextures::uniform("ice"))
+    }
+
+    pub fn Boulder() -> Self {
+        BlockDescriptor {
+            collision_shape: CollisionShape::Boxes(vec![VoxelBox::new(
+                Vec3::new(-0.4, -0.5, -0.4),
+                Vec3::new(0.4, 0.05, 0.4),
+            )]),
+            ..BlockDescriptor::external(ExternalMesh {
+                texture: "stone",
+                ..Default::default()
+            })
+        }
+    }
 }
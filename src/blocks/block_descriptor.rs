@@ -1,5 +1,7 @@
 // REFACTORED
 
+use super::animation::*;
+use super::atlas::*;
 use super::properties::{
     BlockProperty, DynamicProperty, PassiveProperty, PerceptibleProperty, PhysicalProperty,
 };
@@ -41,124 +43,388 @@ impl<T: BlockProperty> std::ops::Deref for PropertyCollection<T> {
 #[derive(Default)]
 pub struct BlockDescriptor {
     pub mesh_builder: MeshBuilder,
+    pub collision_shape: CollisionShape,
+    pub selection_shape: SelectionShape,
+    pub covering: FaceCovering,
+    pub tinted: TintedFaces,
     pub physical: PropertyCollection<PhysicalProperty>,
     pub passive: PropertyCollection<PassiveProperty>,
     pub perceptible: PropertyCollection<PerceptibleProperty>,
     pub dynamic: PropertyCollection<DynamicProperty>,
 }
 
-/// There are 4 types of voxel meshes in NovaCraft:
+impl BlockDescriptor {
+    /// Describe a partial block. Its mesh, collision shape and covering are all derived from its
+    /// [`PartialShape`].
+    pub fn partial(shape: PartialShape, textures: CubeTextures) -> Self {
+        Self {
+            mesh_builder: MeshBuilder::Partial(PartialMeshBuilder::new(shape, textures)),
+            collision_shape: CollisionShape::Boxes(shape.boxes()),
+            covering: shape.covering(),
+            ..Default::default()
+        }
+    }
+
+    /// Describe a translucent block (glass, ice, etc.). It doesn't cover the blocks next to it.
+    pub fn translucent(textures: CubeTextures) -> Self {
+        Self {
+            mesh_builder: MeshBuilder::Translucent(textures.into()),
+            covering: FaceCovering::NONE,
+            ..Default::default()
+        }
+    }
+
+    /// Describe a block with an [`External`](MeshBuilder::External) mesh. Its model doesn't fill
+    /// its space, so it doesn't cover the blocks next to it.
+    pub fn external(external: ExternalMesh<CubeMeshBuilder>) -> Self {
+        Self {
+            mesh_builder: MeshBuilder::External(external),
+            covering: FaceCovering::NONE,
+            ..Default::default()
+        }
+    }
+
+    /// Prepare the mesh of a block with an [`External`](MeshBuilder::External) mesh after it's
+    /// loaded (see [`ExternalMesh::prepare`]).
+    pub fn prepare_external_mesh(&self, mesh: Mesh, atlas: &AtlasLayout) -> Mesh {
+        match &self.mesh_builder {
+            MeshBuilder::External(external) => external.prepare(mesh, atlas),
+            _ => mesh,
+        }
+    }
+
+    /// The shapes of the block, as they are stored in the [`ShapeRegistry`].
+    pub fn shape(&self) -> BlockShape {
+        let collision = self.collision_shape.boxes().to_vec();
+        let selection = match &self.selection_shape {
+            SelectionShape::None => None,
+            SelectionShape::Box(b) => Some(*b),
+            SelectionShape::FromCollision => collision
+                .iter()
+                .copied()
+                .reduce(|a, b| VoxelBox::new(a.min.min(b.min), a.max.max(b.max))),
+        };
+        BlockShape {
+            collision,
+            selection,
+            covering: self.covering,
+            tinted: self.tinted,
+        }
+    }
+}
+
+/// The shape a block collides with (the player, falling blocks, etc.), relative to its center.
+#[derive(Default, Clone)]
+pub enum CollisionShape {
+    /// Nothing collides with the block.
+    None,
+    /// The whole block.
+    #[default]
+    Full,
+    /// A list of boxes, for blocks that aren't whole (slabs, stairs, etc.).
+    Boxes(Vec<VoxelBox>),
+}
+
+impl CollisionShape {
+    pub fn boxes(&self) -> &[VoxelBox] {
+        match self {
+            Self::None => &[],
+            Self::Full => &[VoxelBox::FULL],
+            Self::Boxes(boxes) => boxes.as_slice(),
+        }
+    }
+}
+
+/// The box a block is targeted by, relative to its center.
+#[derive(Default, Clone, Copy)]
+pub enum SelectionShape {
+    /// The block can't be targeted.
+    None,
+    /// The bounds of the block's [`CollisionShape`] (if it has one).
+    #[default]
+    FromCollision,
+    Box(VoxelBox),
+}
+
+/// Which faces of a block fully cover the face of the adjacent block, so the adjacent face can be
+/// culled. Indexed like [`FACES`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FaceCovering(pub [bool; 6]);
+
+impl FaceCovering {
+    pub const ALL: FaceCovering = FaceCovering([true; 6]);
+    pub const NONE: FaceCovering = FaceCovering([false; 6]);
+
+    /// Only the given faces are covering.
+    pub fn only(faces: &[Face]) -> Self {
+        let mut covering = Self::NONE;
+        for face in faces {
+            covering.0[*face as usize] = true;
+        }
+        covering
+    }
+
+    pub fn covers(&self, face: Face) -> bool {
+        self.0[face as usize]
+    }
+}
+
+impl Default for FaceCovering {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Which faces of a block are tinted by the climate of the column they are in (grass and foliage
+/// for example). Their textures should be gray, the tint gives them their color. Indexed like
+/// [`FACES`].
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct TintedFaces(pub [bool; 6]);
+
+impl TintedFaces {
+    pub const ALL: TintedFaces = TintedFaces([true; 6]);
+    pub const NONE: TintedFaces = TintedFaces([false; 6]);
+
+    /// Only the given faces are tinted.
+    pub fn only(faces: &[Face]) -> Self {
+        let mut tinted = Self::NONE;
+        for face in faces {
+            tinted.0[*face as usize] = true;
+        }
+        tinted
+    }
+
+    pub fn tints(&self, face: Face) -> bool {
+        self.0[face as usize]
+    }
+}
+
+/// The shapes of a block, declared in its [`BlockDescriptor`]. They are stored in the
+/// [`ShapeRegistry`], and everything that needs to know the shape of a block (culling, collision,
+/// targeting) reads them from there.
+#[derive(Clone)]
+pub struct BlockShape {
+    /// The boxes the block collides with, relative to its center. Empty if it doesn't collide.
+    pub collision: Vec<VoxelBox>,
+    /// The box the block is targeted by, relative to its center.
+    pub selection: Option<VoxelBox>,
+    pub covering: FaceCovering,
+    pub tinted: TintedFaces,
+}
+
+impl BlockShape {
+    pub fn is_collidable(&self) -> bool {
+        !self.collision.is_empty()
+    }
+}
+
+/// There are 6 types of voxel meshes in NovaCraft:
 ///     -[`Cube`](MeshBuilder::Cube): This is the most basic type of voxel mesh. This includes all the cubes that need
 ///         have their unseen faces culled.
 ///     -[`XSprite`](MeshBuilder::XSprite): This type of voxel mesh is named after the X shape that two sprites are put
 ///         in to create this mesh. This is commonly used for Foliage, flowers, etc.
+///     -[`Partial`](MeshBuilder::Partial): Blocks that don't fill their whole space, like slabs and stairs. Their
+///         sides are culled like the sides of cubes (see [`PartialShape`]).
+///     -[`Translucent`](MeshBuilder::Translucent): Cubes that can be seen through, like glass and ice. They are
+///         blended, and the faces between two of the same block are culled.
 ///     -[`External` (aka `Custom`)](MeshBuilder::External): This type of voxel mesh includes imported meshes that require
-///         run-time loading after starting the game. As opposed to the two above, which are
+///         run-time loading after starting the game. As opposed to the ones above, which are
 ///         already know and well defined in the [Meshing Backend](`novacraft_meshing_backend`).
+///         All the custom blocks of a chunk are batched into one mesh.
 ///     -[`Null` (aka `Air`)](MeshBuilder::Null): No mesh, this is used for Air. Could be used for invisible blocks.
 #[derive(Default)]
 pub enum MeshBuilder {
     Cube(CubeMeshBuilder),
     XSprite(XSpriteMeshBuilder),
+    Partial(PartialMeshBuilder),
+    Translucent(CubeMeshBuilder),
     External(ExternalMesh<CubeMeshBuilder>),
     #[default]
     Null,
 }
 
-impl Into<VoxelMesh<Mesh>> for MeshBuilder {
-    fn into(self) -> VoxelMesh<Mesh> {
+impl MeshBuilder {
+    /// Build the mesh, with the textures where they are on the atlas.
+    pub fn build(self, atlas: &AtlasLayout) -> VoxelMesh<Mesh> {
         match self {
             Self::Null => VoxelMesh::Null,
-            Self::Cube(t) => VoxelMesh::NormalCube(t.into()),
-            Self::XSprite(t) => VoxelMesh::XSprite(t.into()),
-            Self::External(t) => VoxelMesh::CustomMesh(t.into()),
+            Self::Cube(t) => VoxelMesh::NormalCube(t.build(atlas)),
+            Self::XSprite(t) => VoxelMesh::XSprite(t.build(atlas)),
+            Self::Partial(t) => VoxelMesh::Partial(t.build(atlas)),
+            Self::Translucent(t) => VoxelMesh::Translucent(t.build(atlas)),
+            Self::External(t) => VoxelMesh::CustomMesh(t.alt_mesh.build(atlas)),
         }
     }
 }
 
-/// Simple wrapper made to define the UV coordinates of a cubic voxel mesh.
-pub struct CubeTextureCords {
-    pub top: AtlasCords,
-    pub bottom: AtlasCords,
-    pub right: AtlasCords,
-    pub left: AtlasCords,
-    pub back: AtlasCords,
-    pub forward: AtlasCords,
+/// Simple wrapper made to define the textures of a cubic voxel mesh, by name (see
+/// [`atlas`](super::atlas)). Each face can be animated (see [`BlockTexture`]).
+pub struct CubeTextures {
+    pub top: BlockTexture,
+    pub bottom: BlockTexture,
+    pub right: BlockTexture,
+    pub left: BlockTexture,
+    pub back: BlockTexture,
+    pub forward: BlockTexture,
 }
 
-pub struct XSpriteTextureCords {
-    pub sprite: AtlasCords,
+pub struct XSpriteTextures {
+    pub sprite: TextureName,
 }
 
-impl Into<CubeMeshBuilder> for CubeTextureCords {
+impl Into<CubeMeshBuilder> for CubeTextures {
     fn into(self) -> CubeMeshBuilder {
-        CubeMeshBuilder::from_cube_texture_cords(self)
+        CubeMeshBuilder::from_cube_textures(self)
     }
 }
 
-impl Into<XSpriteMeshBuilder> for XSpriteTextureCords {
+impl Into<XSpriteMeshBuilder> for XSpriteTextures {
     fn into(self) -> XSpriteMeshBuilder {
-        XSpriteMeshBuilder::from_xsprite_texture_cords(self)
+        XSpriteMeshBuilder::from_xsprite_textures(self)
     }
 }
 
-impl Into<Mesh> for ExternalMesh<CubeMeshBuilder> {
-    fn into(self) -> Mesh {
-        return self.alt_mesh.into();
+impl XSpriteTextures {
+    pub const fn uniform(texture: TextureName) -> Self {
+        Self { sprite: texture }
     }
 }
 
-impl XSpriteTextureCords {
-    pub const fn uniform(dims: AtlasCords) -> Self {
-        Self { sprite: dims }
+impl CubeTextures {
+    pub const fn uniform(name: TextureName) -> Self {
+        Self::uniform_texture(BlockTexture::new(name))
     }
-}
 
-impl CubeTextureCords {
-    pub const fn uniform(dims: AtlasCords) -> Self {
+    /// The same animated texture on all of the faces, each frame is shown for `frame_time` seconds.
+    pub const fn animated(name: TextureName, frame_time: f32) -> Self {
+        Self::uniform_texture(BlockTexture::animated(name, frame_time))
+    }
+
+    const fn uniform_texture(texture: BlockTexture) -> Self {
         Self {
-            top: dims,
-            bottom: dims,
-            right: dims,
-            left: dims,
-            back: dims,
-            forward: dims,
+            top: texture,
+            bottom: texture,
+            right: texture,
+            left: texture,
+            back: texture,
+            forward: texture,
         }
     }
 
-    pub fn with_face(mut self, face: Face, dims: AtlasCords) -> Self {
+    pub fn with_face(mut self, face: Face, texture: impl Into<BlockTexture>) -> Self {
+        let texture = texture.into();
         match face {
-            Face::Top => self.top = dims,
-            Face::Bottom => self.bottom = dims,
-            Face::Right => self.right = dims,
-            Face::Left => self.left = dims,
-            Face::Back => self.back = dims,
-            Face::Forward => self.forward = dims,
+            Face::Top => self.top = texture,
+            Face::Bottom => self.bottom = texture,
+            Face::Right => self.right = texture,
+            Face::Left => self.left = texture,
+            Face::Back => self.back = texture,
+            Face::Forward => self.forward = texture,
         }
         self
     }
-}
 
-/// The coordniates for a texture on the texture atlas for voxel meshes.
-pub type AtlasCords = [u32; 2];
+    /// The cells of the textures on the atlas, in the order the mesh generators expect them.
+    fn cords(&self, atlas: &AtlasLayout) -> [(Face, AtlasCords); 6] {
+        self.faces()
+            .map(|(face, texture)| (face, atlas.cords(texture.name)))
+    }
+
+    /// The animations of the faces, indexed by [`Face`].
+    fn animations(&self, atlas: &AtlasLayout) -> [[f32; 2]; 6] {
+        self.faces().map(|(_, texture)| texture.animation(atlas))
+    }
+
+    fn faces(&self) -> [(Face, BlockTexture); 6] {
+        [
+            (Face::Top, self.top),
+            (Face::Bottom, self.bottom),
+            (Face::Right, self.right),
+            (Face::Left, self.left),
+            (Face::Back, self.back),
+            (Face::Forward, self.forward),
+        ]
+    }
+}
 
-const MISSING_TEXTURE: AtlasCords = [TEXTURE_ATLAS_DIMS[0] - 1, TEXTURE_ATLAS_DIMS[1] - 1];
 pub const MISSING_ASSET_MESH: CubeMeshBuilder =
-    CubeMeshBuilder::from_cube_texture_cords(CubeTextureCords::uniform(MISSING_TEXTURE));
+    CubeMeshBuilder::from_cube_textures(CubeTextures::uniform(MISSING_TEXTURE));
 
 /// A struct used to represent a voxel mesh that needs to be loaded from an asset.
 /// [`alt_mesh`](ExternalMesh::alt_mesh) is the Mesh to used while the main mesh is loaded or if it
-/// failed loading. The default alt_mesh is a normal cubic block with the texture of the last
-/// texture in the texture atlas (bottom right corner).
-pub struct ExternalMesh<M: Into<Mesh>> {
+/// failed loading. The default alt_mesh is a normal cubic block with the missing texture.
+/// The loaded mesh is textured with [`texture`](ExternalMesh::texture), its UVs are in the texture
+/// and they are moved to the cell of the texture on the atlas.
+pub struct ExternalMesh<M> {
     pub alt_mesh: M,
+    /// Applied to the loaded mesh, to fit it in the voxel (relative to the center of the voxel).
+    pub transform: Transform,
+    pub texture: TextureName,
 }
 
 impl Default for ExternalMesh<CubeMeshBuilder> {
     fn default() -> Self {
         Self {
             alt_mesh: MISSING_ASSET_MESH,
+            transform: Transform::IDENTITY,
+            texture: MISSING_TEXTURE,
+        }
+    }
+}
+
+impl<M> ExternalMesh<M> {
+    /// Prepare a loaded mesh to be batched with the other custom blocks of a chunk: apply the
+    /// transform, move its UVs to its texture on the atlas, and give it the attributes block meshes
+    /// have and imported meshes usually don't.
+    pub fn prepare(&self, mut mesh: Mesh, atlas: &AtlasLayout) -> Mesh {
+        if mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_none() {
+            mesh.duplicate_vertices();
+            mesh.compute_flat_normals();
+        }
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            for position in positions.iter_mut() {
+                *position = self.transform.transform_point((*position).into()).into();
+            }
+        }
+        if let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_NORMAL)
+        {
+            for normal in normals.iter_mut() {
+                let scaled = Vec3::from(*normal) / self.transform.scale;
+                *normal = (self.transform.rotation * scaled)
+                    .normalize_or_zero()
+                    .into();
+            }
+        }
+        let vertex_count = mesh.count_vertices();
+        let atlas_dims = Vec2::from(atlas.dims().map(|d| d as f32));
+        let cell = Vec2::from(atlas.cords(self.texture).map(|c| c as f32));
+        let padding = atlas.padding();
+        let to_atlas = |uv: [f32; 2]| -> [f32; 2] {
+            let uv = Vec2::from(uv).clamp(Vec2::ZERO, Vec2::ONE);
+            ((cell + padding + uv * (1.0 - 2.0 * padding)) / atlas_dims).into()
+        };
+        match mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => {
+                uvs.iter_mut().for_each(|uv| *uv = to_atlas(*uv));
+            }
+            _ => mesh.insert_attribute(
+                Mesh::ATTRIBUTE_UV_0,
+                vec![to_atlas([0.5, 0.5]); vertex_count],
+            ),
         }
+        if mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_none() {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_COLOR,
+                vec![[COLOR_INTENSITY, COLOR_INTENSITY, COLOR_INTENSITY, ALPHA]; vertex_count],
+            );
+        }
+        if mesh.attribute(ATTRIBUTE_ANIMATION).is_none() {
+            mesh.insert_attribute(ATTRIBUTE_ANIMATION, vec![NO_ANIMATION; vertex_count]);
+        }
+        mesh
     }
 }
 
@@ -167,44 +433,37 @@ impl Default for ExternalMesh<CubeMeshBuilder> {
 pub struct CubeMeshBuilder {
     voxel_dims: [f32; 3],
     voxel_center: [f32; 3],
-    texture_atlas_dims: [u32; 2],
-    padding: f32,
     color_intensity: f32,
     alpha: f32,
-    cube_texture_cords: CubeTextureCords,
+    cube_textures: CubeTextures,
 }
 
 #[allow(dead_code)]
 impl CubeMeshBuilder {
-    pub const fn from_cube_texture_cords(cube_texture_cords: CubeTextureCords) -> CubeMeshBuilder {
+    pub const fn from_cube_textures(cube_textures: CubeTextures) -> CubeMeshBuilder {
         Self {
             voxel_dims: VOXEL_DIMS,
             voxel_center: VOXEL_CENTER,
-            texture_atlas_dims: TEXTURE_ATLAS_DIMS,
-            padding: PADDING,
             color_intensity: COLOR_INTENSITY,
             alpha: ALPHA,
-            cube_texture_cords,
+            cube_textures,
         }
     }
 
-    pub fn build(self) -> Mesh {
-        generate_voxel_mesh(
+    pub fn build(self, atlas: &AtlasLayout) -> Mesh {
+        let mut mesh = generate_voxel_mesh(
             self.voxel_dims,
-            self.texture_atlas_dims,
-            [
-                (Face::Top, self.cube_texture_cords.top),
-                (Face::Bottom, self.cube_texture_cords.bottom),
-                (Face::Right, self.cube_texture_cords.right),
-                (Face::Left, self.cube_texture_cords.left),
-                (Face::Back, self.cube_texture_cords.back),
-                (Face::Forward, self.cube_texture_cords.forward),
-            ],
+            atlas.dims(),
+            self.cube_textures.cords(atlas),
             self.voxel_center,
-            self.padding,
+            atlas.padding(),
             Some(self.color_intensity),
             self.alpha,
-        )
+        );
+        // The quads of the mesh are in the order of the faces.
+        let animations = self.cube_textures.animations(atlas);
+        insert_animation(&mut mesh, |quad| animations[quad]);
+        mesh
     }
 
     pub fn override_alpha(mut self, alpha: f32) -> Self {
@@ -215,14 +474,6 @@ impl CubeMeshBuilder {
         self.color_intensity = color_intensity;
         self
     }
-    pub fn override_padding(mut self, padding: f32) -> Self {
-        self.padding = padding;
-        self
-    }
-    pub fn override_texture_atlas_dims(mut self, texture_atlas_dims: [u32; 2]) -> Self {
-        self.texture_atlas_dims = texture_atlas_dims;
-        self
-    }
     pub fn override_voxel_center(mut self, voxel_center: [f32; 3]) -> Self {
         self.voxel_center = voxel_center;
         self
@@ -238,38 +489,32 @@ impl CubeMeshBuilder {
 pub struct XSpriteMeshBuilder {
     voxel_dims: [f32; 3],
     voxel_center: [f32; 3],
-    texture_atlas_dims: [u32; 2],
-    padding: f32,
     color_intensity: f32,
     alpha: f32,
     xsprite_scale: f32,
-    xsprite_texture_cords: XSpriteTextureCords,
+    xsprite_textures: XSpriteTextures,
 }
 
 #[allow(dead_code)]
 impl XSpriteMeshBuilder {
-    pub fn from_xsprite_texture_cords(
-        xsprite_texture_cords: XSpriteTextureCords,
-    ) -> XSpriteMeshBuilder {
+    pub fn from_xsprite_textures(xsprite_textures: XSpriteTextures) -> XSpriteMeshBuilder {
         Self {
             voxel_dims: VOXEL_DIMS,
             voxel_center: VOXEL_CENTER,
-            texture_atlas_dims: TEXTURE_ATLAS_DIMS,
-            padding: PADDING,
             color_intensity: COLOR_INTENSITY,
             alpha: ALPHA,
             xsprite_scale: XSPRITE_SCALE,
-            xsprite_texture_cords,
+            xsprite_textures,
         }
     }
 
-    pub fn build(self) -> Mesh {
+    pub fn build(self, atlas: &AtlasLayout) -> Mesh {
         generate_xsprite_mesh(
             self.voxel_dims,
-            self.texture_atlas_dims,
-            self.xsprite_texture_cords.sprite,
+            atlas.dims(),
+            atlas.cords(self.xsprite_textures.sprite),
             self.voxel_center,
-            self.padding,
+            atlas.padding(),
             Some(self.color_intensity),
             self.alpha,
             self.xsprite_scale,
@@ -284,14 +529,6 @@ impl XSpriteMeshBuilder {
         self.color_intensity = color_intensity;
         self
     }
-    pub fn override_padding(mut self, padding: f32) -> Self {
-        self.padding = padding;
-        self
-    }
-    pub fn override_texture_atlas_dims(mut self, texture_atlas_dims: [u32; 2]) -> Self {
-        self.texture_atlas_dims = texture_atlas_dims;
-        self
-    }
     pub fn override_voxel_center(mut self, voxel_center: [f32; 3]) -> Self {
         self.voxel_center = voxel_center;
         self
@@ -306,14 +543,134 @@ impl XSpriteMeshBuilder {
     }
 }
 
-impl Into<Mesh> for CubeMeshBuilder {
-    fn into(self) -> Mesh {
-        self.build()
+/// The horizontal sides of a block, the sides stairs can face.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HorizontalFace {
+    Right,
+    Left,
+    Back,
+    Forward,
+}
+
+impl From<HorizontalFace> for Face {
+    fn from(face: HorizontalFace) -> Self {
+        match face {
+            HorizontalFace::Right => Face::Right,
+            HorizontalFace::Left => Face::Left,
+            HorizontalFace::Back => Face::Back,
+            HorizontalFace::Forward => Face::Forward,
+        }
     }
 }
 
-impl Into<Mesh> for XSpriteMeshBuilder {
-    fn into(self) -> Mesh {
-        self.build()
+/// The shapes of partial blocks. Stairs and panes are placed in one orientation.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PartialShape {
+    /// Half a block, the bottom half (`false`) or the top half (`true`).
+    Slab { top: bool },
+    /// A bottom slab with a step on top of it, on the given side.
+    Stairs(HorizontalFace),
+    /// A thin post in the middle of the block, like a fence without connections.
+    FencePost,
+    /// A thin sheet through the middle of the block, along the x axis (`false`) or the z axis
+    /// (`true`).
+    Pane { along_z: bool },
+}
+
+const POST_RADIUS: f32 = 0.125;
+const PANE_RADIUS: f32 = 0.0625;
+
+impl PartialShape {
+    /// The boxes that make up the block, relative to its center.
+    pub fn boxes(&self) -> Vec<VoxelBox> {
+        let bottom_half = VoxelBox::new(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.0, 0.5));
+        match *self {
+            Self::Slab { top: false } => vec![bottom_half],
+            Self::Slab { top: true } => vec![bottom_half.translated(Vec3::Y * 0.5)],
+            Self::Stairs(face) => {
+                let (min, max) = match face {
+                    HorizontalFace::Right => (Vec3::new(0.0, 0.0, -0.5), Vec3::new(0.5, 0.5, 0.5)),
+                    HorizontalFace::Left => (Vec3::new(-0.5, 0.0, -0.5), Vec3::new(0.0, 0.5, 0.5)),
+                    HorizontalFace::Back => (Vec3::new(-0.5, 0.0, 0.0), Vec3::new(0.5, 0.5, 0.5)),
+                    HorizontalFace::Forward => {
+                        (Vec3::new(-0.5, 0.0, -0.5), Vec3::new(0.5, 0.5, 0.0))
+                    }
+                };
+                vec![bottom_half, VoxelBox::new(min, max)]
+            }
+            Self::FencePost => vec![VoxelBox::new(
+                Vec3::new(-POST_RADIUS, -0.5, -POST_RADIUS),
+                Vec3::new(POST_RADIUS, 0.5, POST_RADIUS),
+            )],
+            Self::Pane { along_z: false } => vec![VoxelBox::new(
+                Vec3::new(-0.5, -0.5, -PANE_RADIUS),
+                Vec3::new(0.5, 0.5, PANE_RADIUS),
+            )],
+            Self::Pane { along_z: true } => vec![VoxelBox::new(
+                Vec3::new(-PANE_RADIUS, -0.5, -0.5),
+                Vec3::new(PANE_RADIUS, 0.5, 0.5),
+            )],
+        }
+    }
+
+    /// The sides of the block that are fully covered.
+    pub fn covering(&self) -> FaceCovering {
+        match *self {
+            Self::Slab { top: false } => FaceCovering::only(&[Face::Bottom]),
+            Self::Slab { top: true } => FaceCovering::only(&[Face::Top]),
+            Self::Stairs(face) => FaceCovering::only(&[Face::Bottom, face.into()]),
+            Self::FencePost | Self::Pane { .. } => FaceCovering::NONE,
+        }
+    }
+}
+
+/// Used as a buffer before building the `Partial` mesh of a voxel. By default, it will generate the
+/// voxel mesh using the constants defined in the super module, but they can be overridden.
+pub struct PartialMeshBuilder {
+    voxel_dims: [f32; 3],
+    voxel_center: [f32; 3],
+    color_intensity: f32,
+    alpha: f32,
+    shape: PartialShape,
+    cube_textures: CubeTextures,
+}
+
+#[allow(dead_code)]
+impl PartialMeshBuilder {
+    pub const fn new(shape: PartialShape, cube_textures: CubeTextures) -> Self {
+        Self {
+            voxel_dims: VOXEL_DIMS,
+            voxel_center: VOXEL_CENTER,
+            color_intensity: COLOR_INTENSITY,
+            alpha: ALPHA,
+            shape,
+            cube_textures,
+        }
+    }
+
+    pub fn build(self, atlas: &AtlasLayout) -> Mesh {
+        let mut mesh = generate_partial_mesh(
+            &self.shape.boxes(),
+            self.voxel_dims,
+            atlas.dims(),
+            self.cube_textures.cords(atlas),
+            self.voxel_center,
+            atlas.padding(),
+            Some(self.color_intensity),
+            self.alpha,
+        );
+        // Each box has a quad for each face, in the order of the faces.
+        let animations = self.cube_textures.animations(atlas);
+        insert_animation(&mut mesh, |quad| animations[quad % 6]);
+        mesh
+    }
+
+    pub fn override_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+    pub fn override_color_intensity(mut self, color_intensity: f32) -> Self {
+        self.color_intensity = color_intensity;
+        self
     }
 }
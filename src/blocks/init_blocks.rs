@@ -25,4 +25,17 @@ pub enum __InitBlocks__ {
     Stone(BlockDescriptor),
     Greenery(BlockDescriptor),
     Sand(BlockDescriptor),
+    StoneSlab(BlockDescriptor),
+    StoneStairsBack(BlockDescriptor),
+    Glass(BlockDescriptor),
+    Ice(BlockDescriptor),
+    #[custom_mesh(path = "models/boulder.gltf#Mesh0/Primitive0")]
+    Boulder(BlockDescriptor),
+    Magma(BlockDescriptor),
+    StoneTopSlab(BlockDescriptor),
+    StonePost(BlockDescriptor),
+    GlassPane(BlockDescriptor),
+    StoneStairsRight(BlockDescriptor),
+    StoneStairsLeft(BlockDescriptor),
+    StoneStairsForward(BlockDescriptor),
 }
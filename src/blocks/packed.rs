@@ -0,0 +1,290 @@
+//! Packed vertices. The vertices of cube meshes have full `Float32` positions, normals, UVs and
+//! colors (about 56 bytes each, with the [`ATTRIBUTE_ANIMATION`]), but all they really need is a
+//! small position, the face they are on, the cell of their texture and its corner, and their color.
+//! [`pack_mesh`] packs a mesh into 16 bytes per vertex, and the vertex shader of the
+//! [`PackedBlockMaterial`] unpacks it.
+//! The meshing backend still works on unpacked meshes (see
+//! [`UnpackedMesh`](crate::chunk::UnpackedMesh)), the packed mesh is only what is rendered. It's
+//! optional, see [`RenderSettings::packed_vertices`](crate::chunk::RenderSettings::packed_vertices).
+use super::animation::ATTRIBUTE_ANIMATION;
+use super::atlas::AtlasLayout;
+use crate::prelude::*;
+use bevy::asset::load_internal_asset;
+use bevy::pbr::{
+    ExtendedMaterial, MaterialExtension, MaterialExtensionKey, MaterialExtensionPipeline,
+};
+use bevy::render::mesh::MeshVertexBufferLayout;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderDefVal, ShaderRef, SpecializedMeshPipelineError,
+    VertexFormat,
+};
+
+/// The position of a vertex, in 1/256 of a block and moved by one block (so the sides of the first
+/// block aren't negative), and `face | corner << 3` (see [`Face`], the corner is `u | v << 1`).
+pub const ATTRIBUTE_PACKED_POSITION: MeshVertexAttribute = MeshVertexAttribute::new(
+    "PackedPosition",
+    0x3c9e_71a8_0d42_b6f5,
+    VertexFormat::Uint16x4,
+);
+
+/// The texture of a vertex: `cell | frames << 16 | frame_time << 24`, the cell is its index on
+/// the atlas (row by row), and the frame time is in 1/50 of a second (see [`ATTRIBUTE_ANIMATION`]).
+pub const ATTRIBUTE_PACKED_TEXTURE: MeshVertexAttribute =
+    MeshVertexAttribute::new("PackedTexture", 0x9a47_e2c0_5b18_d36f, VertexFormat::Uint32);
+
+/// The color of a vertex (the tint and the light), a byte for each channel.
+pub const ATTRIBUTE_PACKED_COLOR: MeshVertexAttribute =
+    MeshVertexAttribute::new("PackedColor", 0x61f0_8d3b_c7a2_945e, VertexFormat::Unorm8x4);
+
+// These have to match `packed_block.wgsl`.
+const POSITION_SCALE: f32 = 256.0;
+const POSITION_OFFSET: f32 = 1.0;
+const FRAME_TIME_UNIT: f32 = 0.02;
+
+const PACKED_BLOCK_SHADER: Handle<Shader> =
+    Handle::weak_from_u128(0x8f1d_27b4_6e03_4ac9_b5d8_0e7f_13a6_c452);
+
+/// The material of the blocks with packed vertices.
+pub type PackedBlockMaterial = ExtendedMaterial<StandardMaterial, PackedBlock>;
+
+/// The part of the [`PackedBlockMaterial`] that unpacks the vertices.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct PackedBlock {
+    /// The dimensions of the atlas (in cells), to find the cells on it.
+    #[uniform(100)]
+    pub atlas_dims: Vec2,
+    /// The part of each cell that is gutter (see [`AtlasLayout::padding`]).
+    #[uniform(100)]
+    pub padding: f32,
+}
+
+impl MaterialExtension for PackedBlock {
+    fn vertex_shader() -> ShaderRef {
+        PACKED_BLOCK_SHADER.into()
+    }
+
+    fn prepass_vertex_shader() -> ShaderRef {
+        PACKED_BLOCK_SHADER.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialExtensionPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialExtensionKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if descriptor.vertex.shader != PACKED_BLOCK_SHADER
+            || !layout.contains(ATTRIBUTE_PACKED_POSITION)
+        {
+            return Ok(());
+        }
+        descriptor.vertex.buffers = vec![layout.get_layout(&[
+            ATTRIBUTE_PACKED_POSITION.at_shader_location(0),
+            ATTRIBUTE_PACKED_TEXTURE.at_shader_location(1),
+            ATTRIBUTE_PACKED_COLOR.at_shader_location(2),
+        ])?];
+        // The mesh doesn't have the standard attributes, so the standard shaders don't know that
+        // the vertices have UVs and colors.
+        let mut shader_defs = vec![&mut descriptor.vertex.shader_defs];
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            shader_defs.push(&mut fragment.shader_defs);
+        }
+        for defs in shader_defs {
+            for def in ["VERTEX_UVS", "VERTEX_COLORS"] {
+                let def = ShaderDefVal::from(def);
+                if !defs.contains(&def) {
+                    defs.push(def);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pack a cube mesh (see the module docs). Returns `None` if the mesh can't be packed: if it's
+/// missing one of the attributes, if some of its faces aren't aligned to the axes, or if it's too
+/// big.
+pub fn pack_mesh(mesh: &Mesh, atlas: &AtlasLayout) -> Option<Mesh> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+    let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+    else {
+        return None;
+    };
+    let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) else {
+        return None;
+    };
+    let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR)
+    else {
+        return None;
+    };
+    let animations = match mesh.attribute(ATTRIBUTE_ANIMATION) {
+        Some(VertexAttributeValues::Float32x2(animations)) => Some(animations),
+        _ => None,
+    };
+    let atlas_dims = Vec2::from(atlas.dims().map(|d| d as f32));
+
+    let mut packed_positions = Vec::with_capacity(positions.len());
+    let mut packed_textures = Vec::with_capacity(positions.len());
+    for (i, ((position, normal), uv)) in positions.iter().zip(normals).zip(uvs).enumerate() {
+        let face = FACES
+            .into_iter()
+            .find(|face| face.offset().as_vec3().dot(Vec3::from(*normal)) > 0.99)?;
+        let mut packed_position = [0, 0, 0, face as u16];
+        for axis in 0..3 {
+            let value = ((position[axis] + POSITION_OFFSET) * POSITION_SCALE).round();
+            if !(0.0..=u16::MAX as f32).contains(&value) {
+                return None;
+            }
+            packed_position[axis] = value as u16;
+        }
+        // The UVs are inside of the cell, so the cell is the one they are in, and the corner is the
+        // half of the cell they are in.
+        let uv = Vec2::from(*uv) * atlas_dims;
+        let cell = uv.floor();
+        let corner = (uv - cell).cmpge(Vec2::splat(0.5));
+        packed_position[3] |= (corner.x as u16 | (corner.y as u16) << 1) << 3;
+        packed_positions.push(packed_position);
+
+        let [frames, frame_time] = animations.map_or(super::animation::NO_ANIMATION, |a| a[i]);
+        let cell = cell.y as u32 * atlas_dims.x as u32 + cell.x as u32;
+        let frames = (frames as u32).min(u8::MAX as u32);
+        let frame_time = ((frame_time / FRAME_TIME_UNIT).round() as u32).min(u8::MAX as u32);
+        packed_textures.push((cell & 0xffff) | frames << 16 | frame_time << 24);
+    }
+    let packed_colors: Vec<[u8; 4]> = colors
+        .iter()
+        .map(|color| color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8))
+        .collect();
+
+    let mut packed = Mesh::new(mesh.primitive_topology());
+    packed.insert_attribute(
+        ATTRIBUTE_PACKED_POSITION,
+        VertexAttributeValues::Uint16x4(packed_positions),
+    );
+    packed.insert_attribute(ATTRIBUTE_PACKED_TEXTURE, packed_textures);
+    packed.insert_attribute(
+        ATTRIBUTE_PACKED_COLOR,
+        VertexAttributeValues::Unorm8x4(packed_colors),
+    );
+    packed.set_indices(mesh.indices().cloned());
+    Some(packed)
+}
+
+/// Keep the atlas of the packed block materials up to date with the atlas.
+fn update_packed_atlas(
+    atlas: Res<AtlasLayout>,
+    mut materials: ResMut<Assets<PackedBlockMaterial>>,
+) {
+    let [x, y] = atlas.dims();
+    for (_, material) in materials.iter_mut() {
+        material.extension.atlas_dims = Vec2::new(x as f32, y as f32);
+        material.extension.padding = atlas.padding();
+    }
+}
+
+pub(super) struct PackedBlockPlugin;
+
+impl Plugin for PackedBlockPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            PACKED_BLOCK_SHADER,
+            "packed_block.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugins(MaterialPlugin::<PackedBlockMaterial>::default());
+        app.add_systems(
+            Update,
+            update_packed_atlas.run_if(resource_exists_and_changed::<AtlasLayout>()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+    /// A quad on the top face of the block at (1, 3, 0), with its texture in the cell (1, 2) of a
+    /// 4x4 atlas.
+    fn top_quad() -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.5, 3.5, -0.5],
+                [1.5, 3.5, -0.5],
+                [0.5, 3.5, 0.5],
+                [1.5, 3.5, 0.5],
+            ],
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; 4]);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_UV_0,
+            vec![
+                [1.1 / 4.0, 2.1 / 4.0],
+                [1.9 / 4.0, 2.1 / 4.0],
+                [1.1 / 4.0, 2.9 / 4.0],
+                [1.9 / 4.0, 2.9 / 4.0],
+            ],
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![[1.0, 0.5, 0.0, 1.0]; 4]);
+        mesh.set_indices(Some(Indices::U32(vec![0, 2, 1, 1, 2, 3])));
+        mesh
+    }
+
+    #[test]
+    fn pack_mesh_round_trips() {
+        let mesh = top_quad();
+        let packed = pack_mesh(&mesh, &AtlasLayout::with_dims([4, 4])).unwrap();
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            unreachable!();
+        };
+        let Some(VertexAttributeValues::Uint16x4(packed_positions)) =
+            packed.attribute(ATTRIBUTE_PACKED_POSITION)
+        else {
+            panic!("the packed mesh doesn't have packed positions");
+        };
+        let Some(VertexAttributeValues::Uint32(packed_textures)) =
+            packed.attribute(ATTRIBUTE_PACKED_TEXTURE)
+        else {
+            panic!("the packed mesh doesn't have packed textures");
+        };
+        let Some(VertexAttributeValues::Unorm8x4(packed_colors)) =
+            packed.attribute(ATTRIBUTE_PACKED_COLOR)
+        else {
+            panic!("the packed mesh doesn't have packed colors");
+        };
+
+        for (i, packed_position) in packed_positions.iter().enumerate() {
+            // Unpacked like `packed_block.wgsl` does.
+            let position: [f32; 3] =
+                std::array::from_fn(|axis| packed_position[axis] as f32 / POSITION_SCALE - 1.0);
+            assert_eq!(position, positions[i]);
+            let face = packed_position[3] & 0b111;
+            let corner = packed_position[3] >> 3;
+            assert_eq!(face, Face::Top as u16);
+            assert_eq!(corner, i as u16);
+            let cell = packed_textures[i] & 0xffff;
+            assert_eq!(cell, 2 * 4 + 1);
+            assert_eq!(packed_textures[i] >> 16, 1);
+        }
+        assert_eq!(packed_colors, &vec![[255, 128, 0, 255]; 4]);
+        assert!(
+            matches!(packed.indices(), Some(Indices::U32(indices)) if indices == &[0, 2, 1, 1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn pack_mesh_rejects_faces_that_arent_aligned() {
+        let mut mesh = top_quad();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.7, 0.7]; 4]);
+        assert!(pack_mesh(&mesh, &AtlasLayout::with_dims([4, 4])).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+// The vertex shader of the blocks with packed vertices (see `packed.rs`). It unpacks the vertices
+// into the outputs of the standard vertex shaders, so the standard fragment shaders work with them.
+// It's used for the prepass too (shadows), which is why it doesn't need the position attribute.
+#import bevy_pbr::{
+    mesh_functions,
+    view_transformations::position_world_to_clip,
+}
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::prepass_io::VertexOutput
+#else
+#import bevy_pbr::{forward_io::VertexOutput, mesh_view_bindings::globals}
+#endif
+
+struct PackedBlock {
+    atlas_dims: vec2<f32>,
+    padding: f32,
+}
+
+@group(1) @binding(100) var<uniform> packed_block: PackedBlock;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    // [x, y, z, face | corner << 3]
+    @location(0) position: vec4<u32>,
+    // cell | frames << 16 | frame_time << 24
+    @location(1) texture: u32,
+    @location(2) color: vec4<f32>,
+};
+
+// In the same order as `Face`.
+fn face_normal(face: u32) -> vec3<f32> {
+    switch face {
+        case 0u: { return vec3<f32>(0.0, 1.0, 0.0); }
+        case 1u: { return vec3<f32>(0.0, -1.0, 0.0); }
+        case 2u: { return vec3<f32>(1.0, 0.0, 0.0); }
+        case 3u: { return vec3<f32>(-1.0, 0.0, 0.0); }
+        case 4u: { return vec3<f32>(0.0, 0.0, 1.0); }
+        default: { return vec3<f32>(0.0, 0.0, -1.0); }
+    }
+}
+
+// The corner of the cell, inside of its gutter.
+fn cell_uv(cell: u32, corner: u32) -> vec2<f32> {
+    let columns = u32(packed_block.atlas_dims.x);
+    let cell_cords = vec2<f32>(f32(cell % columns), f32(cell / columns));
+    let corner_cords = vec2<f32>(f32(corner & 1u), f32(corner >> 1u));
+    let padding = packed_block.padding;
+    return (cell_cords + padding + corner_cords * (1.0 - 2.0 * padding)) / packed_block.atlas_dims;
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let model = mesh_functions::get_model_matrix(vertex.instance_index);
+    let position = vec3<f32>(vertex.position.xyz) / 256.0 - 1.0;
+    let face = vertex.position.w & 7u;
+    let corner = vertex.position.w >> 3u;
+
+    // The frames of a texture are in consecutive cells (like in `block.wgsl`).
+    var cell = vertex.texture & 0xffffu;
+#ifndef PREPASS_PIPELINE
+    let frames = (vertex.texture >> 16u) & 0xffu;
+    let frame_time = f32(vertex.texture >> 24u) * 0.02;
+    if frames > 1u && frame_time > 0.0 {
+        cell += u32(globals.time / frame_time) % frames;
+    }
+#endif
+
+    out.world_position = mesh_functions::mesh_position_local_to_world(
+        model,
+        vec4<f32>(position, 1.0)
+    );
+    out.position = position_world_to_clip(out.world_position.xyz);
+#ifdef MOTION_VECTOR_PREPASS
+    out.previous_world_position = mesh_functions::mesh_position_local_to_world(
+        mesh_functions::get_previous_model_matrix(vertex.instance_index),
+        vec4<f32>(position, 1.0)
+    );
+#endif
+#ifdef DEPTH_CLAMP_ORTHO
+    out.clip_position_unclamped = out.position;
+    out.position.z = min(out.position.z, 1.0);
+#endif
+#ifdef VERTEX_UVS
+    out.uv = cell_uv(cell, corner);
+#endif
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+#ifdef PREPASS_PIPELINE
+#ifdef NORMAL_PREPASS_OR_DEFERRED_PREPASS
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        face_normal(face),
+        vertex.instance_index
+    );
+#endif
+#else
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        face_normal(face),
+        vertex.instance_index
+    );
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+    return out;
+}
@@ -54,12 +54,4 @@ impl BlockPropertyRegistry<PhysicalProperty> {
             _ => BLOCK_DENSITY,
         }
     }
-
-    pub fn is_collidable(block: &Block) -> bool {
-        match block {
-            Block::AIR => false,
-            Block::GREENERY => false,
-            _ => true,
-        }
-    }
 }
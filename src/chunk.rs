@@ -1,28 +1,49 @@
 pub mod block_update;
 mod chunk_queue;
 pub mod chunkmd;
+mod collision;
 mod falling_block;
+mod height_map;
 mod introduce;
+mod lod;
+mod mesh_jobs;
 mod misc;
+mod occlusion;
 mod smooth_lighting;
 mod spawn;
 mod update_chunks;
-
-pub use self::chunk_queue::ComputeChunk;
+mod voxel_world;
+
+pub use self::chunk_queue::{ChunkQueue, ComputeChunk, NOISE_SEED};
+pub use self::collision::{chunk_collider, ToRebuildCollider};
+pub use self::introduce::{IntroduceMetrics, IntroduceScheduler};
+pub use self::lod::{Lod, LodChild, LodLevel, LodSubChunk, DEFAULT_LOD_DISTANCES};
+pub use self::mesh_jobs::{MeshJob, MeshJobKind, SharedMeshRegistry};
+pub use self::occlusion::ChunkVisibility;
+pub use self::voxel_world::{VoxelRayHit, VoxelWorld};
+use crate::blocks::{
+    animation::AnimatedBlockMaterial, atlas::BLOCK_ATLAS_IMAGE, meshreg::MeshRegistry,
+    packed::PackedBlockMaterial,
+};
 use crate::prelude::*;
 use crate::terrain::TerrainConfig;
 use crate::{blocks::Block, utils::get_neighboring_chunk_cords};
+use bevy::render::view::VisibilitySystems;
 use bevy::utils::hashbrown::HashMap;
-use bevy_xpbd_3d::prelude::AsyncCollider;
 use block_update::*;
 use smooth_lighting::*;
 use std::sync::{Arc, RwLock};
 
 use chunk_queue::*;
+use collision::*;
 pub use falling_block::*;
+use height_map::update_height_maps;
+pub use height_map::{blocks_precipitation, HeightMap};
 use introduce::*;
+use lod::*;
+use mesh_jobs::*;
 use misc::*;
-use rand::prelude::*;
+use occlusion::*;
 use spawn::*;
 use update_chunks::*;
 
@@ -66,6 +87,24 @@ pub struct CubeChild(pub Entity);
 #[derive(Component)]
 pub struct XSpriteChild(pub Entity);
 
+/// "Partial" refers to the type of subchunk. The component is added to the parent chunk.
+#[derive(Component)]
+pub struct PartialChild(pub Entity);
+
+/// "Translucent" refers to the type of subchunk. The component is added to the parent chunk.
+#[derive(Component)]
+pub struct TranslucentChild(pub Entity);
+
+/// "Custom" refers to the type of subchunk. The component is added to the parent chunk.
+#[derive(Component)]
+pub struct CustomChild(pub Entity);
+
+/// The mesh of a subchunk whose vertices are packed (see [`packed`](crate::blocks::packed)). The
+/// mesh jobs work on it, and its packed version is the one that is rendered. Both are kept in CPU
+/// memory (see [`RenderSettings::packed_vertices`]).
+#[derive(Component)]
+pub struct UnpackedMesh(pub Mesh);
+
 /// Marker component to singal that the entity is a subchunk (child of a parent chunk)
 /// This parent-child heirerchy is necessery because each there are many types of blocks,
 /// and it doesn't often go well when they are all in the same mesh, with the same material.
@@ -88,10 +127,6 @@ pub struct ToConnect;
 #[derive(Component)]
 pub struct ToApplySL(pub BlockPos, pub BlockPos);
 
-/// The groups of a chunk (within the context of [`bevy_xpbd_3d`] [`collision layers`](bevy_xpbd_3d::CollisionLayers)
-#[derive(Component)]
-pub struct ChunkRigidLayers(Vec<crate::player::RigidLayer>);
-
 /// A read only thread safe smart pointer [`Arc`]<[`RwLock`]> to the grids of adjecant chunks.
 #[derive(Component)]
 pub struct AdjChunkGrids {
@@ -140,13 +175,33 @@ pub struct CubeSubChunk;
 #[derive(Component)]
 pub struct XSpriteSubChunk;
 
+/// This component marks a partial type subchunk (slabs, stairs, etc.)
+#[derive(Component)]
+pub struct PartialSubChunk;
+
+/// This component marks a translucent type subchunk (glass, ice, etc.)
+#[derive(Component)]
+pub struct TranslucentSubChunk;
+
+/// This component marks a custom mesh type subchunk (torches, chests, etc.)
+#[derive(Component)]
+pub struct CustomSubChunk;
+
 /// Resource containing the handle to the material of most blocks
 #[derive(Resource)]
-pub struct BlockMaterial(Handle<StandardMaterial>);
+pub struct BlockMaterial(Handle<AnimatedBlockMaterial>);
 
 /// Resource containing the handle to the material of xsprite blocks
 #[derive(Resource)]
-pub struct XSpriteMaterial(Handle<StandardMaterial>);
+pub struct XSpriteMaterial(Handle<AnimatedBlockMaterial>);
+
+/// Resource containing the handle to the material of translucent blocks
+#[derive(Resource)]
+pub struct TranslucentMaterial(Handle<AnimatedBlockMaterial>);
+
+/// Resource containing the handle to the material of blocks with packed vertices
+#[derive(Resource)]
+pub struct PackedMaterial(Handle<PackedBlockMaterial>);
 
 /// Resource that maps a chunk's cords to its entity
 #[derive(Resource, Default)]
@@ -162,6 +217,15 @@ pub struct CurrentChunk(pub ChunkCords);
 pub struct RenderSettings {
     pub render_distance: i32,
     pub sl: Option<SmoothLightingParameters>,
+    /// Chunks farther than the first distance (in chunks) from the player are at half detail, and
+    /// chunks farther than the second are at quarter detail (see [`LodLevel`]).
+    pub lod_distances: [i32; 2],
+    /// Render the cube subchunks with packed vertices (see [`packed`](crate::blocks::packed)).
+    /// Changing it respawns the chunks.
+    /// Packing only saves GPU memory. The mesh jobs need the unpacked mesh of each subchunk, which
+    /// is kept in an [`UnpackedMesh`], and the packed mesh asset stays on the CPU too, so it uses
+    /// more CPU memory than the unpacked meshes alone.
+    pub packed_vertices: bool,
 }
 
 pub struct ChunkPlugin;
@@ -173,6 +237,8 @@ impl Plugin for ChunkPlugin {
             .insert_resource(RenderSettings {
                 render_distance: RENDER_DISTANCE,
                 sl: DEFAULT_SL,
+                lod_distances: DEFAULT_LOD_DISTANCES,
+                packed_vertices: false,
             })
             .insert_resource(ChunkMap {
                 pos_to_ent: bevy::utils::hashbrown::HashMap::with_capacity(
@@ -180,34 +246,91 @@ impl Plugin for ChunkPlugin {
                 ),
             })
             .init_resource::<ChunkQueue>()
+            .init_resource::<SharedMeshRegistry>()
+            .init_resource::<IntroduceScheduler>()
             .insert_resource(LockChunkUpdate::unlocked());
         app.add_systems(
             PreUpdate,
-            // If the render settings have been changed, we need to despawn all chunks (they will
-            // be reloaded thereafter with the new render settings)
-            (despawn_all_chunks.run_if(
-                resource_changed::<RenderSettings>().or_else(resource_changed::<TerrainConfig>()),
-            ),),
+            (
+                share_mesh_registry.run_if(resource_changed::<MeshRegistry>()),
+                // If the terrain config (or the vertex layout) has been changed, we need to despawn
+                // all chunks (they will be reloaded thereafter with the new terrain)
+                despawn_all_chunks
+                    .run_if(vertex_layout_changed.or_else(resource_changed::<TerrainConfig>())),
+                // Changes to the render distance are handled by `queue_spawn_despawn_chunks`, but
+                // changes to the smooth lighting need to re-light the loaded chunks
+                relight_chunks_after_sl_change.run_if(resource_changed::<RenderSettings>()),
+            ),
         );
         app.add_systems(
             Update,
             (
-                // The pipeline of spawning and despawning chunks
-                queue_spawn_despawn_chunks,
-                dequeue_all_chunks.run_if(resource_changed::<ChunkQueue>()),
+                (
+                    // The meshes are computed in `MeshJob`s, a subchunk can only have one at a
+                    // time. The jobs are handled before any chunk is despawned, so a job never
+                    // finishes on a subchunk that is being despawned.
+                    (
+                        handle_mesh_jobs,
+                        apply_deferred,
+                        (
+                            update_cube_chunks,
+                            update_subchunks::<XSpriteSubChunk>(update_xsprite),
+                            update_subchunks::<PartialSubChunk>(update_partial),
+                            update_subchunks::<TranslucentSubChunk>(update_translucent),
+                            update_subchunks::<CustomSubChunk>(update_custom),
+                        ),
+                        apply_deferred,
+                        apply_smooth_lighting_edgecases,
+                    )
+                        .chain()
+                        .run_if(resource_equals(LockChunkUpdate::unlocked())),
+                    apply_deferred,
+                    // The pipeline of spawning and despawning chunks
+                    queue_spawn_despawn_chunks,
+                    apply_deferred,
+                    // Chunks change their level of detail as the player moves. Subchunks are
+                    // swapped after the mesh jobs, and only on chunks that are still in range.
+                    (update_chunk_lods, handle_lod_tasks),
+                )
+                    .chain(),
+                cancel_out_of_range_chunk_tasks.run_if(
+                    resource_changed::<CurrentChunk>()
+                        .or_else(resource_changed::<RenderSettings>()),
+                ),
+                dequeue_chunks,
                 handle_chunk_spawn_tasks,
-                ((update_cube_chunks, update_xsprite_chunks), apply_deferred,
-                (apply_smooth_lighting_after_update, apply_smooth_lighting_edgecases))
-                    .chain().run_if(resource_equals(LockChunkUpdate::unlocked())),
-            ).run_if(in_state(AssetLoadingState::Loaded)),
+                update_height_maps,
+                sort_translucent_chunks,
+            )
+                .run_if(in_state(AssetLoadingState::Loaded)),
+        )
+        // More misc systems
+        .add_systems(
+            PostUpdate,
+            (
+                update_close_chunks,
+                apply_deferred,
+                (mark_colliders_to_rebuild, remove_far_chunk_colliders),
+                rebuild_chunk_colliders,
+            )
+                .chain(),
         )
-            // More misc systems 
-        .add_systems(PostUpdate, (update_close_chunks, insert_collider_for_close_chunks))
         .add_systems(
             PostUpdate,
-            ((connect_chunks, introduce_neighboring_chunks, apply_smooth_lighting_after_introduce).run_if(
-                not(any_with_component::<ComputeChunk>())/* .and_then(resource_changed::<OneIn2>()) */,
-            ),),
+            // Chunks that can't be seen from the camera's chunk are hidden. The culling uses the
+            // frustum of this frame, and hides the chunks before their visibility is propagated.
+            (update_visibility_graphs, cull_occluded_chunks)
+                .chain()
+                .after(VisibilitySystems::UpdatePerspectiveFrusta)
+                .after(VisibilitySystems::UpdateProjectionFrusta)
+                .before(VisibilitySystems::VisibilityPropagate)
+                .run_if(in_state(AssetLoadingState::Loaded)),
+        )
+        .add_systems(
+            PostUpdate,
+            // Introducing is budgeted by the `IntroduceScheduler`, so it doesn't wait for the
+            // chunk tasks to finish.
+            (connect_chunks, introduce_neighboring_chunks).chain(),
         )
         .add_systems(PostStartup, setup_texture);
     }
@@ -215,44 +338,101 @@ impl Plugin for ChunkPlugin {
 
 fn setup_texture(
     mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<AnimatedBlockMaterial>>,
+    mut packed_materials: ResMut<Assets<PackedBlockMaterial>>,
 ) {
-    let texture_handle = asset_server.load("blocks.png");
-    let blocks_mat = materials.add(StandardMaterial {
-        base_color_texture: Some(texture_handle.clone()),
-        reflectance: 0.0,
-        alpha_mode: AlphaMode::Mask(0.3),
-        perceptual_roughness: 0.85,
-        ..default()
+    // The atlas is stitched from the block textures once they are loaded.
+    let texture_handle = BLOCK_ATLAS_IMAGE;
+    let blocks_mat = materials.add(AnimatedBlockMaterial {
+        base: StandardMaterial {
+            base_color_texture: Some(texture_handle.clone()),
+            reflectance: 0.0,
+            alpha_mode: AlphaMode::Mask(0.3),
+            perceptual_roughness: 0.85,
+            ..default()
+        },
+        extension: default(),
     });
     commands.insert_resource(BlockMaterial(blocks_mat));
 
-    let xsprite_mat = materials.add(StandardMaterial {
-        base_color_texture: Some(texture_handle),
-        reflectance: 0.0,
-        alpha_mode: AlphaMode::Mask(0.1),
-        perceptual_roughness: 0.85,
-        cull_mode: None,
-        double_sided: true,
-        ..default()
+    // The same as the material of most blocks, for the blocks with packed vertices.
+    let packed_mat = packed_materials.add(PackedBlockMaterial {
+        base: StandardMaterial {
+            base_color_texture: Some(BLOCK_ATLAS_IMAGE),
+            reflectance: 0.0,
+            alpha_mode: AlphaMode::Mask(0.3),
+            perceptual_roughness: 0.85,
+            ..default()
+        },
+        extension: default(),
+    });
+    commands.insert_resource(PackedMaterial(packed_mat));
+
+    let xsprite_mat = materials.add(AnimatedBlockMaterial {
+        base: StandardMaterial {
+            base_color_texture: Some(texture_handle),
+            reflectance: 0.0,
+            alpha_mode: AlphaMode::Mask(0.1),
+            perceptual_roughness: 0.85,
+            cull_mode: None,
+            double_sided: true,
+            ..default()
+        },
+        extension: default(),
     });
     commands.insert_resource(XSpriteMaterial(xsprite_mat));
+
+    // Translucent blocks are blended, their faces are sorted by `sort_translucent_chunks`.
+    let translucent_mat = materials.add(AnimatedBlockMaterial {
+        base: StandardMaterial {
+            base_color_texture: Some(BLOCK_ATLAS_IMAGE),
+            reflectance: 0.1,
+            alpha_mode: AlphaMode::Blend,
+            perceptual_roughness: 0.3,
+            ..default()
+        },
+        extension: default(),
+    });
+    commands.insert_resource(TranslucentMaterial(translucent_mat));
 }
 
 impl AdjChunkGrids {
-    pub fn get_grid_at_direction(&self, dir: crate::prelude::Direction) -> &Arc<RwLock<ChunkGrid>> {
-        let grid_to_return = match dir {
-            North if self.north.is_some() => self.north.as_ref().unwrap(),
-            South if self.south.is_some() => self.north.as_ref().unwrap(),
-            East if self.east.is_some() => self.north.as_ref().unwrap(),
-            West if self.west.is_some() => self.north.as_ref().unwrap(),
-            NoEast if self.no_east.is_some() => self.north.as_ref().unwrap(),
-            NoWest if self.no_west.is_some() => self.north.as_ref().unwrap(),
-            SoEast if self.so_east.is_some() => self.north.as_ref().unwrap(),
-            SoWest if self.so_west.is_some() => self.north.as_ref().unwrap(),
-            _ => panic!("Can't get grid becuase it's not connected."),
+    /// The grid of the adjecant chunk in the direction, if it's connected.
+    pub fn get(&self, dir: crate::prelude::Direction) -> Option<&Arc<RwLock<ChunkGrid>>> {
+        match dir {
+            North => self.north.as_ref(),
+            South => self.south.as_ref(),
+            East => self.east.as_ref(),
+            West => self.west.as_ref(),
+            NoEast => self.no_east.as_ref(),
+            NoWest => self.no_west.as_ref(),
+            SoEast => self.so_east.as_ref(),
+            SoWest => self.so_west.as_ref(),
+        }
+    }
+
+    /// Connect the grid of the adjecant chunk in the direction.
+    pub fn set(&mut self, dir: crate::prelude::Direction, grid: Arc<RwLock<ChunkGrid>>) {
+        let slot = match dir {
+            North => &mut self.north,
+            South => &mut self.south,
+            East => &mut self.east,
+            West => &mut self.west,
+            NoEast => &mut self.no_east,
+            NoWest => &mut self.no_west,
+            SoEast => &mut self.so_east,
+            SoWest => &mut self.so_west,
         };
-        grid_to_return
+        *slot = Some(grid);
+    }
+
+    /// Whether the grids of all of the adjecant chunks are connected.
+    pub fn is_fully_connected(&self) -> bool {
+        DIRECTIONS.into_iter().all(|dir| self.get(dir).is_some())
+    }
+
+    pub fn get_grid_at_direction(&self, dir: crate::prelude::Direction) -> &Arc<RwLock<ChunkGrid>> {
+        self.get(dir)
+            .expect("Can't get grid becuase it's not connected.")
     }
 }
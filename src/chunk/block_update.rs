@@ -1,7 +1,7 @@
 // REFACTORED
 
 use crate::action::properties::DynamicProperty;
-use crate::action::{BreakBlockGlobalEvent, PlaceBlockGlobalEvent};
+use crate::action::BreakBlockGlobalEvent;
 use crate::blocks::{
     existence_conditions::*,
     meshreg::MeshRegistry,
@@ -35,16 +35,25 @@ impl LockChunkUpdate {
 pub fn handle_block_updates(
     mut world_block_update_events: EventReader<WorldBlockUpdate>,
     mut break_block_global_sender: EventWriter<BreakBlockGlobalEvent>,
-    mut place_block_global_sender: EventWriter<PlaceBlockGlobalEvent>,
     mut commands: Commands,
-    chunk_map: Res<ChunkMap>,
+    mut voxel_world: VoxelWorld,
     passive_preg: Res<BlockPropertyRegistry<PassiveProperty>>,
     physical_preg: Res<BlockPropertyRegistry<PhysicalProperty>>,
     dyn_preg: Res<BlockPropertyRegistry<DynamicProperty>>,
     mreg: Res<MeshRegistry>,
-    grids: Query<(&Grid, &CubeChild, &XSpriteChild), With<ParentChunk>>,
+    grids: Query<
+        (
+            &CubeChild,
+            &XSpriteChild,
+            &PartialChild,
+            &TranslucentChild,
+            &CustomChild,
+        ),
+        With<ParentChunk>,
+    >,
     main_mat: Res<BlockMaterial>,
     xsprite_mat: Res<XSpriteMaterial>,
+    translucent_mat: Res<TranslucentMaterial>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
     for wbu in world_block_update_events.read() {
@@ -57,24 +66,35 @@ pub fn handle_block_updates(
         } = *wbu;
 
         let global_pos = BlockGlobalPos::new(block_pos, chunk_cords);
-        let update_chunk_entity = chunk_map.pos_to_ent.get(&chunk_cords).unwrap();
-        let (Grid(chunk_grid), CubeChild(cube_child), XSpriteChild(xsprite_child)) =
-            grids.get(*update_chunk_entity).unwrap();
-        let block_to_update = chunk_grid
-            .read()
-            .unwrap()
-            .get_block(block_pos)
-            .expect("block pos out of bounds");
+        let world_pos = VoxelWorld::to_world(global_pos);
+        let Some(update_chunk_entity) = voxel_world.chunk_entity(chunk_cords) else {
+            continue;
+        };
+        // Chunks that aren't at full detail (see `LodLevel`) don't update their blocks.
+        let Ok((
+            CubeChild(cube_child),
+            XSpriteChild(xsprite_child),
+            PartialChild(partial_child),
+            TranslucentChild(translucent_child),
+            CustomChild(custom_child),
+        )) = grids.get(update_chunk_entity)
+        else {
+            continue;
+        };
+        let Some(block_to_update) = voxel_world.get_block(world_pos) else {
+            continue;
+        };
         let (block_mesh, subchunk_entity, block_material) = match mreg.get_mesh(&block_to_update) {
             VoxelMesh::NormalCube(mesh) => (mesh.clone(), cube_child, &main_mat.0),
             VoxelMesh::XSprite(mesh) => (mesh.clone(), xsprite_child, &xsprite_mat.0),
-            _ => continue,
+            VoxelMesh::Partial(mesh) => (mesh.clone(), partial_child, &main_mat.0),
+            VoxelMesh::Translucent(mesh) => (mesh.clone(), translucent_child, &translucent_mat.0),
+            VoxelMesh::CustomMesh(mesh) => (mesh.clone(), custom_child, &main_mat.0),
+            VoxelMesh::Null => continue,
         };
-        let surrounding_blocks = chunk_grid
-            .read()
-            .unwrap()
-            .get_neighbors_or(block_pos, Block::AIR)
-            .map(|x| Some(x));
+        let surrounding_blocks = voxel_world
+            .get_neighbors(world_pos)
+            .map(|x| Some(x.unwrap_or(Block::AIR)));
 
         // define the data the solver will need
         let solver_data = ExistenceConditionSolverData { surrounding_blocks };
@@ -125,11 +145,7 @@ pub fn handle_block_updates(
                 *subchunk_entity,
             ))
         } else if let Some(alt) = replace_with {
-            place_block_global_sender.send(PlaceBlockGlobalEvent {
-                block: alt,
-                chunk_cords,
-                block_pos,
-            })
+            voxel_world.set_block(world_pos, alt);
         }
     }
 }
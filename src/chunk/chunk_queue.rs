@@ -1,54 +1,197 @@
 // REFACTORED
 
+use super::lod::{meshify_lod_chunk, LodLevel};
 use super::{ChunkCords, ChunkMap, RenderSettings, CHUNK_TOTAL_BLOCKS_USIZE};
 use crate::blocks::meshreg::MeshRegistry;
-use crate::chunk::{Block, CHUNK_DIMS};
+use crate::chunk::{Block, CHUNK_DIMS, HEIGHT, LENGTH, WIDTH};
 use crate::prelude::*;
-use crate::terrain::{generate_chunk, TerrainConfig};
+use crate::terrain::{generate_chunk, generate_tint_map, TerrainConfig};
+use crate::utils::chunk_distance;
+use bevy::math::Affine3A;
+use bevy::render::primitives::{Aabb, Frustum};
 use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy::utils::HashSet;
 use noise::Perlin;
 use std::sync::Arc;
 
-const NOISE_SEED: u32 = 9999;
+/// The seed of the terrain noise.
+pub const NOISE_SEED: u32 = 9999;
+/// The default maximum amount of chunks that can be computed at the same time.
+pub const MAX_CONCURRENT_CHUNK_TASKS: usize = 24;
+/// Chunks that are out of the camera's view are pushed back in the queue, as if they were this
+/// many times farther away.
+pub const OUT_OF_VIEW_PRIORITY_FACTOR: f32 = 3.0;
 
-/// Resource to keep track of the chunks that need to be spawned.
-#[derive(Resource, Default)]
+/// Resource to keep track of the chunks that need to be spawned. It's a priority queue, chunks
+/// that are closer to the player, and in front of the camera, are spawned first.
+#[derive(Resource)]
 pub struct ChunkQueue {
+    /// Sorted by priority (see [`ChunkQueue::prioritize`]), the chunk with the highest priority is
+    /// the last one.
     queue: Vec<ChunkCords>,
+    queued: HashSet<ChunkCords>,
+    /// The maximum amount of [`ComputeChunk`] tasks that can run at the same time.
+    pub max_tasks: usize,
     pub panic_when_cant_find_chunk: bool,
 }
 
+impl Default for ChunkQueue {
+    fn default() -> Self {
+        ChunkQueue {
+            queue: vec![],
+            queued: HashSet::default(),
+            max_tasks: MAX_CONCURRENT_CHUNK_TASKS,
+            panic_when_cant_find_chunk: false,
+        }
+    }
+}
+
 /// Component to hold the data of a task. When completed the task will return all the data needed
-/// to generate a chunk in Some, or None if it didn't succeed.
+/// to generate a chunk (its grid, cords, meshes and [`VisibilityGraph`]) in Some, or None if it
+/// didn't succeed. The second value is the cords of the chunk that is being computed. Despawning
+/// the entity cancels the task.
 #[derive(Component)]
 pub struct ComputeChunk(
     pub  Task<
         Option<(
-            (Mesh, MeshMD<Block>),
             ChunkGrid<Block, CHUNK_TOTAL_BLOCKS_USIZE>,
             ChunkCords,
-            (Mesh, XSpriteMetaData<Block>),
+            ChunkMeshes,
+            VisibilityGraph,
         )>,
     >,
+    pub ChunkCords,
 );
 
+/// The meshes (and their metadata) of the subchunks of a chunk.
+pub enum ChunkMeshes {
+    /// The meshes of a chunk at full detail, a mesh for each type of subchunk.
+    Full {
+        cube: (Mesh, MeshMD<Block>),
+        xsprite: (Mesh, XSpriteMetaData<Block>),
+        partial: (Mesh, PartialMetaData<Block>),
+        translucent: (Mesh, TranslucentMetaData<Block>),
+        custom: (Mesh, CustomMeshMetaData<Block>),
+    },
+    /// The mesh of a chunk that is far from the player, meshed from a downsampled grid (see
+    /// [`lod`](super::lod)).
+    Lod(LodLevel, Mesh),
+}
+
+/// Mesh the grid of a chunk at a level of detail.
+pub fn compute_chunk_meshes(
+    chunk_grid: &ChunkGrid<Block, CHUNK_TOTAL_BLOCKS_USIZE>,
+    chunk_cords: ChunkCords,
+    lod: LodLevel,
+    mreg: &MeshRegistry,
+    smooth_lighting_params: Option<SmoothLightingParameters>,
+) -> Option<ChunkMeshes> {
+    if lod != LodLevel::Full {
+        return meshify_lod_chunk(chunk_grid, chunk_cords, lod, mreg)
+            .map(|mesh| ChunkMeshes::Lod(lod, mesh));
+    }
+    let tint_map = generate_tint_map(chunk_cords, 1);
+    let cube = meshify_cubic_voxels(
+        &[Face::Bottom /* , Forward, Back, Right, Left */],
+        chunk_grid,
+        mreg,
+        MeshingAlgorithm::Culling,
+        smooth_lighting_params,
+        Some(tint_map.clone()),
+    )?;
+    Some(ChunkMeshes::Full {
+        cube,
+        xsprite: meshify_xsprite_voxels(mreg, chunk_grid, Some(tint_map)),
+        partial: meshify_partial_voxels(mreg, chunk_grid),
+        translucent: meshify_translucent_voxels(mreg, chunk_grid),
+        custom: meshify_custom_voxels(mreg, chunk_grid),
+    })
+}
+
+/// The priority of a chunk in the [`ChunkQueue`], lower is sooner. The chunks directly around the
+/// player always come first, after them the chunks are ordered by distance, and chunks that are
+/// not in the camera's view are pushed back.
+pub fn chunk_priority(
+    cords: ChunkCords,
+    current_chunk: ChunkCords,
+    frustum: Option<&Frustum>,
+) -> f32 {
+    let distance = (cords - current_chunk).as_vec2().length();
+    if distance < 1.5 {
+        return distance;
+    }
+    let min = Vec3::new(
+        (cords[0] * WIDTH as i32) as f32 - 0.5,
+        -0.5,
+        (cords[1] * LENGTH as i32) as f32 - 0.5,
+    );
+    let aabb = Aabb::from_min_max(
+        min,
+        min + Vec3::new(WIDTH as f32, HEIGHT as f32, LENGTH as f32),
+    );
+    let in_view = frustum.map_or(true, |frustum| {
+        frustum.intersects_obb(&aabb, &Affine3A::IDENTITY, true, false)
+    });
+    if in_view {
+        distance
+    } else {
+        distance * OUT_OF_VIEW_PRIORITY_FACTOR
+    }
+}
+
 impl ChunkQueue {
     pub fn clear(&mut self) {
         self.queue.clear();
+        self.queued.clear();
     }
 
-    /// Enqueue a new chunk to spawn (using its [`ChunkCords`])
+    /// The amount of chunks waiting to be spawned.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Enqueue a new chunk to spawn (using its [`ChunkCords`]). A chunk that is already in the
+    /// queue won't be enqueued twice.
     pub fn enqueue(&mut self, cords: ChunkCords) {
-        self.queue.push(cords);
+        if self.queued.insert(cords) {
+            self.queue.push(cords);
+        }
     }
 
-    // Dequeue all the pending chunks and handle despawing / spawning them.
-    pub fn dequeue_all<F: Fn(&ChunkCords) -> bool>(
+    /// Remove all the chunks that don't satisfy the predicate from the queue.
+    pub fn retain(&mut self, mut f: impl FnMut(&ChunkCords) -> bool) {
+        let queued = &mut self.queued;
+        self.queue.retain(|cords| {
+            let keep = f(cords);
+            if !keep {
+                queued.remove(cords);
+            }
+            keep
+        });
+    }
+
+    /// Sort the queue by the priority of the chunks (see [`chunk_priority`]).
+    pub fn prioritize(&mut self, current_chunk: ChunkCords, frustum: Option<&Frustum>) {
+        self.queue.sort_by_cached_key(|cords| {
+            // The highest priority (lowest value) goes last.
+            std::cmp::Reverse((chunk_priority(*cords, current_chunk, frustum) * 1000.0) as u32)
+        });
+    }
+
+    /// Dequeue up to `amount` chunks (the ones with the highest priority) and spawn a task to
+    /// compute each of them.
+    pub fn dequeue<F: Fn(&ChunkCords) -> bool>(
         &mut self,
+        amount: usize,
         chunk_map: &mut ChunkMap,
         mut commands: Commands,
         mreg: &Arc<MeshRegistry>,
         condition: Option<F>,
+        current_chunk: ChunkCords,
         render_settings: &RenderSettings,
         terrain_config: &TerrainConfig,
     ) {
@@ -59,7 +202,12 @@ impl ChunkQueue {
         // terrain revamp.
         let noise = Perlin::new(NOISE_SEED);
         let thread_pool = AsyncComputeTaskPool::get();
-        for &chunk_cords in self.queue.as_slice() {
+        let mut spawned = 0;
+        while spawned < amount {
+            let Some(chunk_cords) = self.queue.pop() else {
+                break;
+            };
+            self.queued.remove(&chunk_cords);
             let task;
             if chunk_map.pos_to_ent.contains_key(&chunk_cords) {
                 assert!(
@@ -82,28 +230,36 @@ impl ChunkQueue {
                 .pos_to_ent
                 .insert(chunk_cords, Entity::PLACEHOLDER);
             let smooth_lighting_params = render_settings.sl;
+            let lod = LodLevel::from_distance(
+                chunk_distance(chunk_cords, current_chunk),
+                render_settings.lod_distances,
+            );
             let noise_factor_cont = terrain_config.noise_factor_cont;
             let noise_factor_scale = terrain_config.noise_factor_scale;
             task = thread_pool.spawn(async move {
                 // let grid = generate_flat_chunk(HEIGHT / 2);
-                let grid =
-                    generate_chunk(chunk_cords, &noise, noise_factor_cont, noise_factor_scale);
+                let grid = generate_chunk(
+                    chunk_cords,
+                    NOISE_SEED,
+                    &noise,
+                    noise_factor_cont,
+                    noise_factor_scale,
+                );
                 let chunk_grid = ChunkGrid::new(grid, CHUNK_DIMS);
-                let t = meshify_cubic_voxels(
-                    &[Face::Bottom /* , Forward, Back, Right, Left */],
+                let chunk_meshes = compute_chunk_meshes(
                     &chunk_grid,
+                    chunk_cords,
+                    lod,
                     mreg.as_ref(),
-                    MeshingAlgorithm::Culling,
                     smooth_lighting_params,
                 )?;
-                let custom_voxel_meshes = meshify_xsprite_voxels(mreg.as_ref(), &chunk_grid);
-                Some((t, chunk_grid, chunk_cords, custom_voxel_meshes))
+                let visibility = compute_visibility_graph(mreg.as_ref(), &chunk_grid);
+                Some((chunk_grid, chunk_cords, chunk_meshes, visibility))
             });
 
             // Spawn the task as an entity so it can be polled by a system.
-            commands.spawn(ComputeChunk(task));
+            commands.spawn(ComputeChunk(task, chunk_cords));
+            spawned += 1;
         }
-
-        self.queue.clear();
     }
 }
@@ -11,6 +11,9 @@ pub struct SubChunkMD(pub RwLock<MetaData>);
 pub enum MetaData {
     CubeMD(MeshMD<Block>),
     XSpriteMD(XSpriteMetaData<Block>),
+    PartialMD(PartialMetaData<Block>),
+    TranslucentMD(TranslucentMetaData<Block>),
+    CustomMD(CustomMeshMetaData<Block>),
 }
 
 impl MetaData {
@@ -25,6 +28,21 @@ impl MetaData {
                     .log
                     .push((VoxelChange::Broken, Block::GREENERY, block_pos))
             }
+            Self::PartialMD(partialmd) => {
+                partialmd
+                    .log
+                    .push((VoxelChange::Broken, Block::AIR, block_pos))
+            }
+            Self::TranslucentMD(translucentmd) => {
+                translucentmd
+                    .log
+                    .push((VoxelChange::Broken, Block::AIR, block_pos))
+            }
+            Self::CustomMD(custommd) => {
+                custommd
+                    .log
+                    .push((VoxelChange::Broken, Block::AIR, block_pos))
+            }
         }
     }
 
@@ -35,6 +53,61 @@ impl MetaData {
             Self::XSpriteMD(xspritemd) => {
                 xspritemd.log.push((VoxelChange::Added, block, block_pos))
             }
+            Self::PartialMD(partialmd) => {
+                partialmd.log.push((VoxelChange::Added, block, block_pos))
+            }
+            Self::TranslucentMD(translucentmd) => {
+                translucentmd
+                    .log
+                    .push((VoxelChange::Added, block, block_pos))
+            }
+            Self::CustomMD(custommd) => custommd.log.push((VoxelChange::Added, block, block_pos)),
+        }
+    }
+
+    /// Log that a block next to the block at the position changed, in another chunk. Only partial
+    /// and translucent subchunks are culled by the blocks next to them, so they are rebuilt.
+    pub fn log_neighbor_change(&mut self, block_pos: BlockPos, block: Block) {
+        match self {
+            Self::PartialMD(partialmd) => {
+                partialmd
+                    .log
+                    .push((VoxelChange::AddFaces, block, block_pos))
+            }
+            Self::TranslucentMD(translucentmd) => {
+                translucentmd
+                    .log
+                    .push((VoxelChange::AddFaces, block, block_pos))
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the metadata with the one a [`MeshJob`](super::mesh_jobs::MeshJob) has updated.
+    /// Changes that were logged while the job was running are kept. Returns whether there are
+    /// changes that still need to be applied.
+    pub fn swap_in(&mut self, updated: MetaData) -> bool {
+        match (self, updated) {
+            (Self::CubeMD(meshmd), Self::CubeMD(updated)) => meshmd.swap_in(updated),
+            (Self::XSpriteMD(xspritemd), Self::XSpriteMD(updated)) => xspritemd.swap_in(updated),
+            (Self::PartialMD(partialmd), Self::PartialMD(updated)) => partialmd.swap_in(updated),
+            (Self::TranslucentMD(translucentmd), Self::TranslucentMD(updated)) => {
+                translucentmd.swap_in(updated)
+            }
+            (Self::CustomMD(custommd), Self::CustomMD(updated)) => custommd.swap_in(updated),
+            _ => panic!("Can't swap in the metadata of a different type of subchunk."),
+        }
+    }
+
+    /// Take a snapshot of the metadata, for a [`MeshJob`](super::mesh_jobs::MeshJob) to work on.
+    /// The logged changes are moved into the snapshot.
+    pub fn snapshot(&mut self) -> MetaData {
+        match self {
+            Self::CubeMD(meshmd) => Self::CubeMD(meshmd.snapshot(true)),
+            Self::XSpriteMD(xspritemd) => Self::XSpriteMD(xspritemd.snapshot()),
+            Self::PartialMD(partialmd) => Self::PartialMD(partialmd.snapshot()),
+            Self::TranslucentMD(translucentmd) => Self::TranslucentMD(translucentmd.snapshot()),
+            Self::CustomMD(custommd) => Self::CustomMD(custommd.snapshot()),
         }
     }
 
@@ -0,0 +1,91 @@
+//! Terrain collision. Chunks that are close to the player ([`CloseChunk`]) collide through a
+//! compound of cuboids that is built from their grid, using the collision shapes of the blocks (see
+//! [`ShapeRegistry`]).
+//! Whole blocks are merged into as few cuboids as possible (see [`merge_collision_boxes`]), and the
+//! collider is rebuilt whenever the blocks of the chunk change.
+use super::*;
+use crate::{action::RigidLayer, blocks::ShapeRegistry};
+use bevy_xpbd_3d::prelude::{Collider, CollisionLayers, RigidBody};
+
+/// Component of a parent chunk whose collider needs to be rebuilt from its grid.
+#[derive(Component)]
+pub struct ToRebuildCollider;
+
+/// The compound collider of a chunk's grid, relative to the chunk. `None` if none of the blocks in
+/// the chunk are collidable.
+pub fn chunk_collider(grid: &ChunkGrid) -> Option<Collider> {
+    let shapes = ShapeRegistry::get();
+    let boxes = merge_collision_boxes(grid, |block| shapes.get_shape(&block).collision.as_slice());
+    if boxes.is_empty() {
+        return None;
+    }
+    Some(Collider::compound(
+        boxes
+            .iter()
+            .map(|b| {
+                let size = b.size();
+                (
+                    b.center(),
+                    Quat::IDENTITY,
+                    Collider::cuboid(size.x, size.y, size.z),
+                )
+            })
+            .collect(),
+    ))
+}
+
+/// Mark the close chunks whose blocks were changed (one of their subchunks is [`ToUpdate`]).
+pub(super) fn mark_colliders_to_rebuild(
+    mut commands: Commands,
+    updated_subchunks: Query<&Parent, (With<Subchunk>, Changed<ToUpdate>)>,
+    close_chunks: Query<(), With<CloseChunk>>,
+) {
+    for parent in updated_subchunks.iter() {
+        if close_chunks.contains(parent.get()) {
+            commands.entity(parent.get()).insert(ToRebuildCollider);
+        }
+    }
+}
+
+/// Build the colliders of the chunks that just became close to the player, and rebuild the ones
+/// that were marked with [`ToRebuildCollider`].
+pub(super) fn rebuild_chunk_colliders(
+    mut commands: Commands,
+    chunks: Query<
+        (Entity, &Grid),
+        (
+            With<CloseChunk>,
+            Or<(Added<CloseChunk>, With<ToRebuildCollider>)>,
+        ),
+    >,
+) {
+    for (entity, Grid(grid)) in chunks.iter() {
+        let collider = chunk_collider(&grid.read().unwrap());
+        let mut comm = commands.entity(entity);
+        comm.remove::<ToRebuildCollider>();
+        if let Some(collider) = collider {
+            comm.insert((
+                RigidBody::Static,
+                collider,
+                CollisionLayers::all_masks::<RigidLayer>().add_groups([RigidLayer::Ground]),
+            ));
+        } else {
+            comm.remove::<(RigidBody, Collider)>();
+        }
+    }
+}
+
+/// Remove the colliders of the chunks that aren't close to the player anymore.
+pub(super) fn remove_far_chunk_colliders(
+    mut commands: Commands,
+    mut far_chunks: RemovedComponents<CloseChunk>,
+    chunks: Query<(), With<ParentChunk>>,
+) {
+    for entity in far_chunks.read() {
+        if chunks.contains(entity) {
+            commands
+                .entity(entity)
+                .remove::<(RigidBody, Collider, CollisionLayers, ToRebuildCollider)>();
+        }
+    }
+}
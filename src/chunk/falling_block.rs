@@ -3,6 +3,7 @@
 use super::*;
 use crate::action::VOXEL_DIMS;
 use crate::action::{properties::FallingBlock, PlaceBlockGlobalEvent};
+use crate::blocks::animation::AnimatedBlockMaterial;
 use crate::player::RigidLayer;
 use bevy_xpbd_3d::prelude::ShapeHits;
 use bevy_xpbd_3d::prelude::*;
@@ -44,7 +45,7 @@ pub fn follow_falling_block(
 pub(super) fn spawn_falling_block(
     commands: &mut Commands,
     mesh_handle: Handle<Mesh>,
-    material: Handle<StandardMaterial>,
+    material: Handle<AnimatedBlockMaterial>,
     global_pos: BlockGlobalPos,
     density: f32,
     block: Block,
@@ -55,7 +56,7 @@ pub(super) fn spawn_falling_block(
     let mut caster_shape = collider.clone();
     caster_shape.set_scale(Vec3::splat(0.99), 10);
     commands
-        .spawn(PbrBundle {
+        .spawn(MaterialMeshBundle {
             mesh: mesh_handle,
             material,
             transform: Transform::from_translation(global_block_pos_to_block_trans(
@@ -0,0 +1,62 @@
+//! The height map of a chunk, it's used by systems that need to know what's the highest block in
+//! a column (for example, to stop rain from falling through roofs).
+use crate::blocks::{ShapeRegistry, WorldBlockUpdate};
+
+use super::*;
+
+/// The height of the highest block that blocks precipitation in every column of a chunk.
+/// The height is one above the highest block, so 0 means that the column is empty.
+#[derive(Component, Clone)]
+pub struct HeightMap(pub [u8; (WIDTH * LENGTH) as usize]);
+
+impl HeightMap {
+    /// Compute the height map of a chunk from its grid.
+    pub fn from_grid(grid: &ChunkGrid) -> Self {
+        let mut height_map = HeightMap([0; (WIDTH * LENGTH) as usize]);
+        for x in 0..WIDTH {
+            for z in 0..LENGTH {
+                height_map.update_column(grid, x, z);
+            }
+        }
+        height_map
+    }
+
+    /// Recompute the height of a single column.
+    pub fn update_column(&mut self, grid: &ChunkGrid, x: u32, z: u32) {
+        let height = (0..HEIGHT)
+            .rev()
+            .find(|&y| {
+                grid.get_block([x, y, z].into())
+                    .is_some_and(|block| blocks_precipitation(&block))
+            })
+            .map_or(0, |y| y + 1);
+        self.0[(z * WIDTH + x) as usize] = height as u8;
+    }
+
+    /// Get the height of a column (one above the highest block).
+    pub fn get(&self, x: u32, z: u32) -> u32 {
+        self.0[(z * WIDTH + x) as usize] as u32
+    }
+}
+
+/// Whether a block stops precipitation (and anything else falling from the sky).
+pub fn blocks_precipitation(block: &Block) -> bool {
+    ShapeRegistry::get().get_shape(block).is_collidable()
+}
+
+/// Keep the height maps up to date when blocks change.
+pub(super) fn update_height_maps(
+    mut world_block_update_events: EventReader<WorldBlockUpdate>,
+    chunk_map: Res<ChunkMap>,
+    mut chunks: Query<(&Grid, &mut HeightMap), With<ParentChunk>>,
+) {
+    for wbu in world_block_update_events.read() {
+        let Some(chunk_entity) = chunk_map.pos_to_ent.get(&wbu.chunk_cords) else {
+            continue;
+        };
+        if let Ok((Grid(grid), mut height_map)) = chunks.get_mut(*chunk_entity) {
+            let grid = grid.read().unwrap();
+            height_map.update_column(&grid, wbu.block_pos.x, wbu.block_pos.z);
+        }
+    }
+}
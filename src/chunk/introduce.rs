@@ -1,62 +1,153 @@
 // REFACTORED
 
-use super::{chunkmd::SubChunkMD, *};
-use crate::blocks::MeshRegistry;
+use super::{
+    chunkmd::{MetaData, SubChunkMD},
+    mesh_jobs::*,
+    smooth_lighting::SLGrids,
+    *,
+};
+use crate::blocks::atlas::AtlasLayout;
+use crate::utils::chunk_distance;
+
+/// The default amount of chunks that are introduced every frame.
+pub const INTRODUCE_BUDGET: usize = 12;
+/// The default maximum amount of frames a chunk that's ready to be introduced waits before it is
+/// introduced (even if the budget of the frame has been used up).
+pub const MAX_INTRODUCE_WAIT: u32 = 30;
+
+/// Resource that schedules the introduction of chunks. Every frame, the chunks that are ready to be
+/// introduced (at least one of their adjecant chunks is connected) are introduced nearest-first,
+/// up to the budget. A chunk that has been waiting for [`max_wait`](IntroduceScheduler::max_wait)
+/// frames is introduced regardless of the budget, so every chunk is introduced in a bounded amount
+/// of frames.
+#[derive(Resource)]
+pub struct IntroduceScheduler {
+    /// The amount of chunks to introduce every frame.
+    pub budget: usize,
+    /// The maximum amount of frames a chunk that's ready waits before it is introduced.
+    pub max_wait: u32,
+    /// The amount of frames each ready chunk has been waiting.
+    waiting: HashMap<Entity, u32>,
+    pub metrics: IntroduceMetrics,
+}
+
+/// Metrics about the introduction of chunks (updated every frame).
+#[derive(Default, Clone, Copy, Debug)]
+pub struct IntroduceMetrics {
+    /// Chunks that still need to be introduced to some of their adjecant chunks.
+    pub pending: usize,
+    /// Pending chunks that can be introduced now.
+    pub ready: usize,
+    /// Chunks that were introduced in the last frame.
+    pub introduced: usize,
+    /// Chunks that were introduced in the last frame because they waited for too long.
+    pub overdue: usize,
+    /// The amount of frames the chunk that has been waiting the longest has waited.
+    pub longest_wait: u32,
+    /// Chunks introduced since the game started.
+    pub total_introduced: u64,
+}
+
+impl Default for IntroduceScheduler {
+    fn default() -> Self {
+        IntroduceScheduler {
+            budget: INTRODUCE_BUDGET,
+            max_wait: MAX_INTRODUCE_WAIT,
+            waiting: HashMap::default(),
+            metrics: IntroduceMetrics::default(),
+        }
+    }
+}
 
 /// "Introduce" means cull the sides between the chunks (the intersection). And apply Smooth
-/// Lighting if needed.
+/// Lighting if needed. This system schedules the chunks to introduce (see [`IntroduceScheduler`]).
 pub(super) fn introduce_neighboring_chunks(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mesh_query: Query<(&Handle<Mesh>, &SubChunkMD), With<CubeSubChunk>>,
-    mut to_introduce_query: Query<(Entity, &Children, &mut ToIntroduce, &AdjChunkGrids)>,
-    mreg: Res<MeshRegistry>,
+    meshes: Res<Assets<Mesh>>,
+    mesh_query: Query<(&Handle<Mesh>, &SubChunkMD, Option<&UnpackedMesh>), With<CubeSubChunk>>,
+    busy_query: Query<(), With<MeshJob>>,
+    mut to_introduce_query: Query<(
+        Entity,
+        &Cords,
+        &CubeChild,
+        &mut ToIntroduce,
+        &AdjChunkGrids,
+        &Grid,
+    )>,
+    mut scheduler: ResMut<IntroduceScheduler>,
+    current_chunk: Res<CurrentChunk>,
+    mreg: Res<SharedMeshRegistry>,
+    atlas: Res<AtlasLayout>,
 ) {
-    let mreg = Arc::new(mreg.into_inner().to_owned());
-    for (chunk_entity, subchunks, mut to_introduce, adj_chunk_grids) in
-        to_introduce_query.iter_mut()
+    let scheduler = scheduler.as_mut();
+    let mut metrics = IntroduceMetrics {
+        total_introduced: scheduler.metrics.total_introduced,
+        ..default()
+    };
+    // Chunks that are ready to be introduced, with whether they are overdue.
+    let mut ready = vec![];
+    for (chunk_entity, Cords(cords), CubeChild(cube_child), to_introduce, acj, _) in
+        to_introduce_query.iter()
     {
-        // optimization method. we dont want to overload the system with a lot of chunks to
-        // compute at once, so we only compute 1/12 of them each frame. This is ok because introducing
-        // is not a critical operation that needs to be executed right away.
-        if rand::thread_rng().gen::<f32>() > 0.08 {
+        metrics.pending += 1;
+        if !to_introduce
+            .0
+            .iter()
+            .any(|(_, direction)| acj.get(*direction).is_some())
+        {
+            scheduler.waiting.remove(&chunk_entity);
+            continue;
+        }
+        metrics.ready += 1;
+        let waited = scheduler.waiting.entry(chunk_entity).or_insert(0);
+        metrics.longest_wait = metrics.longest_wait.max(*waited);
+        // If the mesh is already being computed, the chunk will be introduced later.
+        if busy_query.contains(*cube_child) {
+            *waited += 1;
             continue;
         }
+        ready.push((*waited >= scheduler.max_wait, *cords, chunk_entity));
+    }
+    // Overdue chunks go first, then the nearest ones. The cords break ties, so the order is
+    // deterministic.
+    ready.sort_by_key(|(overdue, cords, _)| {
+        (
+            !overdue,
+            chunk_distance(*cords, current_chunk.0),
+            cords.distance_squared(current_chunk.0),
+            cords.x,
+            cords.y,
+        )
+    });
+
+    for (i, (overdue, _, chunk_entity)) in ready.into_iter().enumerate() {
+        if i >= scheduler.budget && !overdue {
+            *scheduler.waiting.get_mut(&chunk_entity).unwrap() += 1;
+            continue;
+        }
+        scheduler.waiting.remove(&chunk_entity);
+        let Ok((_, _, CubeChild(cube_child), mut to_introduce, acj, Grid(grid))) =
+            to_introduce_query.get_mut(chunk_entity)
+        else {
+            continue;
+        };
+        let Ok((mesh_handle, metadata, unpacked)) = mesh_query.get(*cube_child) else {
+            continue;
+        };
+        let Some(mesh) = working_mesh(&meshes, mesh_handle, unpacked) else {
+            continue;
+        };
         let mut introduced = [false; 8];
-        for subchunk in subchunks {
-            if let Ok((mesh_handle, metadata)) = mesh_query.get(*subchunk) {
-                let mesh_ref_mut = meshes.get_mut(mesh_handle).unwrap();
-                // iterate over all of the directions (that represebt adjecant chunks we need to introduce)
-                for (_, direction) in to_introduce.0.iter() {
-                    if let Some(adj_grid) = match direction {
-                        North => &adj_chunk_grids.north,
-                        South => &adj_chunk_grids.south,
-                        East => &adj_chunk_grids.east,
-                        West => &adj_chunk_grids.west,
-                        NoEast => &adj_chunk_grids.no_east,
-                        NoWest => &adj_chunk_grids.no_west,
-                        SoEast => &adj_chunk_grids.so_east,
-                        SoWest => &adj_chunk_grids.so_west,
-                    } {
-                        introduced[(*direction) as usize] = true;
-                        match direction {
-                            North | South | West | East => {
-                                introduce_adjacent_chunks(
-                                    Arc::clone(&mreg).as_ref(),
-                                    mesh_ref_mut,
-                                    &mut metadata
-                                        .0
-                                        .write()
-                                        .expect("a")
-                                        .extract_meshmd_mut()
-                                        .unwrap(),
-                                    (*direction).into(),
-                                    &adj_grid.read().expect("b"),
-                                );
-                            }
-                            _ => {}
-                        }
+        let mut sides_to_introduce = vec![];
+        // iterate over all of the directions (that represebt adjecant chunks we need to introduce)
+        for (_, direction) in to_introduce.0.iter() {
+            if let Some(adj_grid) = acj.get(*direction) {
+                introduced[(*direction) as usize] = true;
+                match direction {
+                    North | South | West | East => {
+                        sides_to_introduce.push((Face::from(*direction), Arc::clone(adj_grid)));
                     }
+                    _ => {}
                 }
             }
         }
@@ -67,9 +158,54 @@ pub(super) fn introduce_neighboring_chunks(
             .copied()
             .filter(|(_, y)| !introduced[*y as usize])
             .collect();
-        // If there are no more adjecant chunks to intreoduce, remove the component
-        if to_introduce.0.is_empty() {
+        // If there are no more adjecant chunks to intreoduce, remove the component, and apply
+        // Smooth Lighting (now that all of the adjecant grids are known).
+        let sl_grids = if to_introduce.0.is_empty() {
             commands.entity(chunk_entity).remove::<ToIntroduce>();
+            SLGrids::new(grid, acj)
+        } else {
+            None
+        };
+        metrics.introduced += 1;
+        metrics.overdue += overdue as usize;
+        metrics.total_introduced += 1;
+        if sides_to_introduce.is_empty() && sl_grids.is_none() {
+            continue;
         }
+        let Some(metadata) = metadata
+            .0
+            .write()
+            .unwrap()
+            .extract_meshmd_mut()
+            .map(|meshmd| meshmd.snapshot(false))
+        else {
+            continue;
+        };
+        let mreg = Arc::clone(&mreg.0);
+        let job = MeshJob::spawn(
+            MeshJobKind::Introduce,
+            mesh.clone(),
+            MetaData::CubeMD(metadata),
+            unpacked.map(|_| atlas.clone()),
+            move |mesh, metadata| {
+                let Some(metadata) = metadata.extract_meshmd_mut() else {
+                    return;
+                };
+                for (side, adj_grid) in sides_to_introduce {
+                    // Copy the adjecant grid, so it isn't locked while introducing.
+                    let adj_grid = adj_grid.read().unwrap().clone();
+                    introduce_adjacent_chunks(mreg.as_ref(), mesh, metadata, side, &adj_grid);
+                }
+                if let Some(sl_grids) = sl_grids {
+                    sl_grids.apply(mreg.as_ref(), mesh, metadata, 0, CHUNK_TOTAL_BLOCKS_USIZE);
+                }
+            },
+        );
+        commands.entity(*cube_child).insert(job);
     }
+    // Forget the chunks that were despawned (or introduced) while waiting.
+    scheduler
+        .waiting
+        .retain(|entity, _| to_introduce_query.contains(*entity));
+    scheduler.metrics = metrics;
 }
@@ -0,0 +1,218 @@
+//! Level of detail. Chunks that are far from the player are meshed from a downsampled grid, where
+//! each voxel stands for 2x2x2 (or 4x4x4) blocks, without sprites and without smooth lighting. A
+//! chunk at a lower level of detail has one subchunk (see [`LodChild`]), scaled up to fit the
+//! chunk, instead of a subchunk for each type of mesh.
+//! A voxel of the downsampled grid is solid if any of its blocks is a cube, so the downsampled
+//! terrain always contains the real one. The sides of chunks that aren't at full detail are never
+//! culled, so together there are no cracks between chunks of different levels of detail.
+use super::{
+    chunk_queue::{compute_chunk_meshes, ChunkMeshes},
+    spawn::{spawn_subchunks, ChunkMaterials},
+    *,
+};
+use crate::terrain::generate_tint_map;
+use crate::utils::chunk_distance;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+
+/// The default [`lod_distances`](RenderSettings::lod_distances): chunks farther than 6 chunks from
+/// the player are at half detail, and chunks farther than 9 are at quarter detail.
+pub const DEFAULT_LOD_DISTANCES: [i32; 2] = [6, 9];
+
+const HALF_TOTAL_BLOCKS: usize = CHUNK_TOTAL_BLOCKS_USIZE / 8;
+const QUARTER_TOTAL_BLOCKS: usize = CHUNK_TOTAL_BLOCKS_USIZE / 64;
+
+/// The level of detail of a chunk.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LodLevel {
+    #[default]
+    Full,
+    /// Each voxel is 2x2x2 blocks.
+    Half,
+    /// Each voxel is 4x4x4 blocks.
+    Quarter,
+}
+
+impl LodLevel {
+    /// The amount of blocks along each axis of a voxel.
+    pub const fn scale(self) -> u32 {
+        match self {
+            Self::Full => 1,
+            Self::Half => 2,
+            Self::Quarter => 4,
+        }
+    }
+
+    /// The level of detail of a chunk that is `distance` chunks away from the player. The chunks
+    /// around the player are always at full detail, so the blocks the player can reach are too.
+    pub fn from_distance(distance: i32, lod_distances: [i32; 2]) -> Self {
+        if distance <= 1 || distance <= lod_distances[0] {
+            Self::Full
+        } else if distance <= lod_distances[1] {
+            Self::Half
+        } else {
+            Self::Quarter
+        }
+    }
+
+    /// The transform of the subchunk of a chunk at this level of detail, relative to the chunk.
+    /// The voxels are scaled up, and the first voxel starts where the first block of the chunk
+    /// starts.
+    pub fn subchunk_transform(self) -> Transform {
+        let scale = self.scale() as f32;
+        Transform::from_translation(Vec3::splat((scale - 1.0) / 2.0)).with_scale(Vec3::splat(scale))
+    }
+}
+
+/// The level of detail of a parent chunk.
+#[derive(Component)]
+pub struct Lod(pub LodLevel);
+
+/// "Lod" refers to the type of subchunk. The component is added to the parent chunk, instead of
+/// the components of the other subchunks, when it isn't at full detail.
+#[derive(Component)]
+pub struct LodChild(pub Entity);
+
+/// This component marks the subchunk of a chunk that isn't at full detail.
+#[derive(Component)]
+pub struct LodSubChunk;
+
+/// Component to hold the task that meshes a parent chunk at a new level of detail. Its subchunks
+/// are replaced when it's done.
+#[derive(Component)]
+pub struct ComputeLod(Task<Option<ChunkMeshes>>);
+
+/// Mesh the cubes of the grid of a chunk, downsampled to a level of detail.
+pub fn meshify_lod_chunk(
+    grid: &ChunkGrid,
+    cords: ChunkCords,
+    lod: LodLevel,
+    mreg: &MeshRegistry,
+) -> Option<Mesh> {
+    match lod {
+        LodLevel::Full => None,
+        LodLevel::Half => meshify_downsampled::<HALF_TOTAL_BLOCKS>(grid, cords, lod, mreg),
+        LodLevel::Quarter => meshify_downsampled::<QUARTER_TOTAL_BLOCKS>(grid, cords, lod, mreg),
+    }
+}
+
+fn meshify_downsampled<const N: usize>(
+    grid: &ChunkGrid,
+    cords: ChunkCords,
+    lod: LodLevel,
+    mreg: &MeshRegistry,
+) -> Option<Mesh> {
+    let lod_grid = downsample::<N>(grid, lod.scale(), mreg);
+    meshify_cubic_voxels(
+        &[Face::Bottom],
+        &lod_grid,
+        mreg,
+        MeshingAlgorithm::Culling,
+        None,
+        Some(generate_tint_map(cords, lod.scale())),
+    )
+    .map(|(mesh, _)| mesh)
+}
+
+/// Downsample the grid of a chunk, each voxel of the downsampled grid is the highest cube among
+/// its `scale`x`scale`x`scale` blocks (so the tops of the columns keep their blocks), or air if
+/// there are none.
+fn downsample<const N: usize>(
+    grid: &ChunkGrid,
+    scale: u32,
+    mreg: &MeshRegistry,
+) -> crate::prelude::ChunkGrid<Block, N> {
+    let dims = CHUNK_DIMS / scale;
+    let mut lod_grid = crate::prelude::ChunkGrid::new([Block::AIR; N], dims);
+    for y in 0..dims.y {
+        for z in 0..dims.z {
+            for x in 0..dims.x {
+                let voxel_pos = BlockPos::new(x, y, z);
+                let block = (0..scale).rev().find_map(|dy| {
+                    (0..scale * scale).find_map(|i| {
+                        let block_pos = voxel_pos * scale + BlockPos::new(i % scale, dy, i / scale);
+                        grid.get_block(block_pos).filter(|block| {
+                            matches!(mreg.get_mesh(block), VoxelMesh::NormalCube(_))
+                        })
+                    })
+                });
+                if let Some(block) = block {
+                    let _ = lod_grid.set_block(block, voxel_pos);
+                }
+            }
+        }
+    }
+    lod_grid
+}
+
+/// Spawn [`ComputeLod`] tasks for the chunks whose level of detail doesn't match their distance
+/// from the player anymore. The grid is copied, so it isn't locked while the task is running.
+pub(super) fn update_chunk_lods(
+    mut commands: Commands,
+    current_chunk: Res<CurrentChunk>,
+    render_settings: Res<RenderSettings>,
+    mreg: Res<SharedMeshRegistry>,
+    chunks: Query<(Entity, &Cords, &Lod, &Grid), (With<ParentChunk>, Without<ComputeLod>)>,
+) {
+    let thread_pool = AsyncComputeTaskPool::get();
+    for (entity, &Cords(cords), Lod(lod), Grid(grid)) in chunks.iter() {
+        let distance = chunk_distance(cords, current_chunk.0);
+        // The chunk is despawned this frame.
+        if distance > render_settings.render_distance {
+            continue;
+        }
+        let new_lod = LodLevel::from_distance(distance, render_settings.lod_distances);
+        if new_lod == *lod {
+            continue;
+        }
+        let mreg = Arc::clone(&mreg.0);
+        let grid = Arc::clone(grid);
+        let smooth_lighting_params = render_settings.sl;
+        let task = thread_pool.spawn(async move {
+            let grid = grid.read().unwrap().clone();
+            compute_chunk_meshes(&grid, cords, new_lod, mreg.as_ref(), smooth_lighting_params)
+        });
+        commands.entity(entity).insert(ComputeLod(task));
+    }
+}
+
+/// Replace the subchunks of the chunks that have been meshed at a new level of detail.
+pub(super) fn handle_lod_tasks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: ChunkMaterials,
+    render_settings: Res<RenderSettings>,
+    mut chunks: Query<(Entity, &Cords, &mut ComputeLod)>,
+) {
+    for (entity, &Cords(cords), mut task) in chunks.iter_mut() {
+        let Some(chunk_meshes) =
+            futures_lite::future::block_on(futures_lite::future::poll_once(&mut task.0))
+        else {
+            continue;
+        };
+        commands.entity(entity).remove::<ComputeLod>();
+        let Some(chunk_meshes) = chunk_meshes else {
+            continue;
+        };
+        // The chunk will be introduced and lit again if it's at full detail.
+        commands.entity(entity).despawn_descendants().remove::<(
+            CubeChild,
+            XSpriteChild,
+            PartialChild,
+            TranslucentChild,
+            CustomChild,
+            LodChild,
+            ToIntroduce,
+            ToApplySL,
+        )>();
+        spawn_subchunks(
+            &mut commands,
+            entity,
+            cords,
+            chunk_meshes,
+            &mut meshes,
+            &materials,
+            render_settings.sl,
+            render_settings.packed_vertices,
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Mesh jobs. Updating, introducing and applying Smooth Lighting to a subchunk's mesh is done off
+//! the main thread, on the [`AsyncComputeTaskPool`]. A job works on a snapshot of the mesh and its
+//! metadata, and the result is swapped in once the job is done. Only one job runs for a subchunk
+//! at a time, changes that are logged while it runs are all applied by the next one. The meshes of
+//! subchunks with packed vertices are packed in the job too.
+use super::{chunkmd::*, *};
+use crate::blocks::{atlas::AtlasLayout, meshreg::MeshRegistry, packed::pack_mesh};
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+
+/// The [`MeshRegistry`], shared with the tasks that need it (so it isn't cloned every frame).
+#[derive(Resource, Default)]
+pub struct SharedMeshRegistry(pub Arc<MeshRegistry>);
+
+/// What a [`MeshJob`] does to the mesh.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MeshJobKind {
+    /// Apply the logged changes ([`ToUpdate`]).
+    Update,
+    /// Cull the sides between the chunk and its adjacent chunks ([`ToIntroduce`]).
+    Introduce,
+    /// Apply Smooth Lighting ([`ToApplySL`]).
+    SmoothLighting,
+}
+
+pub struct MeshJobOutput {
+    kind: MeshJobKind,
+    mesh: Mesh,
+    /// The packed mesh, if the job packs it and it could be packed.
+    packed: Option<Mesh>,
+    metadata: MetaData,
+}
+
+/// Component of a subchunk while a job is computing its mesh.
+#[derive(Component)]
+pub struct MeshJob(Task<MeshJobOutput>);
+
+impl MeshJob {
+    /// Spawn a job that runs `job` on a snapshot of a subchunk's mesh and metadata. If there is an
+    /// atlas to `pack_with`, the mesh is packed after (see [`pack_mesh`]).
+    pub(super) fn spawn<F>(
+        kind: MeshJobKind,
+        mut mesh: Mesh,
+        mut metadata: MetaData,
+        pack_with: Option<AtlasLayout>,
+        job: F,
+    ) -> Self
+    where
+        F: FnOnce(&mut Mesh, &mut MetaData) + Send + 'static,
+    {
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            job(&mut mesh, &mut metadata);
+            let packed = pack_with.and_then(|atlas| pack_mesh(&mesh, &atlas));
+            MeshJobOutput {
+                kind,
+                mesh,
+                packed,
+                metadata,
+            }
+        });
+        MeshJob(task)
+    }
+}
+
+/// Keep the [`SharedMeshRegistry`] up to date with the [`MeshRegistry`] (it changes once, when the
+/// external meshes are loaded).
+pub(super) fn share_mesh_registry(mut commands: Commands, mreg: Res<MeshRegistry>) {
+    commands.insert_resource(SharedMeshRegistry(Arc::new(mreg.clone())));
+}
+
+/// The mesh the jobs of a subchunk work on, its [`UnpackedMesh`] if its vertices are packed.
+pub(super) fn working_mesh<'a>(
+    meshes: &'a Assets<Mesh>,
+    mesh_handle: &Handle<Mesh>,
+    unpacked: Option<&'a UnpackedMesh>,
+) -> Option<&'a Mesh> {
+    match unpacked {
+        Some(UnpackedMesh(mesh)) => Some(mesh),
+        None => meshes.get(mesh_handle),
+    }
+}
+
+/// Swap in the meshes (and metadata) of the jobs that are done. If the mesh of a subchunk with
+/// packed vertices couldn't be packed again, the subchunk goes back to unpacked vertices.
+pub(super) fn handle_mesh_jobs(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    block_material: Res<BlockMaterial>,
+    mut jobs: Query<(
+        Entity,
+        &mut MeshJob,
+        &SubChunkMD,
+        &Handle<Mesh>,
+        Option<&mut UnpackedMesh>,
+    )>,
+) {
+    for (entity, mut job, metadata, mesh_handle, unpacked) in jobs.iter_mut() {
+        let Some(MeshJobOutput {
+            kind,
+            mesh,
+            packed,
+            metadata: updated,
+        }) = futures_lite::future::block_on(futures_lite::future::poll_once(&mut job.0))
+        else {
+            continue;
+        };
+        let pending_changes = metadata.0.write().unwrap().swap_in(updated);
+        let aabb = mesh.compute_aabb();
+        let vertex_count = mesh.count_vertices();
+        let mut comm = commands.entity(entity);
+        comm.remove::<MeshJob>();
+        let render_mesh = match (unpacked, packed) {
+            (Some(mut unpacked), Some(packed)) => {
+                unpacked.0 = mesh;
+                packed
+            }
+            // The packed mesh would be stale, so the unpacked mesh is rendered instead.
+            (Some(_), None) => {
+                warn!("Couldn't pack the vertices of the mesh after updating");
+                comm.remove::<(UnpackedMesh, Handle<PackedBlockMaterial>)>()
+                    .insert(block_material.0.clone());
+                mesh
+            }
+            (None, _) => mesh,
+        };
+        if let Some(mesh_ref_mut) = meshes.get_mut(mesh_handle) {
+            *mesh_ref_mut = render_mesh;
+        }
+        if kind != MeshJobKind::Update {
+            continue;
+        }
+        if let Some(aabb) = aabb {
+            comm.insert(aabb);
+        } else if vertex_count > 0 {
+            warn!("Couldn't compute Aabb for mesh after updating");
+        }
+        // If blocks were changed while the job was running, the subchunk stays `ToUpdate`, and
+        // the next job applies all of the changes at once.
+        if !pending_changes {
+            comm.remove::<ToUpdate>();
+        }
+    }
+}
@@ -1,8 +1,7 @@
 // REFACTORED
 
 use super::{chunk_queue::ChunkQueue, *};
-use crate::{action::RigidLayer, utils::chunk_distance};
-use bevy_xpbd_3d::prelude::{CollisionLayers, ComputedCollider, RigidBody, TriMeshFlags};
+use crate::utils::chunk_distance;
 
 pub(super) fn despawn_all_chunks(
     mut commands: Commands,
@@ -21,6 +20,17 @@ pub(super) fn despawn_all_chunks(
     }
 }
 
+/// Run condition, whether [`RenderSettings::packed_vertices`] has changed since the last time.
+pub(super) fn vertex_layout_changed(
+    render_settings: Res<RenderSettings>,
+    mut last_packed: Local<Option<bool>>,
+) -> bool {
+    let packed = render_settings.packed_vertices;
+    last_packed
+        .replace(packed)
+        .is_some_and(|last_packed| last_packed != packed)
+}
+
 /// A system that "connects" chunks. "connecting" chunk A and chunk B means
 /// clone the grid of chunk A ([`Arc`]) and put it in the corresponding place
 /// in the [`AdjChunkGrids`] of the chunk.
@@ -41,26 +51,9 @@ pub(super) fn connect_chunks(
                     continue;
                 }
                 if let Ok(Grid(adj_grid)) = chunk_grid_query.get(*adj_entity) {
-                    match direction {
-                        North => adj_chunk_grids.north = Some(Arc::clone(adj_grid)),
-                        South => adj_chunk_grids.south = Some(Arc::clone(adj_grid)),
-                        East => adj_chunk_grids.east = Some(Arc::clone(adj_grid)),
-                        West => adj_chunk_grids.west = Some(Arc::clone(adj_grid)),
-                        NoEast => adj_chunk_grids.no_east = Some(Arc::clone(adj_grid)),
-                        NoWest => adj_chunk_grids.no_west = Some(Arc::clone(adj_grid)),
-                        SoEast => adj_chunk_grids.so_east = Some(Arc::clone(adj_grid)),
-                        SoWest => adj_chunk_grids.so_west = Some(Arc::clone(adj_grid)),
-                    }
+                    adj_chunk_grids.set(direction, Arc::clone(adj_grid));
                 }
-                if adj_chunk_grids.north.is_some()
-                    && adj_chunk_grids.south.is_some()
-                    && adj_chunk_grids.west.is_some()
-                    && adj_chunk_grids.east.is_some()
-                    && adj_chunk_grids.no_east.is_some()
-                    && adj_chunk_grids.no_west.is_some()
-                    && adj_chunk_grids.so_east.is_some()
-                    && adj_chunk_grids.so_west.is_some()
-                {
+                if adj_chunk_grids.is_fully_connected() {
                     commands.entity(entity).remove::<ToConnect>();
                 }
             }
@@ -85,39 +78,3 @@ pub(super) fn update_close_chunks(
         }
     }
 }
-
-/// This system inserts the collider for newly added [`CloseChunk`]
-pub(super) fn insert_collider_for_close_chunks(
-    mut commands: Commands,
-    new_close_chunks: Query<&Children, Added<CloseChunk>>,
-    subchunks_query: Query<(Entity, &ChunkRigidLayers), With<Subchunk>>,
-) {
-    for subchunks in new_close_chunks.iter() {
-        for &subchunk_entity in subchunks {
-            if let Ok((entity, rigid_layers)) = subchunks_query.get(subchunk_entity) {
-                commands.entity(entity).insert((
-                    RigidBody::Static,
-                    AsyncCollider(ComputedCollider::TriMeshWithFlags(
-                        TriMeshFlags::MERGE_DUPLICATE_VERTICES,
-                    )),
-                    CollisionLayers::all_masks::<RigidLayer>().add_groups(rigid_layers.0.clone()),
-                ));
-            }
-        }
-    }
-}
-
-// pub(super) fn remove_collider_for_far_away_chunks(
-//     mut commands: Commands,
-//     mut far_away_chunks: RemovedComponents<CloseChunk>,
-//     parent_query: Query<&MainChild>,
-// ) {
-//     for far_away_chunk in far_away_chunks.read() {
-//         if let Ok(MainChild(child)) = parent_query.get(far_away_chunk) {
-//             commands
-//                 .entity(*child)
-//                 .remove::<RigidBody>()
-//                 .remove::<Collider>();
-//         }
-//     }
-// }
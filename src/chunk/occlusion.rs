@@ -0,0 +1,163 @@
+//! Occlusion culling. Each chunk has a [`VisibilityGraph`] that tells which of its sides can see
+//! each other through it. When the camera's frustum or the chunks change, the chunks are traversed
+//! from the camera's chunk, leaving a chunk only through the sides that can be seen from the side
+//! it was entered through, and never going back in a direction that was already taken. Chunks that
+//! weren't reached are hidden, on top of Bevy's frustum culling (which is per subchunk).
+//! Chunks span the whole height of the world, so the traversal is horizontal. Going up out of a
+//! chunk leads to the sky, and from the sky any chunk that is open to it can be seen.
+use super::*;
+use crate::blocks::WorldBlockUpdate;
+use crate::player::PlayerCamera;
+use bevy::math::Affine3A;
+use bevy::render::primitives::{Aabb, Frustum};
+use bevy::utils::hashbrown::HashSet;
+use std::collections::VecDeque;
+
+const HORIZONTAL_FACES: [Face; 4] = [Face::Right, Face::Left, Face::Back, Face::Forward];
+
+/// The visibility graph of a parent chunk, computed when the chunk is meshed and kept up to date
+/// when its blocks change.
+#[derive(Component)]
+pub struct ChunkVisibility(pub VisibilityGraph);
+
+/// Recompute the visibility graphs of the chunks whose blocks changed.
+pub(super) fn update_visibility_graphs(
+    mut world_block_update_events: EventReader<WorldBlockUpdate>,
+    chunk_map: Res<ChunkMap>,
+    mreg: Res<MeshRegistry>,
+    mut chunks: Query<(&Grid, &mut ChunkVisibility), With<ParentChunk>>,
+) {
+    let changed: HashSet<ChunkCords> = world_block_update_events
+        .read()
+        .map(|wbu| wbu.chunk_cords)
+        .collect();
+    for cords in changed {
+        let Some(chunk_entity) = chunk_map.pos_to_ent.get(&cords) else {
+            continue;
+        };
+        if let Ok((Grid(grid), mut visibility)) = chunks.get_mut(*chunk_entity) {
+            let grid = grid.read().unwrap();
+            visibility.0 = compute_visibility_graph(&*mreg, &grid);
+        }
+    }
+}
+
+/// Hide the chunks that can't be seen from the camera's chunk (see the module docs). It runs after
+/// the frusta are updated, so the frustum is the one of this frame.
+pub(super) fn cull_occluded_chunks(
+    chunk_map: Res<ChunkMap>,
+    camera: Query<(&GlobalTransform, Ref<Frustum>), With<PlayerCamera>>,
+    mut chunks: Query<(&Cords, &ChunkVisibility, &HeightMap, &mut Visibility), With<ParentChunk>>,
+    changed_chunks: Query<
+        (),
+        (
+            With<ParentChunk>,
+            Or<(Changed<ChunkVisibility>, Changed<HeightMap>)>,
+        ),
+    >,
+    mut removed_chunks: RemovedComponents<ChunkVisibility>,
+) {
+    let Ok((camera_transform, frustum)) = camera.get_single() else {
+        return;
+    };
+    // The frustum changes when the camera moves (or its projection changes).
+    let chunks_removed = removed_chunks.read().count() > 0;
+    if !frustum.is_changed() && changed_chunks.is_empty() && !chunks_removed {
+        return;
+    }
+    let camera_pos = camera_transform.translation();
+    let camera_chunk = point_to_chunk_cords(camera_pos, CHUNK_DIMS);
+    let block_x = (camera_pos.x + 0.5).floor() as i32 - camera_chunk[0] * WIDTH as i32;
+    let block_z = (camera_pos.z + 0.5).floor() as i32 - camera_chunk[1] * LENGTH as i32;
+    let camera_column_height = chunk_map
+        .pos_to_ent
+        .get(&camera_chunk)
+        .and_then(|entity| chunks.get(*entity).ok())
+        .map(|(_, _, height_map, _)| {
+            height_map.get(
+                block_x.clamp(0, WIDTH as i32 - 1) as u32,
+                block_z.clamp(0, LENGTH as i32 - 1) as u32,
+            )
+        });
+    let Some(camera_column_height) = camera_column_height else {
+        // The camera's chunk hasn't been spawned yet, so there is nothing to start from.
+        for (_, _, _, mut visibility) in chunks.iter_mut() {
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+        return;
+    };
+    // The camera can see the sky if it's above the highest block of its column.
+    let mut sky_reached = camera_pos.y + 0.5 >= camera_column_height as f32;
+
+    let graph_at = |cords: ChunkCords| {
+        let entity = chunk_map.pos_to_ent.get(&cords)?;
+        chunks
+            .get(*entity)
+            .ok()
+            .map(|(_, &ChunkVisibility(graph), _, _)| graph)
+    };
+    let in_view = |cords: ChunkCords| {
+        let min = Vec3::new(
+            (cords[0] * WIDTH as i32) as f32 - 0.5,
+            -0.5,
+            (cords[1] * LENGTH as i32) as f32 - 0.5,
+        );
+        let aabb = Aabb::from_min_max(
+            min,
+            min + Vec3::new(WIDTH as f32, HEIGHT as f32, LENGTH as f32),
+        );
+        frustum.intersects_obb(&aabb, &Affine3A::IDENTITY, true, false)
+    };
+
+    // The chunks to visit, with the side they were entered through, and the directions that were
+    // taken to get to them.
+    let mut queue: VecDeque<(ChunkCords, Option<Face>, u8)> = VecDeque::new();
+    let mut visible = HashSet::new();
+    let mut sky_visited = false;
+    visible.insert(camera_chunk);
+    queue.push_back((camera_chunk, None, 0));
+    loop {
+        if sky_reached && !sky_visited {
+            // Any chunk that is open to the sky can be seen from it.
+            sky_visited = true;
+            for cords in chunk_map.pos_to_ent.keys() {
+                if graph_at(*cords).is_some_and(|graph| graph.connects(Face::Top, Face::Top))
+                    && in_view(*cords)
+                    && visible.insert(*cords)
+                {
+                    queue.push_back((*cords, Some(Face::Top), 0));
+                }
+            }
+        }
+        let Some((cords, entered_through, taken)) = queue.pop_front() else {
+            break;
+        };
+        let Some(graph) = graph_at(cords) else {
+            continue;
+        };
+        if entered_through.is_some_and(|entry| graph.connects(entry, Face::Top)) {
+            sky_reached = true;
+        }
+        for face in HORIZONTAL_FACES {
+            if entered_through.is_some_and(|entry| entry == face || !graph.connects(entry, face))
+                || taken & (1 << face.opposite() as u8) != 0
+            {
+                continue;
+            }
+            let offset = face.offset();
+            let neighbor = cords + IVec2::new(offset.x, offset.z);
+            if !visible.contains(&neighbor) && graph_at(neighbor).is_some() && in_view(neighbor) {
+                visible.insert(neighbor);
+                queue.push_back((neighbor, Some(face.opposite()), taken | 1 << face as u8));
+            }
+        }
+    }
+
+    for (Cords(cords), _, _, mut visibility) in chunks.iter_mut() {
+        visibility.set_if_neq(if visible.contains(cords) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
@@ -1,136 +1,159 @@
-use super::{chunkmd::SubChunkMD, *};
-use crate::blocks::meshreg::MeshRegistry;
+use super::{chunkmd::SubChunkMD, mesh_jobs::*, *};
+use crate::blocks::{atlas::AtlasLayout, meshreg::MeshRegistry, ALPHA, COLOR_INTENSITY};
 
-pub(super) fn apply_smooth_lighting_after_update(
-    mut meshes: ResMut<Assets<Mesh>>,
-    mesh_query: Query<(&Handle<Mesh>, &SubChunkMD, &Parent), With<CubeSubChunk>>,
-    chunks_to_apply_q: Query<(&Children, &AdjChunkGrids, &Grid)>,
-    mut removed_components2: RemovedComponents<ToUpdate>,
-    mreg: Res<MeshRegistry>,
-) {
-    let breg = Arc::new(mreg.into_inner().to_owned());
-    for chunk_entity in removed_components2.read() {
-        let Ok((mesh_handle, metadata, parent)) = mesh_query.get(chunk_entity) else {
-            continue;
-        };
-        if let Ok((_, acj, Grid(grid))) = chunks_to_apply_q.get(parent.get()) {
-            let mesh_ref_mut = meshes.get_mut(mesh_handle).unwrap();
-            let north = acj.north.as_ref().unwrap();
-            let south = acj.south.as_ref().unwrap();
-            let east = acj.east.as_ref().unwrap();
-            let west = acj.west.as_ref().unwrap();
-            let no_east = acj.no_east.as_ref().unwrap();
-            let no_west = acj.no_west.as_ref().unwrap();
-            let so_east = acj.so_east.as_ref().unwrap();
-            let so_west = acj.so_west.as_ref().unwrap();
-            apply_smooth_lighting_with_connected_chunks(
-                Arc::clone(&breg).as_ref(),
-                mesh_ref_mut,
-                &metadata.0.read().unwrap().extract_meshmd().unwrap(),
-                CHUNK_DIMS,
-                0,
-                CHUNK_TOTAL_BLOCKS_USIZE,
-                &*grid.read().unwrap(),
-                Some(&*north.read().unwrap()),
-                Some(&*south.read().unwrap()),
-                Some(&*east.read().unwrap()),
-                Some(&*west.read().unwrap()),
-                Some(&*no_east.read().unwrap()),
-                Some(&*no_west.read().unwrap()),
-                Some(&*so_east.read().unwrap()),
-                Some(&*so_west.read().unwrap()),
-            );
+/// The grids Smooth Lighting needs: the grid of the chunk, and the grids of all of its adjacent
+/// chunks. They are read (and copied) on the thread of the [`MeshJob`].
+pub(super) struct SLGrids {
+    grid: Arc<RwLock<ChunkGrid>>,
+    // In the same order as `DIRECTIONS`
+    adj: [Arc<RwLock<ChunkGrid>>; 8],
+}
+
+impl SLGrids {
+    /// Returns `None` if the chunk isn't connected to all of its adjacent chunks yet.
+    pub(super) fn new(grid: &Arc<RwLock<ChunkGrid>>, acj: &AdjChunkGrids) -> Option<Self> {
+        let adj = DIRECTIONS.map(|dir| acj.get(dir).cloned());
+        if adj.iter().any(Option::is_none) {
+            return None;
         }
+        Some(SLGrids {
+            grid: Arc::clone(grid),
+            adj: adj.map(Option::unwrap),
+        })
     }
-}
 
-pub(super) fn apply_smooth_lighting_after_introduce(
-    mut meshes: ResMut<Assets<Mesh>>,
-    mesh_query: Query<(&Handle<Mesh>, &SubChunkMD, &Parent), With<CubeSubChunk>>,
-    chunks_to_apply_q: Query<(&Children, &AdjChunkGrids, &Grid)>,
-    mut removed_components: RemovedComponents<ToIntroduce>,
-    breg: Res<MeshRegistry>,
-) {
-    let breg = Arc::new(breg.into_inner().to_owned());
-    for chunk_entity in removed_components.read() {
-        let Ok((children, acj, Grid(grid))) = chunks_to_apply_q.get(chunk_entity) else {
-            continue;
-        };
-        for child in children {
-            if let Ok((mesh_handle, metadata, _)) = mesh_query.get(*child) {
-                let mesh_ref_mut = meshes.get_mut(mesh_handle).unwrap();
-                let north = acj.north.as_ref().unwrap();
-                let south = acj.south.as_ref().unwrap();
-                let east = acj.east.as_ref().unwrap();
-                let west = acj.west.as_ref().unwrap();
-                let no_east = acj.no_east.as_ref().unwrap();
-                let no_west = acj.no_west.as_ref().unwrap();
-                let so_east = acj.so_east.as_ref().unwrap();
-                let so_west = acj.so_west.as_ref().unwrap();
-                apply_smooth_lighting_with_connected_chunks(
-                    Arc::clone(&breg).as_ref(),
-                    mesh_ref_mut,
-                    &metadata.0.read().unwrap().extract_meshmd().unwrap(),
-                    CHUNK_DIMS,
-                    0,
-                    CHUNK_TOTAL_BLOCKS_USIZE,
-                    &*grid.read().unwrap(),
-                    Some(&*north.read().unwrap()),
-                    Some(&*south.read().unwrap()),
-                    Some(&*east.read().unwrap()),
-                    Some(&*west.read().unwrap()),
-                    Some(&*no_east.read().unwrap()),
-                    Some(&*no_west.read().unwrap()),
-                    Some(&*so_east.read().unwrap()),
-                    Some(&*so_west.read().unwrap()),
-                );
-            }
+    /// Apply Smooth Lighting to the blocks between the indices `from` and `to`, against snapshots
+    /// of the grids (so they aren't locked while lighting). If Smooth Lighting is disabled in the
+    /// metadata, it is removed from the mesh instead.
+    pub(super) fn apply(
+        &self,
+        mreg: &MeshRegistry,
+        mesh: &mut Mesh,
+        metadata: &MeshMD<Block>,
+        from: usize,
+        to: usize,
+    ) {
+        if metadata.get_sl_params().is_none() {
+            remove_smooth_lighting_keep_tint(
+                mreg,
+                mesh,
+                metadata,
+                &*self.grid.read().unwrap(),
+                [COLOR_INTENSITY, COLOR_INTENSITY, COLOR_INTENSITY, ALPHA],
+            );
+            return;
         }
+        let grid = self.grid.read().unwrap().clone();
+        let adj: Vec<ChunkGrid> = self
+            .adj
+            .iter()
+            .map(|adj_grid| adj_grid.read().unwrap().clone())
+            .collect();
+        apply_smooth_lighting_with_connected_chunks(
+            mreg,
+            mesh,
+            metadata,
+            CHUNK_DIMS,
+            from,
+            to,
+            &grid,
+            Some(&adj[0]),
+            Some(&adj[1]),
+            Some(&adj[2]),
+            Some(&adj[3]),
+            Some(&adj[4]),
+            Some(&adj[5]),
+            Some(&adj[6]),
+            Some(&adj[7]),
+        );
     }
 }
 
+/// Spawn [`MeshJob`]s to apply Smooth Lighting to the chunks where blocks were changed near
+/// their borders (or in an adjacent chunk).
 pub(super) fn apply_smooth_lighting_edgecases(
-    mut meshes: ResMut<Assets<Mesh>>,
     mut commands: Commands,
-    mesh_query: Query<(&Handle<Mesh>, &SubChunkMD, &Parent), With<CubeSubChunk>>,
+    meshes: Res<Assets<Mesh>>,
+    mesh_query: Query<
+        (&Handle<Mesh>, &SubChunkMD, Option<&UnpackedMesh>),
+        (With<CubeSubChunk>, Without<MeshJob>),
+    >,
     chunks_to_apply_q: Query<
-        (Entity, &Children, &AdjChunkGrids, &Grid, &ToApplySL),
+        (Entity, &CubeChild, &AdjChunkGrids, &Grid, &ToApplySL),
         Without<ToConnect>,
     >,
-    breg: Res<MeshRegistry>,
+    mreg: Res<SharedMeshRegistry>,
+    atlas: Res<AtlasLayout>,
+) {
+    for (parent_entity, CubeChild(cube_child), acj, Grid(grid), apply_sl) in
+        chunks_to_apply_q.iter()
+    {
+        let Ok((mesh_handle, metadata, unpacked)) = mesh_query.get(*cube_child) else {
+            continue;
+        };
+        let (Some(mesh), Some(sl_grids)) = (
+            working_mesh(&meshes, mesh_handle, unpacked),
+            SLGrids::new(grid, acj),
+        ) else {
+            continue;
+        };
+        let Some(metadata) = metadata
+            .0
+            .write()
+            .unwrap()
+            .extract_meshmd_mut()
+            .map(|meshmd| meshmd.snapshot(false))
+        else {
+            continue;
+        };
+        let from = pos_to_index(apply_sl.0, CHUNK_DIMS).unwrap_or(0);
+        let to = pos_to_index(apply_sl.1, CHUNK_DIMS).unwrap_or(CHUNK_TOTAL_BLOCKS_USIZE);
+        let mreg = Arc::clone(&mreg.0);
+        let job = MeshJob::spawn(
+            MeshJobKind::SmoothLighting,
+            mesh.clone(),
+            MetaData::CubeMD(metadata),
+            unpacked.map(|_| atlas.clone()),
+            move |mesh, metadata| {
+                if let Some(metadata) = metadata.extract_meshmd() {
+                    sl_grids.apply(mreg.as_ref(), mesh, metadata, from, to);
+                }
+            },
+        );
+        commands.entity(*cube_child).insert(job);
+        commands.entity(parent_entity).remove::<ToApplySL>();
+    }
+}
+
+/// When the Smooth Lighting parameters change (in the [`RenderSettings`]), re-light all of the
+/// loaded chunks in place (or remove their Smooth Lighting if it was disabled), instead of
+/// regenerating them.
+pub(super) fn relight_chunks_after_sl_change(
+    mut commands: Commands,
+    render_settings: Res<RenderSettings>,
+    mut last_sl: Local<Option<Option<SmoothLightingParameters>>>,
+    mesh_query: Query<&SubChunkMD, With<CubeSubChunk>>,
+    chunks_query: Query<(Entity, &CubeChild), With<ParentChunk>>,
 ) {
-    let breg = Arc::new(breg.into_inner().to_owned());
-    for (parent_entity, subchunks, acj, Grid(grid), apply_sl) in chunks_to_apply_q.iter() {
-        for subchunk_entity in subchunks {
-            if let Ok((mesh_handle, metadata, _)) = mesh_query.get(*subchunk_entity) {
-                let mesh_ref_mut = meshes.get_mut(mesh_handle).unwrap();
-                let north = acj.north.as_ref().unwrap();
-                let south = acj.south.as_ref().unwrap();
-                let east = acj.east.as_ref().unwrap();
-                let west = acj.west.as_ref().unwrap();
-                let no_east = acj.no_east.as_ref().unwrap();
-                let no_west = acj.no_west.as_ref().unwrap();
-                let so_east = acj.so_east.as_ref().unwrap();
-                let so_west = acj.so_west.as_ref().unwrap();
-                apply_smooth_lighting_with_connected_chunks(
-                    Arc::clone(&breg).as_ref(),
-                    mesh_ref_mut,
-                    &metadata.0.read().unwrap().extract_meshmd().unwrap(),
-                    CHUNK_DIMS,
-                    pos_to_index(apply_sl.0, CHUNK_DIMS).unwrap_or(0),
-                    pos_to_index(apply_sl.1, CHUNK_DIMS).unwrap_or(CHUNK_TOTAL_BLOCKS_USIZE),
-                    &*grid.read().unwrap(),
-                    Some(&*north.read().unwrap()),
-                    Some(&*south.read().unwrap()),
-                    Some(&*east.read().unwrap()),
-                    Some(&*west.read().unwrap()),
-                    Some(&*no_east.read().unwrap()),
-                    Some(&*no_west.read().unwrap()),
-                    Some(&*so_east.read().unwrap()),
-                    Some(&*so_west.read().unwrap()),
-                );
-                commands.entity(parent_entity).remove::<ToApplySL>();
-            }
+    let sl = render_settings.sl;
+    // The first time this system runs, there's nothing to re-light.
+    let Some(previous_sl) = last_sl.replace(sl) else {
+        return;
+    };
+    if previous_sl == sl {
+        return;
+    }
+    for (chunk_entity, CubeChild(cube_child)) in chunks_query.iter() {
+        let Ok(metadata) = mesh_query.get(*cube_child) else {
+            continue;
+        };
+        if let Some(meshmd) = metadata.0.write().unwrap().extract_meshmd_mut() {
+            meshmd.set_sl_params(sl);
         }
+        // The upper bound is out of the chunk, so the whole chunk is re-lit. Chunks that aren't
+        // connected yet will be re-lit once they are.
+        commands
+            .entity(chunk_entity)
+            .insert(ToApplySL(BlockPos::ZERO, CHUNK_DIMS));
     }
 }
@@ -1,10 +1,13 @@
 #[allow(unused_imports)]
 use bevy::pbr::wireframe::Wireframe;
 
-use crate::{blocks::meshreg::MeshRegistry, utils::chunk_distance};
+use crate::blocks::{atlas::AtlasLayout, packed::pack_mesh};
+use crate::{player::PlayerCamera, utils::chunk_distance};
+use bevy::ecs::system::{EntityCommands, SystemParam};
+use bevy::render::primitives::Frustum;
 
 use super::{
-    chunk_queue::ChunkQueue,
+    chunk_queue::{ChunkMeshes, ChunkQueue},
     chunkmd::{MetaData, SubChunkMD},
     *,
 };
@@ -29,6 +32,8 @@ pub fn queue_spawn_despawn_chunks(
         .iter()
         .filter(|ent| **ent != Entity::PLACEHOLDER)
         .for_each(|ent| commands.entity(*ent).despawn_recursive());
+    // Chunks that are still in the queue might not be in range anymore
+    chunk_queue.retain(|cords| chunk_distance(*cords, current_chunk) <= render_distance);
 
     for u in -render_distance..=render_distance {
         for v in -render_distance..=render_distance {
@@ -40,41 +45,82 @@ pub fn queue_spawn_despawn_chunks(
     }
 }
 
-pub fn dequeue_all_chunks(
+/// Spawn tasks for the chunks with the highest priority in the [`ChunkQueue`], without exceeding
+/// the maximum amount of concurrent tasks.
+pub fn dequeue_chunks(
     commands: Commands,
     mut chunk_map: ResMut<ChunkMap>,
-    mreg: Res<MeshRegistry>,
+    mreg: Res<SharedMeshRegistry>,
     mut chunk_queue: ResMut<ChunkQueue>,
     current_chunk: Res<CurrentChunk>,
     render_settings: Res<RenderSettings>,
     terrain_config: Res<TerrainConfig>,
+    tasks: Query<(), With<ComputeChunk>>,
+    camera: Query<&Frustum, With<PlayerCamera>>,
 ) {
-    let breg = Arc::new(mreg.into_inner().to_owned());
-    chunk_queue.dequeue_all(
+    let running_tasks = tasks.iter().len();
+    if chunk_queue.is_empty() || running_tasks >= chunk_queue.max_tasks {
+        return;
+    }
+    // The player may have moved or turned since the last time, so the priorities are recomputed.
+    chunk_queue.prioritize(current_chunk.0, camera.get_single().ok());
+    let amount = chunk_queue.max_tasks - running_tasks;
+    chunk_queue.dequeue(
+        amount,
         &mut chunk_map,
         commands,
-        &breg,
+        &mreg.0,
         Some(|x: &ChunkCords| {
             chunk_distance(*x, current_chunk.0) < render_settings.render_distance
         }),
+        current_chunk.0,
         &render_settings,
         &terrain_config,
     );
 }
 
+/// Cancel the tasks of chunks that are no longer in range (the player moved away from them while
+/// they were being computed).
+pub fn cancel_out_of_range_chunk_tasks(
+    mut commands: Commands,
+    tasks: Query<(Entity, &ComputeChunk)>,
+    current_chunk: Res<CurrentChunk>,
+    render_settings: Res<RenderSettings>,
+    mut chunk_map: ResMut<ChunkMap>,
+) {
+    for (entity, ComputeChunk(_, cords)) in tasks.iter() {
+        if chunk_distance(*cords, current_chunk.0) > render_settings.render_distance {
+            // Dropping the task cancels it
+            commands.entity(entity).despawn();
+            if chunk_map.pos_to_ent.get(cords) == Some(&Entity::PLACEHOLDER) {
+                chunk_map.pos_to_ent.remove(cords);
+            }
+        }
+    }
+}
+
+/// The materials of the subchunks (and the atlas, to pack their vertices).
+#[derive(SystemParam)]
+pub struct ChunkMaterials<'w> {
+    blocks: Res<'w, BlockMaterial>,
+    xsprite: Res<'w, XSpriteMaterial>,
+    translucent: Res<'w, TranslucentMaterial>,
+    packed: Res<'w, PackedMaterial>,
+    atlas: Res<'w, AtlasLayout>,
+}
+
 pub fn handle_chunk_spawn_tasks(
     mut task_query: Query<(Entity, &mut ComputeChunk)>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    blocks_mat: Res<BlockMaterial>,
-    xsprite_mat: Res<XSpriteMaterial>,
+    materials: ChunkMaterials,
     current_chunk: Res<CurrentChunk>,
     mut chunk_map: ResMut<ChunkMap>,
     render_settings: Res<RenderSettings>,
 ) {
     let current_chunk_cords = current_chunk.0;
     for (ent, mut task) in task_query.iter_mut() {
-        if let Some(Some(((culled_mesh, metadata), grid, cords, (xsprite_mesh, data)))) =
+        if let Some(Some((grid, cords, chunk_meshes, visibility))) =
             futures_lite::future::block_on(futures_lite::future::poll_once(&mut task.0))
         {
             // Remove the task so we don't poll it again
@@ -88,46 +134,16 @@ pub fn handle_chunk_spawn_tasks(
                 continue;
             }
             if let Some(chunk_entity) = chunk_map.pos_to_ent.get_mut(&cords) {
-                let culled_mesh_handle = meshes.add(culled_mesh);
-                let xsprite_mesh_handle = meshes.add(xsprite_mesh);
                 let transform = Transform::from_xyz(
                     (cords[0] * WIDTH as i32) as f32,
                     0.0,
                     (cords[1] * LENGTH as i32) as f32,
                 );
-                let culled_mesh_child = commands
-                    .spawn((
-                        // MainCulledMesh(metadata.into()),
-                        SubChunkMD(MetaData::CubeMD(metadata).into()),
-                        CubeSubChunk,
-                        ChunkRigidLayers(vec![crate::player::RigidLayer::Ground]),
-                        Subchunk,
-                        PbrBundle {
-                            mesh: culled_mesh_handle,
-                            material: blocks_mat.0.clone(),
-                            ..Default::default()
-                        },
-                    ))
-                    .id();
-                let xsprite_mesh_child = commands
-                    .spawn((
-                        PbrBundle {
-                            mesh: xsprite_mesh_handle,
-                            material: xsprite_mat.0.clone(),
-                            ..Default::default()
-                        },
-                        ChunkRigidLayers(vec![crate::player::RigidLayer::GroundNonCollidable]),
-                        Subchunk,
-                        // XSpriteMesh(RwLock::new(data)),
-                        SubChunkMD(MetaData::XSpriteMD(data).into()),
-                        XSpriteSubChunk,
-                    ))
-                    .id();
                 let entity = commands
                     .spawn((
                         ParentChunk,
-                        CubeChild(culled_mesh_child),
-                        XSpriteChild(xsprite_mesh_child),
+                        HeightMap::from_grid(&grid),
+                        ChunkVisibility(visibility),
                         Grid(Arc::new(RwLock::new(grid))),
                         AdjChunkGrids {
                             north: None,
@@ -140,16 +156,6 @@ pub fn handle_chunk_spawn_tasks(
                             so_west: None,
                         },
                         Cords(cords),
-                        ToIntroduce(vec![
-                            (get_neighboring_chunk_cords(cords, East), East),
-                            (get_neighboring_chunk_cords(cords, West), West),
-                            (get_neighboring_chunk_cords(cords, North), North),
-                            (get_neighboring_chunk_cords(cords, South), South),
-                            (get_neighboring_chunk_cords(cords, NoEast), NoEast),
-                            (get_neighboring_chunk_cords(cords, NoWest), NoWest),
-                            (get_neighboring_chunk_cords(cords, SoEast), SoEast),
-                            (get_neighboring_chunk_cords(cords, SoWest), SoWest),
-                        ]),
                         ToConnect,
                         SpatialBundle {
                             transform,
@@ -157,12 +163,201 @@ pub fn handle_chunk_spawn_tasks(
                         },
                     ))
                     .id();
-
-                commands
-                    .entity(entity)
-                    .push_children(&[culled_mesh_child, xsprite_mesh_child]);
+                spawn_subchunks(
+                    &mut commands,
+                    entity,
+                    cords,
+                    chunk_meshes,
+                    &mut meshes,
+                    &materials,
+                    render_settings.sl,
+                    render_settings.packed_vertices,
+                );
                 *chunk_entity = entity;
             }
         }
     }
 }
+
+/// Spawn the subchunks of a parent chunk from its meshes, and add the components that point to
+/// them to the parent. A chunk at full detail has a subchunk for each type of mesh, and needs to be
+/// introduced to its adjecant chunks. A chunk at a lower level of detail has one subchunk.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn spawn_subchunks(
+    commands: &mut Commands,
+    parent: Entity,
+    cords: ChunkCords,
+    chunk_meshes: ChunkMeshes,
+    meshes: &mut Assets<Mesh>,
+    materials: &ChunkMaterials,
+    sl: Option<SmoothLightingParameters>,
+    packed_vertices: bool,
+) {
+    let (cube, xsprite, partial, translucent, custom) = match chunk_meshes {
+        ChunkMeshes::Full {
+            cube,
+            xsprite,
+            partial,
+            translucent,
+            custom,
+        } => (cube, xsprite, partial, translucent, custom),
+        ChunkMeshes::Lod(lod, mesh) => {
+            // The mesh is never updated, so the unpacked mesh isn't kept.
+            let lod_mesh_child = spawn_cube_mesh(
+                commands,
+                mesh,
+                lod.subchunk_transform(),
+                meshes,
+                materials,
+                packed_vertices,
+                false,
+            )
+            .insert((Subchunk, LodSubChunk))
+            .id();
+            commands
+                .entity(parent)
+                .insert((Lod(lod), LodChild(lod_mesh_child)))
+                .push_children(&[lod_mesh_child]);
+            return;
+        }
+    };
+    let (culled_mesh, mut metadata) = cube;
+    // The smooth lighting settings might have changed while the task was computing.
+    metadata.set_sl_params(sl);
+    let culled_mesh_child = spawn_cube_mesh(
+        commands,
+        culled_mesh,
+        Transform::IDENTITY,
+        meshes,
+        materials,
+        packed_vertices,
+        true,
+    )
+    .insert((
+        // MainCulledMesh(metadata.into()),
+        SubChunkMD(MetaData::CubeMD(metadata).into()),
+        CubeSubChunk,
+        Subchunk,
+    ))
+    .id();
+    let (xsprite_mesh, data) = xsprite;
+    let xsprite_mesh_child = commands
+        .spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(xsprite_mesh),
+                material: materials.xsprite.0.clone(),
+                ..Default::default()
+            },
+            Subchunk,
+            // XSpriteMesh(RwLock::new(data)),
+            SubChunkMD(MetaData::XSpriteMD(data).into()),
+            XSpriteSubChunk,
+        ))
+        .id();
+    let (partial_mesh, partial_data) = partial;
+    let partial_mesh_child = commands
+        .spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(partial_mesh),
+                material: materials.blocks.0.clone(),
+                ..Default::default()
+            },
+            Subchunk,
+            SubChunkMD(MetaData::PartialMD(partial_data).into()),
+            PartialSubChunk,
+        ))
+        .id();
+    let (translucent_mesh, translucent_data) = translucent;
+    let translucent_mesh_child = commands
+        .spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(translucent_mesh),
+                material: materials.translucent.0.clone(),
+                ..Default::default()
+            },
+            Subchunk,
+            SubChunkMD(MetaData::TranslucentMD(translucent_data).into()),
+            TranslucentSubChunk,
+        ))
+        .id();
+    let (custom_mesh, custom_data) = custom;
+    let custom_mesh_child = commands
+        .spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(custom_mesh),
+                material: materials.blocks.0.clone(),
+                ..Default::default()
+            },
+            Subchunk,
+            SubChunkMD(MetaData::CustomMD(custom_data).into()),
+            CustomSubChunk,
+        ))
+        .id();
+    commands
+        .entity(parent)
+        .insert((
+            Lod(LodLevel::Full),
+            CubeChild(culled_mesh_child),
+            XSpriteChild(xsprite_mesh_child),
+            PartialChild(partial_mesh_child),
+            TranslucentChild(translucent_mesh_child),
+            CustomChild(custom_mesh_child),
+            ToIntroduce(vec![
+                (get_neighboring_chunk_cords(cords, East), East),
+                (get_neighboring_chunk_cords(cords, West), West),
+                (get_neighboring_chunk_cords(cords, North), North),
+                (get_neighboring_chunk_cords(cords, South), South),
+                (get_neighboring_chunk_cords(cords, NoEast), NoEast),
+                (get_neighboring_chunk_cords(cords, NoWest), NoWest),
+                (get_neighboring_chunk_cords(cords, SoEast), SoEast),
+                (get_neighboring_chunk_cords(cords, SoWest), SoWest),
+            ]),
+        ))
+        .push_children(&[
+            culled_mesh_child,
+            xsprite_mesh_child,
+            partial_mesh_child,
+            translucent_mesh_child,
+            custom_mesh_child,
+        ]);
+}
+
+/// Spawn the entity of a subchunk with a mesh of cubes. If `packed_vertices`, its vertices are packed
+/// (see [`packed`](crate::blocks::packed)), and if `keep_unpacked`, the unpacked mesh is kept in an
+/// [`UnpackedMesh`] for the mesh jobs.
+fn spawn_cube_mesh<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    mesh: Mesh,
+    transform: Transform,
+    meshes: &mut Assets<Mesh>,
+    materials: &ChunkMaterials,
+    packed_vertices: bool,
+    keep_unpacked: bool,
+) -> EntityCommands<'w, 's, 'a> {
+    if let Some(packed) = packed_vertices
+        .then(|| pack_mesh(&mesh, &materials.atlas))
+        .flatten()
+    {
+        // The packed mesh doesn't have positions, so its bounds are computed from the unpacked one.
+        let aabb = mesh.compute_aabb().unwrap_or_default();
+        let mut entity = commands.spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(packed),
+                material: materials.packed.0.clone(),
+                transform,
+                ..Default::default()
+            },
+            aabb,
+        ));
+        if keep_unpacked {
+            entity.insert(UnpackedMesh(mesh));
+        }
+        return entity;
+    }
+    commands.spawn(MaterialMeshBundle {
+        mesh: meshes.add(mesh),
+        material: materials.blocks.0.clone(),
+        transform,
+        ..Default::default()
+    })
+}
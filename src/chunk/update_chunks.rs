@@ -1,69 +1,213 @@
-use bevy_xpbd_3d::prelude::ComputedCollider;
-use parry3d::shape::TriMeshFlags;
-
-use super::{chunkmd::*, *};
-use crate::{
-    action::meshreg::MeshRegistry, mesh_utils::xsprite_mesh::update_xsprite_mesh, prelude::*,
+use super::{chunkmd::*, mesh_jobs::*, smooth_lighting::SLGrids, *};
+use crate::blocks::{atlas::AtlasLayout, meshreg::MeshRegistry};
+use crate::mesh_utils::{
+    custom_mesh::update_custom_mesh,
+    partial_mesh::update_partial_mesh,
+    translucent_mesh::{sort_translucent_mesh, update_translucent_mesh},
+    xsprite_mesh::update_xsprite_mesh,
 };
+use crate::player::PlayerCamera;
+use crate::utils::chunk_distance;
+
+/// How far (in blocks) the camera can move before the faces of the translucent subchunks are
+/// sorted again.
+const TRANSLUCENT_RESORT_DISTANCE: f32 = 0.5;
+
+/// How far (in chunks) from the camera the translucent subchunks are sorted again as it moves.
+const TRANSLUCENT_SORT_RADIUS: i32 = 2;
 
+/// Spawn [`MeshJob`]s to apply the logged changes to the cube subchunks that need to be updated.
+/// Smooth Lighting is applied (in the same job) right after.
 pub(super) fn update_cube_chunks(
-    mut meshes: ResMut<Assets<Mesh>>,
     mut commands: Commands,
-    mreg: Res<MeshRegistry>,
-    mut chunks_to_update: Query<
-        (Entity, &SubChunkMD, &Handle<Mesh>),
-        (With<ToUpdate>, With<CubeSubChunk>),
+    meshes: Res<Assets<Mesh>>,
+    mreg: Res<SharedMeshRegistry>,
+    atlas: Res<AtlasLayout>,
+    chunks_to_update: Query<
+        (
+            Entity,
+            &SubChunkMD,
+            &Handle<Mesh>,
+            &Parent,
+            Option<&UnpackedMesh>,
+        ),
+        (With<ToUpdate>, With<CubeSubChunk>, Without<MeshJob>),
     >,
+    parent_chunks: Query<(&AdjChunkGrids, &Grid)>,
 ) {
-    let breg = Arc::new(mreg.into_inner().clone());
-    for (entity, metadata, mesh_handle) in &mut chunks_to_update {
-        let mesh = meshes.get_mut(mesh_handle).unwrap();
-        let MetaData::CubeMD(metadata) = &mut *metadata.0.write().unwrap() else {
+    for (entity, metadata, mesh_handle, parent, unpacked) in chunks_to_update.iter() {
+        let Some(mesh) = working_mesh(&meshes, mesh_handle, unpacked) else {
             continue;
         };
-        update_mesh(mesh, metadata, Arc::clone(&breg).as_ref());
-        if let Some(aabb) = mesh.compute_aabb() {
-            if let Some(mut comm) = commands.get_entity(entity) {
-                comm.insert(aabb)
-                    .insert(AsyncCollider(ComputedCollider::TriMeshWithFlags(
-                        TriMeshFlags::MERGE_DUPLICATE_VERTICES,
-                    )))
-                    .remove::<ToUpdate>();
-            }
-        } else {
-            warn!("Couldn't compute Aabb for mesh after updating");
+        let Some(metadata) = metadata
+            .0
+            .write()
+            .unwrap()
+            .extract_meshmd_mut()
+            .map(|meshmd| meshmd.snapshot(true))
+        else {
+            continue;
+        };
+        let sl_grids = parent_chunks
+            .get(parent.get())
+            .ok()
+            .and_then(|(acj, Grid(grid))| SLGrids::new(grid, acj));
+        let mreg = Arc::clone(&mreg.0);
+        let job = MeshJob::spawn(
+            MeshJobKind::Update,
+            mesh.clone(),
+            MetaData::CubeMD(metadata),
+            unpacked.map(|_| atlas.clone()),
+            move |mesh, metadata| {
+                let Some(metadata) = metadata.extract_meshmd_mut() else {
+                    return;
+                };
+                update_mesh(mesh, metadata, mreg.as_ref());
+                if let Some(sl_grids) = sl_grids {
+                    sl_grids.apply(mreg.as_ref(), mesh, metadata, 0, CHUNK_TOTAL_BLOCKS_USIZE);
+                }
+            },
+        );
+        commands.entity(entity).insert(job);
+    }
+}
+
+/// How the mesh of a type of subchunk is updated in a [`MeshJob`], from the changes logged in its
+/// metadata (and the grid of its parent chunk).
+pub(super) type UpdateMeshFn = fn(&MeshRegistry, &mut Mesh, &mut MetaData, &ChunkGrid);
+
+/// Spawn [`MeshJob`]s to apply the logged changes to the subchunks with the `Marker` that need to
+/// be updated, with `update_mesh`. Cube subchunks are updated by [`update_cube_chunks`], since
+/// they are lit (and packed) too.
+pub(super) fn update_subchunks<Marker: Component>(
+    update_mesh: UpdateMeshFn,
+) -> impl FnMut(
+    Commands,
+    Res<Assets<Mesh>>,
+    Res<SharedMeshRegistry>,
+    Query<
+        (Entity, &SubChunkMD, &Handle<Mesh>, &Parent),
+        (With<ToUpdate>, With<Marker>, Without<MeshJob>),
+    >,
+    Query<&Grid>,
+) {
+    move |mut commands, meshes, mreg, chunks_to_update, parent_chunks| {
+        for (entity, metadata, mesh_handle, parent) in chunks_to_update.iter() {
+            let Some(mesh) = meshes.get(mesh_handle) else {
+                continue;
+            };
+            let Ok(Grid(grid)) = parent_chunks.get(parent.get()) else {
+                continue;
+            };
+            let metadata = metadata.0.write().unwrap().snapshot();
+            let mreg = Arc::clone(&mreg.0);
+            let grid = Arc::clone(grid);
+            let job = MeshJob::spawn(
+                MeshJobKind::Update,
+                mesh.clone(),
+                metadata,
+                None,
+                move |mesh, metadata| {
+                    update_mesh(mreg.as_ref(), mesh, metadata, &grid.read().unwrap());
+                },
+            );
+            commands.entity(entity).insert(job);
         }
     }
 }
 
-pub(super) fn update_xsprite_chunks(
+/// Apply the logged changes to the mesh of an xsprite subchunk.
+pub(super) fn update_xsprite(
+    mreg: &MeshRegistry,
+    mesh: &mut Mesh,
+    metadata: &mut MetaData,
+    _grid: &ChunkGrid,
+) {
+    if let MetaData::XSpriteMD(metadata) = metadata {
+        update_xsprite_mesh(mreg, mesh, metadata, CHUNK_DIMS);
+    }
+}
+
+/// Apply the logged changes to the mesh of a partial subchunk, it's rebuilt from the grid.
+pub(super) fn update_partial(
+    mreg: &MeshRegistry,
+    mesh: &mut Mesh,
+    metadata: &mut MetaData,
+    grid: &ChunkGrid,
+) {
+    if let MetaData::PartialMD(metadata) = metadata {
+        update_partial_mesh(mreg, mesh, metadata, grid);
+    }
+}
+
+/// Apply the logged changes to the mesh of a translucent subchunk, like partial meshes it's
+/// rebuilt from the grid.
+pub(super) fn update_translucent(
+    mreg: &MeshRegistry,
+    mesh: &mut Mesh,
+    metadata: &mut MetaData,
+    grid: &ChunkGrid,
+) {
+    if let MetaData::TranslucentMD(metadata) = metadata {
+        update_translucent_mesh(mreg, mesh, metadata, grid);
+    }
+}
+
+/// Apply the logged changes to the mesh of a custom mesh subchunk.
+pub(super) fn update_custom(
+    mreg: &MeshRegistry,
+    mesh: &mut Mesh,
+    metadata: &mut MetaData,
+    _grid: &ChunkGrid,
+) {
+    if let MetaData::CustomMD(metadata) = metadata {
+        update_custom_mesh(mreg, mesh, metadata, CHUNK_DIMS);
+    }
+}
+
+/// Translucent faces are blended, so they have to be drawn from back to front. Sort the faces of
+/// the translucent subchunks near the camera, when it has moved far enough since they were last
+/// sorted. Subchunks further than [`TRANSLUCENT_SORT_RADIUS`] are only sorted when their mesh has
+/// been rebuilt, the order of their faces barely changes as the camera moves.
+pub(super) fn sort_translucent_chunks(
     mut meshes: ResMut<Assets<Mesh>>,
-    mut commands: Commands,
-    mreg: Res<MeshRegistry>,
-    chunks_to_update: Query<
-        (Entity, &SubChunkMD, &Handle<Mesh>),
-        (With<ToUpdate>, With<XSpriteSubChunk>),
+    current_chunk: Res<CurrentChunk>,
+    camera: Query<&GlobalTransform, With<PlayerCamera>>,
+    subchunks: Query<
+        (&SubChunkMD, &Handle<Mesh>, &GlobalTransform, &Parent),
+        (With<TranslucentSubChunk>, Without<MeshJob>),
     >,
+    parent_chunks: Query<&Cords>,
 ) {
-    let breg = Arc::new(mreg.into_inner().clone());
-    for (entity, metadata, mesh_handle) in chunks_to_update.iter() {
-        let mesh = meshes.get_mut(mesh_handle).unwrap();
-        let MetaData::XSpriteMD(metadata) = &mut *metadata.0.write().unwrap() else {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    for (metadata, mesh_handle, transform, parent) in subchunks.iter() {
+        let MetaData::TranslucentMD(metadata) = &mut *metadata.0.write().unwrap() else {
             continue;
         };
-        update_xsprite_mesh(Arc::clone(&breg).as_ref(), mesh, metadata, CHUNK_DIMS);
-        if let Some(aabb) = mesh.compute_aabb() {
-            if let Some(mut comm) = commands.get_entity(entity) {
-                comm.insert(aabb)
-                    .insert(AsyncCollider(ComputedCollider::TriMeshWithFlags(
-                        TriMeshFlags::MERGE_DUPLICATE_VERTICES,
-                    )))
-                    .remove::<ToUpdate>();
+        let eye = transform
+            .affine()
+            .inverse()
+            .transform_point3(camera.translation());
+        if let Some(sorted_from) = metadata.sorted_from {
+            let near = parent_chunks.get(parent.get()).is_ok_and(|Cords(cords)| {
+                chunk_distance(*cords, current_chunk.0) <= TRANSLUCENT_SORT_RADIUS
+            });
+            if !near || sorted_from.distance(eye) < TRANSLUCENT_RESORT_DISTANCE {
+                continue;
             }
-        } else if mesh.count_vertices() > 0 {
-            warn!("Couldn't compute Aabb for mesh after updating");
-        } else {
-            commands.entity(entity).remove::<ToUpdate>();
+        }
+        // Empty meshes are only marked as sorted, to not trigger a mesh change.
+        if meshes
+            .get(mesh_handle)
+            .map_or(true, |mesh| mesh.count_vertices() == 0)
+        {
+            metadata.sorted_from = Some(eye);
+            continue;
+        }
+        if let Some(mesh) = meshes.get_mut(mesh_handle) {
+            sort_translucent_mesh(mesh, metadata, eye);
         }
     }
 }
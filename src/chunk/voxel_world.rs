@@ -0,0 +1,179 @@
+//! The [`VoxelWorld`] is the one supported way to read and write blocks by their position in the
+//! world. It finds the chunk a position is in and locks its grid internally, so callers don't need
+//! to go through the [`ChunkMap`] and the chunks' [`Grid`]s themselves.
+use super::*;
+use crate::action::PlaceBlockGlobalEvent;
+use crate::blocks::ShapeRegistry;
+use bevy::ecs::system::SystemParam;
+use std::sync::RwLockReadGuard;
+
+/// A block hit by [`VoxelWorld::raycast`].
+#[derive(Clone, Copy, Debug)]
+pub struct VoxelRayHit {
+    /// The position of the block in the world.
+    pub pos: IVec3,
+    pub block: Block,
+    /// The face of the block the ray entered through. `None` if the ray started inside the block.
+    pub face: Option<Face>,
+    /// The distance from the origin of the ray to the hit.
+    pub distance: f32,
+}
+
+/// [`SystemParam`] to read and write the blocks of the loaded chunks by their position in the world
+/// (counted in blocks). Positions that are in chunks that aren't loaded, or that are out of the
+/// chunks' height, have no blocks.
+#[derive(SystemParam)]
+pub struct VoxelWorld<'w, 's> {
+    chunk_map: Res<'w, ChunkMap>,
+    grids: Query<'w, 's, &'static Grid, With<ParentChunk>>,
+    commands: Commands<'w, 's>,
+}
+
+impl VoxelWorld<'_, '_> {
+    /// The global position (chunk cords and position in the chunk) of a position in the world.
+    pub fn to_global(world_pos: IVec3) -> BlockGlobalPos {
+        world_to_global_block_pos(world_pos, CHUNK_DIMS)
+    }
+
+    /// The position in the world of a global position.
+    pub fn to_world(global_pos: BlockGlobalPos) -> IVec3 {
+        global_block_pos_to_world(global_pos, CHUNK_DIMS)
+    }
+
+    /// The entity of the chunk, if it's loaded.
+    pub fn chunk_entity(&self, chunk_cords: ChunkCords) -> Option<Entity> {
+        self.chunk_map
+            .pos_to_ent
+            .get(&chunk_cords)
+            .copied()
+            .filter(|entity| *entity != Entity::PLACEHOLDER)
+    }
+
+    /// The grid of the chunk, if it's loaded.
+    pub fn grid(&self, chunk_cords: ChunkCords) -> Option<&Arc<RwLock<ChunkGrid>>> {
+        let entity = self.chunk_entity(chunk_cords)?;
+        self.grids.get(entity).ok().map(|Grid(grid)| grid)
+    }
+
+    /// The block at the position.
+    pub fn get_block(&self, world_pos: IVec3) -> Option<Block> {
+        let BlockGlobalPos {
+            pos,
+            chunk_cords,
+            valid,
+        } = Self::to_global(world_pos);
+        if !valid {
+            return None;
+        }
+        self.grid(chunk_cords)?.read().unwrap().get_block(pos)
+    }
+
+    /// The block at the position, or `default` if there is none.
+    pub fn get_block_or(&self, world_pos: IVec3, default: Block) -> Block {
+        self.get_block(world_pos).unwrap_or(default)
+    }
+
+    /// Place a block at the position (or break the block, if it's [`Block::AIR`]). This goes
+    /// through the block placing pipeline (see [`PlaceBlockGlobalEvent`]), so the meshes are
+    /// updated accordingly. Returns false if there's no chunk loaded at the position.
+    pub fn set_block(&mut self, world_pos: IVec3, block: Block) -> bool {
+        let global_pos = Self::to_global(world_pos);
+        if !global_pos.valid || self.chunk_entity(global_pos.chunk_cords).is_none() {
+            return false;
+        }
+        self.commands.add(move |world: &mut World| {
+            world.send_event(PlaceBlockGlobalEvent::from_global_pos(global_pos, block));
+        });
+        true
+    }
+
+    /// The neighbors of the position, in the same order as [`FACES`], across chunk borders.
+    pub fn get_neighbors(&self, world_pos: IVec3) -> [Option<Block>; 6] {
+        FACES.map(|face| self.get_block(world_pos + face.offset()))
+    }
+
+    /// Iterate over the neighbors of the position (across chunk borders), with their face and
+    /// position.
+    pub fn neighbors(
+        &self,
+        world_pos: IVec3,
+    ) -> impl Iterator<Item = (Face, IVec3, Option<Block>)> + '_ {
+        FACES.into_iter().map(move |face| {
+            let neighbor_pos = world_pos + face.offset();
+            (face, neighbor_pos, self.get_block(neighbor_pos))
+        })
+    }
+
+    /// All of the loaded blocks in the box between the two corners (inclusive). Each grid is only
+    /// locked once.
+    pub fn blocks_in_region(&self, corner_a: IVec3, corner_b: IVec3) -> Vec<(IVec3, Block)> {
+        let min = corner_a
+            .min(corner_b)
+            .max(IVec3::new(i32::MIN, 0, i32::MIN));
+        let max = corner_a
+            .max(corner_b)
+            .min(IVec3::new(i32::MAX, HEIGHT as i32 - 1, i32::MAX));
+        let mut blocks = vec![];
+        if min.y > max.y {
+            return blocks;
+        }
+        let min_cords = Self::to_global(min).chunk_cords;
+        let max_cords = Self::to_global(max).chunk_cords;
+        for u in min_cords.x..=max_cords.x {
+            for v in min_cords.y..=max_cords.y {
+                let Some(grid) = self.grid([u, v].into()) else {
+                    continue;
+                };
+                let grid = grid.read().unwrap();
+                let chunk_origin = IVec3::new(u * WIDTH as i32, 0, v * LENGTH as i32);
+                let local_min = (min - chunk_origin).max(IVec3::ZERO);
+                let local_max = (max - chunk_origin).min(CHUNK_DIMS.as_ivec3() - IVec3::ONE);
+                for y in local_min.y..=local_max.y {
+                    for z in local_min.z..=local_max.z {
+                        for x in local_min.x..=local_max.x {
+                            let local_pos = IVec3::new(x, y, z);
+                            if let Some(block) = grid.get_block(local_pos.as_uvec3()) {
+                                blocks.push((chunk_origin + local_pos, block));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        blocks
+    }
+
+    /// Cast a ray, and return the first block it hits within `max_distance`. Blocks are hit by their
+    /// selection shape (see [`ShapeRegistry`]), so blocks without one (like [`Block::AIR`]) are
+    /// passed through.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<VoxelRayHit> {
+        // The grid of the chunk the ray is in, so it's only locked again when the ray enters
+        // another chunk.
+        let mut current: Option<(ChunkCords, Option<RwLockReadGuard<ChunkGrid>>)> = None;
+        let mut hit_block = Block::AIR;
+        let hit = raycast_voxels(origin, direction, max_distance, |world_pos| {
+            let BlockGlobalPos {
+                pos,
+                chunk_cords,
+                valid,
+            } = Self::to_global(world_pos);
+            if !valid {
+                return None;
+            }
+            if current.as_ref().map(|(cords, _)| *cords) != Some(chunk_cords) {
+                let grid = self.grid(chunk_cords).map(|grid| grid.read().unwrap());
+                current = Some((chunk_cords, grid));
+            }
+            let block = current.as_ref()?.1.as_ref()?.get_block(pos)?;
+            let shape = ShapeRegistry::get().get_shape(&block).selection?;
+            hit_block = block;
+            Some(shape)
+        })?;
+        Some(VoxelRayHit {
+            pos: hit.pos,
+            block: hit_block,
+            face: hit.face,
+            distance: hit.distance,
+        })
+    }
+}
@@ -1,10 +1,12 @@
 mod commands;
 pub(super) mod global_parameter;
+pub mod script;
 
 use crate::prelude::*;
 use bevy_console::*;
 use clap::builder::OsStr;
 use commands::*;
+use script::*;
 
 pub struct GlobalConsolePlugin;
 
@@ -17,6 +19,22 @@ impl Plugin for GlobalConsolePlugin {
         });
         app.add_systems(PreUpdate, pause_game);
         app.add_console_command::<SetCommand, _>(set_command);
+        app.add_console_command::<ExecCommand, _>(exec_command);
+        app.add_console_command::<ExportCommand, _>(export_command);
+        app.add_console_command::<GameModeCommand, _>(gamemode_command);
+        app.add_console_command::<TpCommand, _>(tp_command);
+        app.add_console_command::<TimeCommand, _>(time_command);
+        app.add_console_command::<WeatherCommand, _>(weather_command);
+        app.init_resource::<ScriptRunner>();
+        app.add_systems(Startup, queue_autoexec);
+        // Scripts can only run once the player exists, because many of the commands affect it.
+        app.add_systems(
+            Update,
+            run_console_scripts
+                .after(ConsoleSet::ConsoleUI)
+                .before(ConsoleSet::Commands)
+                .run_if(any_with_component::<crate::player::PlayerCamera>()),
+        );
     }
 }
 
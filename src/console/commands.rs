@@ -1,6 +1,19 @@
+mod exec;
+mod export;
+mod gamemode;
 mod set;
+mod time;
+mod tp;
+mod weather;
 pub(super) use super::global_parameter::*;
+pub(super) use super::script::ScriptRunner;
 pub(super) use crate::{chunk::RenderSettings, prelude::*};
 pub(super) use bevy_console::*;
 pub(super) use clap::Parser;
+pub use exec::*;
+pub use export::*;
+pub use gamemode::*;
 pub use set::*;
+pub use time::*;
+pub use tp::*;
+pub use weather::*;
@@ -0,0 +1,24 @@
+use super::*;
+
+/// Execute a console script
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "exec")]
+pub struct ExecCommand {
+    /// Path to the script (relative to the working directory or to the assets directory)
+    pub file: String,
+}
+
+pub fn exec_command(mut exec: ConsoleCommand<ExecCommand>, mut runner: ResMut<ScriptRunner>) {
+    if let Some(Ok(ExecCommand { file })) = exec.take() {
+        match runner.push_file(&file) {
+            Ok(path) => {
+                reply!(exec, "Executing {}", path.display());
+                exec.ok();
+            }
+            Err(err) => {
+                reply!(exec, "Couldn't read {}: {}", file, err);
+                exec.failed();
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use super::*;
+use crate::export::{export_meshes, ChunkRegion, LoadedChunkMeshes};
+use std::path::Path;
+
+/// Export the meshes of the loaded chunks to a file
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "export")]
+pub struct ExportCommand {
+    /// Path to the file (.glb, or .obj which is saved with a .mtl and a .png of the atlas)
+    pub file: String,
+    /// Only export the chunks from these chunk cords (x z)
+    #[arg(long, num_args = 2, allow_negative_numbers = true)]
+    pub from: Option<Vec<i32>>,
+    /// Only export the chunks up to these chunk cords (x z)
+    #[arg(long, num_args = 2, allow_negative_numbers = true)]
+    pub to: Option<Vec<i32>>,
+}
+
+pub fn export_command(mut export: ConsoleCommand<ExportCommand>, chunk_meshes: LoadedChunkMeshes) {
+    if let Some(Ok(ExportCommand { file, from, to })) = export.take() {
+        let region = match (from, to) {
+            (Some(from), Some(to)) => Some(ChunkRegion::new(
+                IVec2::new(from[0], from[1]),
+                IVec2::new(to[0], to[1]),
+            )),
+            (None, None) => None,
+            _ => {
+                reply!(export, "Expected both --from and --to, or neither of them.");
+                export.failed();
+                return;
+            }
+        };
+        let Some(atlas) = chunk_meshes.atlas() else {
+            reply!(export, "The block atlas hasn't been stitched yet.");
+            export.failed();
+            return;
+        };
+        let meshes = chunk_meshes.collect(region);
+        match export_meshes(Path::new(&file), &meshes, atlas) {
+            Ok(()) => {
+                reply!(export, "Exported {} meshes to {}", meshes.len(), file);
+                export.ok();
+            }
+            Err(err) => {
+                reply!(export, "Couldn't export to {}: {}", file, err);
+                export.failed();
+            }
+        }
+    }
+}
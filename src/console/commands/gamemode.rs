@@ -0,0 +1,41 @@
+use super::*;
+use crate::player::PlayerGameMode;
+
+/// Set the game mode of the player
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "gamemode")]
+pub struct GameModeCommand {
+    /// The game mode:
+    ///     -creative
+    ///     -survival
+    ///     -adventure
+    ///     -spectator
+    pub game_mode: String,
+}
+
+pub fn gamemode_command(
+    mut gamemode: ConsoleCommand<GameModeCommand>,
+    mut player: Query<&mut PlayerGameMode>,
+) {
+    if let Some(Ok(GameModeCommand { game_mode })) = gamemode.take() {
+        let new_game_mode = match game_mode.to_lowercase().as_str() {
+            "creative" | "c" | "1" => PlayerGameMode::Creative,
+            "survival" | "s" | "0" => PlayerGameMode::Survival,
+            "adventure" | "a" | "2" => PlayerGameMode::Adventure,
+            "spectator" | "sp" | "3" => PlayerGameMode::Spectator,
+            _ => {
+                reply!(gamemode, "Unknown game mode: {}", game_mode);
+                gamemode.failed();
+                return;
+            }
+        };
+        if let Ok(mut player_game_mode) = player.get_single_mut() {
+            reply!(gamemode, "Game mode set to {:?}", new_game_mode);
+            player_game_mode.set(new_game_mode);
+            gamemode.ok();
+        } else {
+            reply!(gamemode, "Couldn't find the player.");
+            gamemode.failed();
+        }
+    }
+}
@@ -8,6 +8,9 @@ use super::*;
 pub struct SetCommand {
     /// Parameter to change:
     ///     -render-distance
+    ///     -lod-half (the distance chunks are at half detail from)
+    ///     -lod-quarter (the distance chunks are at quarter detail from)
+    ///     -packed-vertices (1 to render the chunks with packed vertices, 0 not to)
     pub parameter_to_change: GlobalParameter,
     /// The value to set
     pub value: f32,
@@ -27,6 +30,14 @@ pub fn set_command(
                 render_settings.render_distance = value as i32;
                 reply!(set, "render-distance set to {}", value);
             }
+            GlobalParameter::LodHalf => {
+                render_settings.lod_distances[0] = value as i32;
+                reply!(set, "lod-half set to {}", value);
+            }
+            GlobalParameter::LodQuarter => {
+                render_settings.lod_distances[1] = value as i32;
+                reply!(set, "lod-quarter set to {}", value);
+            }
             GlobalParameter::SLintensity => {
                 if let Some(ref mut sl) = render_settings.sl {
                     sl.intensity = value;
@@ -72,6 +83,23 @@ pub fn set_command(
                     set.failed();
                 }
             },
+            GlobalParameter::PackedVertices => match value as i32 {
+                1 => {
+                    render_settings.packed_vertices = true;
+                    reply!(set, "packed-vertices enabled, the chunks will be respawned");
+                }
+                0 => {
+                    render_settings.packed_vertices = false;
+                    reply!(
+                        set,
+                        "packed-vertices disabled, the chunks will be respawned"
+                    );
+                }
+                _ => {
+                    set.reply("Expected either 1.0 to pack the vertices or 0.0 not to.");
+                    set.failed();
+                }
+            },
         }
         set.ok();
     }
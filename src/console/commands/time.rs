@@ -0,0 +1,86 @@
+use super::*;
+use crate::env::TimeOfDay;
+
+/// Control the time of day
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "time")]
+pub struct TimeCommand {
+    /// What to do:
+    ///     -set <hours | day | noon | sunset | night | midnight | sunrise>
+    ///     -day-length <seconds>
+    ///     -freeze
+    ///     -unfreeze
+    ///     -get
+    pub action: String,
+    /// The value for `set` or `day-length`
+    pub value: Option<String>,
+}
+
+pub fn time_command(mut time: ConsoleCommand<TimeCommand>, mut time_of_day: ResMut<TimeOfDay>) {
+    if let Some(Ok(TimeCommand { action, value })) = time.take() {
+        match (action.as_str(), value) {
+            ("set", Some(value)) => {
+                let hours = match value.as_str() {
+                    "sunrise" => 6.0,
+                    "day" => 9.0,
+                    "noon" => 12.0,
+                    "sunset" => 18.0,
+                    "night" => 21.0,
+                    "midnight" => 0.0,
+                    hours => match hours.parse::<f32>() {
+                        Ok(hours) => hours,
+                        Err(_) => {
+                            reply!(
+                                time,
+                                "Expected the time in hours (0 - 24), found: {}",
+                                value
+                            );
+                            time.failed();
+                            return;
+                        }
+                    },
+                };
+                time_of_day.set_hours(hours);
+                reply!(time, "time set to {:.2}", time_of_day.hours());
+            }
+            ("day-length", Some(value)) => match value.parse::<f32>() {
+                Ok(seconds) if seconds > 0.0 => {
+                    time_of_day.day_length = seconds;
+                    reply!(time, "day length set to {} seconds", seconds);
+                }
+                _ => {
+                    reply!(
+                        time,
+                        "Expected a positive amount of seconds, found: {}",
+                        value
+                    );
+                    time.failed();
+                    return;
+                }
+            },
+            ("freeze", None) => {
+                time_of_day.frozen = true;
+                reply!(time, "time frozen at {:.2}", time_of_day.hours());
+            }
+            ("unfreeze", None) => {
+                time_of_day.frozen = false;
+                reply!(time, "time unfrozen");
+            }
+            ("get", None) => {
+                reply!(
+                    time,
+                    "time: {:.2}, day length: {} seconds{}",
+                    time_of_day.hours(),
+                    time_of_day.day_length,
+                    if time_of_day.frozen { " (frozen)" } else { "" }
+                );
+            }
+            _ => {
+                reply!(time, "Unknown usage of time, run 'time --help' for help.");
+                time.failed();
+                return;
+            }
+        }
+        time.ok();
+    }
+}
@@ -0,0 +1,31 @@
+use super::*;
+use crate::player::PhysicalPlayer;
+use bevy_xpbd_3d::prelude::{LinearVelocity, Position};
+
+/// Teleport the player to a position in the world
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "tp")]
+pub struct TpCommand {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+pub fn tp_command(
+    mut tp: ConsoleCommand<TpCommand>,
+    mut player: Query<(&mut Transform, &mut Position, &mut LinearVelocity), With<PhysicalPlayer>>,
+) {
+    if let Some(Ok(TpCommand { x, y, z })) = tp.take() {
+        if let Ok((mut transform, mut position, mut velocity)) = player.get_single_mut() {
+            let new_pos = Vec3::new(x, y, z);
+            transform.translation = new_pos;
+            position.0 = new_pos;
+            velocity.0 = Vec3::ZERO;
+            reply!(tp, "Teleported to {} {} {}", x, y, z);
+            tp.ok();
+        } else {
+            reply!(tp, "Couldn't find the player.");
+            tp.failed();
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::*;
+use crate::env::{Weather, WeatherKind};
+
+/// Control the weather
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "weather")]
+pub struct WeatherCommand {
+    /// What to do:
+    ///     -clear | rain | snow | storm [duration in seconds]
+    ///     -freeze
+    ///     -unfreeze
+    ///     -get
+    pub action: String,
+    /// How long the new weather will last (in seconds)
+    pub duration: Option<f32>,
+}
+
+pub fn weather_command(
+    mut weather: ConsoleCommand<WeatherCommand>,
+    mut world_weather: ResMut<Weather>,
+) {
+    if let Some(Ok(WeatherCommand { action, duration })) = weather.take() {
+        if let Some(kind) = WeatherKind::from_name(action.as_str()) {
+            world_weather.set(kind, duration);
+            reply!(weather, "weather set to {:?}", kind);
+            weather.ok();
+            return;
+        }
+        match action.as_str() {
+            "freeze" => {
+                world_weather.frozen = true;
+                reply!(weather, "weather frozen");
+            }
+            "unfreeze" => {
+                world_weather.frozen = false;
+                reply!(weather, "weather unfrozen");
+            }
+            "get" => {
+                reply!(
+                    weather,
+                    "weather: {:?} (intensity {:.2}), changes in {:.0} seconds{}",
+                    world_weather.next().unwrap_or(world_weather.kind()),
+                    world_weather.intensity(),
+                    world_weather.time_left,
+                    if world_weather.frozen {
+                        " (frozen)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            _ => {
+                reply!(weather, "Unknown weather: {}", action);
+                weather.failed();
+                return;
+            }
+        }
+        weather.ok();
+    }
+}
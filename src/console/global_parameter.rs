@@ -4,10 +4,13 @@ pub(super) use std::cmp;
 #[derive(Copy, Clone, Debug)]
 pub enum GlobalParameter {
     RenderDistance,
+    LodHalf,
+    LodQuarter,
     SLintensity,
     SLmax,
     SLsmoothing,
     SL,
+    PackedVertices,
 }
 
 impl clap::builder::ValueParserFactory for GlobalParameter {
@@ -30,19 +33,31 @@ impl clap::builder::TypedValueParser for GlobalParameterParser {
         value: &std::ffi::OsStr,
     ) -> Result<Self::Value, clap::Error> {
         let render_distance = OsStr::from("render-distance");
+        let lod_half = OsStr::from("lod-half");
+        let lod_quarter = OsStr::from("lod-quarter");
         let sl_intensity = OsStr::from("sli");
         let sl_max = OsStr::from("slm");
         let sl_smoothing = OsStr::from("sls");
         let sl = OsStr::from("sl");
+        let packed_vertices = OsStr::from("packed-vertices");
         if matches!(value.cmp(&render_distance), cmp::Ordering::Equal) {
             return Ok(GlobalParameter::RenderDistance);
         }
+        if matches!(value.cmp(&lod_half), cmp::Ordering::Equal) {
+            return Ok(GlobalParameter::LodHalf);
+        }
+        if matches!(value.cmp(&lod_quarter), cmp::Ordering::Equal) {
+            return Ok(GlobalParameter::LodQuarter);
+        }
         if matches!(value.cmp(&sl_max), cmp::Ordering::Equal) {
             return Ok(GlobalParameter::SLmax);
         }
         if matches!(value.cmp(&sl), cmp::Ordering::Equal) {
             return Ok(GlobalParameter::SL);
         }
+        if matches!(value.cmp(&packed_vertices), cmp::Ordering::Equal) {
+            return Ok(GlobalParameter::PackedVertices);
+        }
         if matches!(value.cmp(&sl_intensity), cmp::Ordering::Equal) {
             return Ok(GlobalParameter::SLintensity);
         }
@@ -0,0 +1,340 @@
+//! Console scripts. A script is a plain text file of console commands, one statement per line
+//! (or several, chained with `;`). On top of the normal console commands, scripts understand a
+//! few directives of their own:
+//!     -`wait <frames>`: stop executing the script for the given amount of frames.
+//!     -`var <name> <value>`: define a variable, which can be used later as `$name`.
+//!     -`exec <file>`: execute another script in place.
+//! Anything after `//` or `#` (outside of quotes) is a comment.
+use super::*;
+use bevy::utils::HashMap;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// The script that is executed automatically when the game starts.
+pub const AUTOEXEC_PATH: &str = "assets/autoexec.cfg";
+/// If a script can't be found at the given path, it is searched for in this directory.
+pub const SCRIPTS_DIR: &str = "assets";
+/// A script can execute another script, this is the maximum depth of nested `exec`s.
+pub const MAX_EXEC_DEPTH: usize = 8;
+
+/// A single statement in a console script.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptStatement {
+    /// A console command, the first word is the name of the command.
+    Command(Vec<String>),
+    /// Pause the script for a number of frames.
+    Wait(u32),
+    /// Define a variable.
+    Var(String, String),
+    /// Execute another script in place.
+    Exec(String),
+}
+
+/// Resource that executes the pending console script statements, a few every frame.
+#[derive(Resource, Default)]
+pub struct ScriptRunner {
+    queue: VecDeque<(usize, ScriptStatement)>,
+    frames_to_wait: u32,
+    vars: HashMap<String, String>,
+}
+
+impl ScriptRunner {
+    /// Queue the statements of a script (from its source) to execute after the pending ones.
+    pub fn push_source(&mut self, source: &str) {
+        for statement in parse_script(source) {
+            self.queue.push_back((0, statement));
+        }
+    }
+
+    /// Queue a script file to execute after the pending statements.
+    pub fn push_file(&mut self, path: &str) -> std::io::Result<PathBuf> {
+        let (path, source) = read_script(path)?;
+        self.push_source(&source);
+        Ok(path)
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.queue.is_empty() && self.frames_to_wait == 0
+    }
+
+    /// Replace every `$name` with the value of the variable.
+    fn substitute_vars(&self, word: &str) -> String {
+        if !word.contains('$') {
+            return word.to_string();
+        }
+        let mut result = word.to_string();
+        // Substitute the longest names first, so `$ab` doesn't get replaced by the value of `$a`.
+        let mut names: Vec<&String> = self.vars.keys().collect();
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        for name in names {
+            result = result.replace(&format!("${}", name), &self.vars[name]);
+        }
+        result
+    }
+
+    /// Execute statements until the queue is empty or a `wait` is reached.
+    fn run(
+        &mut self,
+        console_config: &ConsoleConfiguration,
+        command_sender: &mut EventWriter<ConsoleCommandEntered>,
+    ) {
+        if self.frames_to_wait > 0 {
+            self.frames_to_wait -= 1;
+            return;
+        }
+        while let Some((depth, statement)) = self.queue.pop_front() {
+            match statement {
+                ScriptStatement::Wait(frames) => {
+                    self.frames_to_wait = frames;
+                    if frames > 0 {
+                        return;
+                    }
+                }
+                ScriptStatement::Var(name, value) => {
+                    let value = self.substitute_vars(&value);
+                    self.vars.insert(name, value);
+                }
+                ScriptStatement::Exec(path) => {
+                    if depth >= MAX_EXEC_DEPTH {
+                        warn!("Can't exec {}, scripts are nested too deep.", path);
+                        continue;
+                    }
+                    match read_script(&self.substitute_vars(&path)) {
+                        Ok((_, source)) => {
+                            // Execute the script in place, before the rest of the queue.
+                            for statement in parse_script(&source).into_iter().rev() {
+                                self.queue.push_front((depth + 1, statement));
+                            }
+                        }
+                        Err(err) => warn!("Couldn't exec script {}: {}", path, err),
+                    }
+                }
+                ScriptStatement::Command(words) => {
+                    // A variable can hold several words (`var pos 1 -2 3`), so the substituted
+                    // words are split again. Words without variables keep their quoting.
+                    let mut words = words.iter().flat_map(|word| {
+                        if word.contains('$') {
+                            let value = self.substitute_vars(word);
+                            value.split_whitespace().map(str::to_string).collect()
+                        } else {
+                            vec![word.clone()]
+                        }
+                    });
+                    let command_name = words.next().unwrap_or_default();
+                    if !console_config.commands.contains_key(command_name.as_str()) {
+                        warn!("Unknown command in console script: {}", command_name);
+                        continue;
+                    }
+                    info!("[script] {}", command_name);
+                    command_sender.send(ConsoleCommandEntered {
+                        command_name,
+                        args: words.collect(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Read a script file. If it can't be found at `path`, look for it in [`SCRIPTS_DIR`].
+pub fn read_script(path: &str) -> std::io::Result<(PathBuf, String)> {
+    let mut path = PathBuf::from(path);
+    if !path.exists() {
+        let fallback = Path::new(SCRIPTS_DIR).join(&path);
+        if fallback.exists() {
+            path = fallback;
+        }
+    }
+    std::fs::read_to_string(&path).map(|source| (path, source))
+}
+
+/// Parse the source of a console script into its statements.
+pub fn parse_script(source: &str) -> Vec<ScriptStatement> {
+    let mut statements = vec![];
+    for line in source.lines() {
+        for words in split_statements(line) {
+            let Some(first) = words.first() else {
+                continue;
+            };
+            let statement = match first.as_str() {
+                "wait" => match words.get(1).map(|frames| frames.parse::<u32>()) {
+                    Some(Ok(frames)) => ScriptStatement::Wait(frames),
+                    _ => {
+                        warn!("Expected `wait <frames>`, found: {}", line.trim());
+                        continue;
+                    }
+                },
+                "var" if words.len() >= 3 => {
+                    ScriptStatement::Var(words[1].clone(), words[2..].join(" "))
+                }
+                "var" => {
+                    warn!("Expected `var <name> <value>`, found: {}", line.trim());
+                    continue;
+                }
+                "exec" if words.len() == 2 => ScriptStatement::Exec(words[1].clone()),
+                "exec" => {
+                    warn!("Expected `exec <file>`, found: {}", line.trim());
+                    continue;
+                }
+                _ => ScriptStatement::Command(words),
+            };
+            statements.push(statement);
+        }
+    }
+    statements
+}
+
+/// Split a line into statements (seperated by `;`), and each statement into words. Quotes group
+/// words together, comments (`//` or `#`) end the line.
+fn split_statements(line: &str) -> Vec<Vec<String>> {
+    let mut statements = vec![];
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' if !in_quotes => break,
+            '/' if !in_quotes && chars.peek() == Some(&'/') => break,
+            ';' if !in_quotes => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                statements.push(std::mem::take(&mut words));
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    statements.push(words);
+    statements.retain(|words| !words.is_empty());
+    statements
+}
+
+/// Queue the autoexec script (if it exists) when the game starts.
+pub(super) fn queue_autoexec(mut runner: ResMut<ScriptRunner>) {
+    if Path::new(AUTOEXEC_PATH).exists() {
+        match runner.push_file(AUTOEXEC_PATH) {
+            Ok(path) => info!("Queued autoexec script: {}", path.display()),
+            Err(err) => warn!("Couldn't read autoexec script: {}", err),
+        }
+    }
+}
+
+/// Execute the pending console script statements.
+pub(super) fn run_console_scripts(
+    mut runner: ResMut<ScriptRunner>,
+    console_config: Res<ConsoleConfiguration>,
+    mut command_sender: EventWriter<ConsoleCommandEntered>,
+) {
+    runner.run(&console_config, &mut command_sender);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::SystemState;
+
+    fn command(words: &[&str]) -> ScriptStatement {
+        ScriptStatement::Command(words.iter().map(|word| word.to_string()).collect())
+    }
+
+    #[test]
+    fn parses_directives() {
+        let statements = parse_script("wait 10\nvar pos 1 -2 3\nexec other.cfg\ntp $pos");
+        assert_eq!(
+            statements,
+            vec![
+                ScriptStatement::Wait(10),
+                ScriptStatement::Var("pos".to_string(), "1 -2 3".to_string()),
+                ScriptStatement::Exec("other.cfg".to_string()),
+                command(&["tp", "$pos"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_malformed_directives() {
+        let statements = parse_script("wait\nwait -1\nwait soon\nvar x\nexec a b");
+        assert!(statements.is_empty());
+    }
+
+    #[test]
+    fn splits_statements_and_strips_comments() {
+        let statements = parse_script("set sl 0; tp 0 -64 0 // teleport\n  \n# comment\n;;wait 1;");
+        assert_eq!(
+            statements,
+            vec![
+                command(&["set", "sl", "0"]),
+                command(&["tp", "0", "-64", "0"]),
+                ScriptStatement::Wait(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_group_words() {
+        let statements = parse_script("say \"a; b // c # d\" e");
+        assert_eq!(statements, vec![command(&["say", "a; b // c # d", "e"])]);
+    }
+
+    #[test]
+    fn single_slash_is_not_a_comment() {
+        let statements = parse_script("exec scripts/a.cfg");
+        assert_eq!(
+            statements,
+            vec![ScriptStatement::Exec("scripts/a.cfg".to_string())]
+        );
+    }
+
+    /// Run a script once and return the console commands it sent, `known_commands` are the
+    /// commands registered in the console.
+    fn run_script(source: &str, known_commands: &[&'static str]) -> Vec<(String, Vec<String>)> {
+        let mut world = World::new();
+        world.init_resource::<Events<ConsoleCommandEntered>>();
+        let mut console_config = ConsoleConfiguration::default();
+        for name in known_commands {
+            console_config
+                .commands
+                .insert(*name, clap::Command::new(*name));
+        }
+        let mut runner = ScriptRunner::default();
+        runner.push_source(source);
+        let mut state = SystemState::<EventWriter<ConsoleCommandEntered>>::new(&mut world);
+        runner.run(&console_config, &mut state.get_mut(&mut world));
+        world
+            .resource_mut::<Events<ConsoleCommandEntered>>()
+            .drain()
+            .map(|command| (command.command_name, command.args))
+            .collect()
+    }
+
+    #[test]
+    fn variables_are_split_into_args() {
+        let sent = run_script("var pos 1 -2 3\ntp $pos\nsay \"a b\" $pos", &["tp", "say"]);
+        let args =
+            |words: &[&str]| -> Vec<String> { words.iter().map(|word| word.to_string()).collect() };
+        assert_eq!(
+            sent,
+            vec![
+                ("tp".to_string(), args(&["1", "-2", "3"])),
+                ("say".to_string(), args(&["a b", "1", "-2", "3"])),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_commands_are_not_sent() {
+        let sent = run_script("fly 1 2 3\ntp 0 0 0", &["tp"]);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "tp");
+    }
+}
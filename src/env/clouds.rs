@@ -0,0 +1,375 @@
+//! A layer of clouds above the world. The clouds are generated from 2D noise (seeded by the
+//! [`TerrainConfig`]), and meshed just like the chunks are, each cloud "cell" is a voxel.
+use super::sky::{lerp_color, TimeOfDay};
+use super::weather::Weather;
+use crate::chunk::HEIGHT;
+use crate::player::PlayerCamera;
+use crate::prelude::*;
+use crate::terrain::TerrainConfig;
+use bevy::pbr::NotShadowCaster;
+use noise::{NoiseFn, Perlin};
+
+/// The amount of cloud cells along each (horizontal) axis of the cloud layer.
+pub const CLOUD_GRID_SIZE: u32 = 48;
+const CLOUD_GRID_CELLS: usize = (CLOUD_GRID_SIZE * CLOUD_GRID_SIZE) as usize;
+/// The default altitude of the clouds (above the highest possible block).
+pub const DEFAULT_CLOUD_ALTITUDE: f32 = HEIGHT as f32 + 24.0;
+/// The default size (width and length) of a cloud cell.
+pub const DEFAULT_CLOUD_CELL_SIZE: f32 = 12.0;
+/// The default thickness of the clouds when [`CloudStyle::Thick`] is used.
+pub const DEFAULT_CLOUD_THICKNESS: f32 = 4.0;
+/// The default wind, in blocks per second.
+pub const DEFAULT_WIND: Vec2 = Vec2::new(1.5, 0.5);
+/// How zoomed in the noise of the clouds is.
+pub const CLOUD_NOISE_SCALE: f64 = 0.09;
+/// The color of the clouds during the day.
+pub const CLOUD_DAY_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+/// The color of the clouds during the night.
+pub const CLOUD_NIGHT_COLOR: Color = Color::rgba(0.1, 0.11, 0.16, 0.8);
+/// The color of the clouds at sunrise and sunset.
+pub const CLOUD_TWILIGHT_COLOR: Color = Color::rgba(1.0, 0.7, 0.55, 0.8);
+/// The color of the clouds in a storm.
+pub const CLOUD_STORM_COLOR: Color = Color::rgba(0.35, 0.36, 0.4, 0.9);
+
+/// The shape of the clouds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloudStyle {
+    /// A flat layer, without any thickness. Each cloud cell is a single quad.
+    Flat,
+    /// Each cloud cell is a box, with the thickness of [`CloudSettings::thickness`].
+    Thick,
+}
+
+/// Resource to configure the clouds. Changing it rebuilds the cloud layer.
+#[derive(Resource, Clone, PartialEq)]
+pub struct CloudSettings {
+    pub enabled: bool,
+    pub style: CloudStyle,
+    /// The height of the bottom of the clouds.
+    pub altitude: f32,
+    pub thickness: f32,
+    pub cell_size: f32,
+    /// How much of the sky is covered with clouds, in the range [0, 1].
+    pub coverage: f32,
+    /// The direction and speed (blocks per second) the clouds drift in.
+    pub wind: Vec2,
+}
+
+impl Default for CloudSettings {
+    fn default() -> Self {
+        CloudSettings {
+            enabled: true,
+            style: CloudStyle::Thick,
+            altitude: DEFAULT_CLOUD_ALTITUDE,
+            thickness: DEFAULT_CLOUD_THICKNESS,
+            cell_size: DEFAULT_CLOUD_CELL_SIZE,
+            coverage: 0.4,
+            wind: DEFAULT_WIND,
+        }
+    }
+}
+
+impl CloudSettings {
+    fn voxel_dims(&self) -> [f32; 3] {
+        let thickness = match self.style {
+            CloudStyle::Flat => 0.0,
+            CloudStyle::Thick => self.thickness,
+        };
+        [self.cell_size, thickness, self.cell_size]
+    }
+}
+
+/// Marker for the entity of the cloud layer.
+#[derive(Component)]
+pub struct CloudLayer;
+
+/// The state of the cloud layer.
+#[derive(Resource)]
+pub struct Clouds {
+    /// How far the clouds have drifted with the wind.
+    pub drift: Vec2,
+    /// The cloud cell in the corner of the current mesh, [`None`] if it needs to be rebuilt.
+    origin: Option<IVec2>,
+    /// The distances (start and end) the current mesh is faded out at, [`None`] if it isn't.
+    fade: Option<Vec2>,
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+/// A [`VoxelRegistry`] for the cloud cells, a cell is either cloudy (true) or clear (false).
+struct CloudRegistry {
+    cube: Mesh,
+    voxel_dims: [f32; 3],
+}
+
+impl CloudRegistry {
+    fn new(voxel_dims: [f32; 3]) -> Self {
+        CloudRegistry {
+            cube: generate_voxel_mesh(
+                voxel_dims,
+                [1, 1],
+                [
+                    (Face::Top, [0, 0]),
+                    (Face::Bottom, [0, 0]),
+                    (Face::Right, [0, 0]),
+                    (Face::Left, [0, 0]),
+                    (Face::Back, [0, 0]),
+                    (Face::Forward, [0, 0]),
+                ],
+                [0.0, 0.0, 0.0],
+                0.0,
+                Some(1.0),
+                1.0,
+            ),
+            voxel_dims,
+        }
+    }
+}
+
+impl VoxelRegistry for CloudRegistry {
+    type Voxel = bool;
+
+    fn get_mesh(&self, voxel: &bool) -> VoxelMesh<&Mesh> {
+        if *voxel {
+            VoxelMesh::NormalCube(&self.cube)
+        } else {
+            VoxelMesh::Null
+        }
+    }
+
+    fn is_covering(&self, voxel: &bool, _side: Face) -> bool {
+        *voxel
+    }
+
+    fn get_center(&self) -> [f32; 3] {
+        [0.0, 0.0, 0.0]
+    }
+
+    fn get_voxel_dimensions(&self) -> [f32; 3] {
+        self.voxel_dims
+    }
+
+    fn all_attributes(&self) -> Vec<MeshVertexAttribute> {
+        vec![
+            Mesh::ATTRIBUTE_POSITION,
+            Mesh::ATTRIBUTE_UV_0,
+            Mesh::ATTRIBUTE_COLOR,
+            Mesh::ATTRIBUTE_NORMAL,
+        ]
+    }
+}
+
+/// Generate the mesh of the clouds, starting at the cloud cell `origin`.
+fn build_cloud_mesh(origin: IVec2, settings: &CloudSettings, seed: u32) -> Option<Mesh> {
+    let noise = Perlin::new(seed);
+    let threshold = 0.5 - settings.coverage as f64;
+    let mut cells = [false; CLOUD_GRID_CELLS];
+    for z in 0..CLOUD_GRID_SIZE {
+        for x in 0..CLOUD_GRID_SIZE {
+            let cell = origin + IVec2::new(x as i32, z as i32);
+            let value = noise.get([
+                cell.x as f64 * CLOUD_NOISE_SCALE,
+                cell.y as f64 * CLOUD_NOISE_SCALE,
+            ]);
+            cells[(z * CLOUD_GRID_SIZE + x) as usize] = value > threshold;
+        }
+    }
+    match settings.style {
+        CloudStyle::Flat => build_flat_cloud_mesh(&cells, settings.cell_size),
+        CloudStyle::Thick => {
+            let grid = ChunkGrid::new(cells, UVec3::new(CLOUD_GRID_SIZE, 1, CLOUD_GRID_SIZE));
+            let reg = CloudRegistry::new(settings.voxel_dims());
+            meshify_cubic_voxels(&[], &grid, &reg, MeshingAlgorithm::Culling, None, None)
+                .map(|(mesh, _)| mesh)
+        }
+    }
+}
+
+/// Generate the mesh of a flat cloud layer, one quad (facing down) per cloudy cell. The material
+/// doesn't cull the back faces in this style, so the clouds can be seen from above too.
+fn build_flat_cloud_mesh(cells: &[bool; CLOUD_GRID_CELLS], cell_size: f32) -> Option<Mesh> {
+    let half = cell_size / 2.0;
+    let mut positions = vec![];
+    let mut indices = vec![];
+    for (i, _) in cells.iter().enumerate().filter(|(_, cloudy)| **cloudy) {
+        let x = (i as u32 % CLOUD_GRID_SIZE) as f32 * cell_size;
+        let z = (i as u32 / CLOUD_GRID_SIZE) as f32 * cell_size;
+        let first = positions.len() as u32;
+        positions.extend([
+            [x - half, 0.0, z - half],
+            [x + half, 0.0, z - half],
+            [x + half, 0.0, z + half],
+            [x - half, 0.0, z + half],
+        ]);
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+    if positions.is_empty() {
+        return None;
+    }
+    let vertex_count = positions.len();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; vertex_count]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![[1.0; 4]; vertex_count]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, -1.0, 0.0]; vertex_count]);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    Some(mesh)
+}
+
+/// The distances (start and end) the clouds should fade out at, which are the distances of the
+/// fog. Without a (linear) fog the clouds don't fade out.
+fn cloud_fade(fog: Option<&FogSettings>) -> Vec2 {
+    match fog.map(|fog| &fog.falloff) {
+        Some(&FogFalloff::Linear { start, end }) => Vec2::new(start, end),
+        _ => Vec2::splat(f32::MAX),
+    }
+}
+
+/// Fade out the clouds towards the end of the fog, by the alpha of their vertices. Otherwise the
+/// clouds past the fog would stand out from the sky in the color of the fog. `center` is the
+/// (local) position of the player in the cloud layer.
+fn fade_cloud_mesh(mesh: &mut Mesh, center: Vec2, fade: Vec2) {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+    let alphas: Vec<f32> = positions
+        .iter()
+        .map(|[x, _, z]| {
+            let distance = Vec2::new(*x, *z).distance(center);
+            if fade.y > fade.x {
+                1.0 - ((distance - fade.x) / (fade.y - fade.x)).clamp(0.0, 1.0)
+            } else {
+                1.0
+            }
+        })
+        .collect();
+    if let Some(VertexAttributeValues::Float32x4(colors)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
+    {
+        for (color, alpha) in colors.iter_mut().zip(alphas) {
+            color[3] = alpha;
+        }
+    }
+}
+
+pub fn setup_clouds(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mesh = meshes.add(Mesh::new(PrimitiveTopology::TriangleList));
+    let material = materials.add(StandardMaterial {
+        base_color: CLOUD_DAY_COLOR,
+        alpha_mode: AlphaMode::Blend,
+        perceptual_roughness: 1.0,
+        reflectance: 0.0,
+        ..default()
+    });
+    commands.spawn((
+        PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            // Hidden until the mesh is built
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NotShadowCaster,
+        CloudLayer,
+    ));
+    commands.insert_resource(Clouds {
+        drift: Vec2::ZERO,
+        origin: None,
+        fade: None,
+        mesh,
+        material,
+    });
+}
+
+/// Move the clouds with the wind, and rebuild their mesh when the player moves to a new cloud
+/// cell (so the player is always in the middle of the cloud layer). The clouds fade out with the
+/// fog.
+pub fn update_clouds(
+    mut clouds: ResMut<Clouds>,
+    settings: Res<CloudSettings>,
+    terrain_config: Res<TerrainConfig>,
+    time: Res<Time>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    camera: Query<(&GlobalTransform, Option<&FogSettings>), With<PlayerCamera>>,
+    mut cloud_layer: Query<(&mut Transform, &mut Visibility), With<CloudLayer>>,
+) {
+    let Ok((mut transform, mut visibility)) = cloud_layer.get_single_mut() else {
+        return;
+    };
+    let Ok((camera, fog)) = camera.get_single() else {
+        return;
+    };
+    if settings.is_changed() || terrain_config.is_changed() {
+        clouds.origin = None;
+        if let Some(material) = materials.get_mut(&clouds.material) {
+            material.cull_mode = match settings.style {
+                CloudStyle::Flat => None,
+                CloudStyle::Thick => Some(bevy::render::render_resource::Face::Back),
+            };
+        }
+    }
+    if !settings.enabled {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    clouds.drift += settings.wind * time.delta_seconds();
+    let camera_pos = Vec2::new(camera.translation().x, camera.translation().z);
+    let camera_cell = ((camera_pos - clouds.drift) / settings.cell_size)
+        .floor()
+        .as_ivec2();
+    let origin = camera_cell - IVec2::splat(CLOUD_GRID_SIZE as i32 / 2);
+
+    if clouds.origin != Some(origin) {
+        let seed = terrain_config.noise_seed as u32;
+        if let Some(mesh) = build_cloud_mesh(origin, &settings, seed) {
+            if let Some(old_mesh) = meshes.get_mut(&clouds.mesh) {
+                *old_mesh = mesh;
+            }
+            clouds.origin = Some(origin);
+            clouds.fade = None;
+            *visibility = Visibility::Visible;
+        }
+    }
+
+    // The fog changes with the render distance and the weather
+    let fade = cloud_fade(fog);
+    if clouds.origin.is_some() && clouds.fade.map_or(true, |old| old.distance(fade) > 1.0) {
+        if let Some(mesh) = meshes.get_mut(&clouds.mesh) {
+            // The player is in the middle cell of the cloud layer
+            let center = Vec2::splat((CLOUD_GRID_SIZE / 2) as f32 * settings.cell_size);
+            fade_cloud_mesh(mesh, center, fade);
+        }
+        clouds.fade = Some(fade);
+    }
+
+    // The center of the first voxel in the mesh is at the local origin
+    let corner = (origin.as_vec2() + 0.5) * settings.cell_size + clouds.drift;
+    let altitude = settings.altitude + settings.voxel_dims()[1] / 2.0;
+    transform.translation = Vec3::new(corner.x, altitude, corner.y);
+}
+
+/// Tint the clouds according to the time of day and the weather.
+pub fn update_cloud_color(
+    clouds: Res<Clouds>,
+    time_of_day: Res<TimeOfDay>,
+    weather: Res<Weather>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if let Some(material) = materials.get_mut(&clouds.material) {
+        let day = lerp_color(
+            CLOUD_DAY_COLOR,
+            CLOUD_TWILIGHT_COLOR,
+            time_of_day.twilight() * 0.6,
+        );
+        let day = lerp_color(day, CLOUD_STORM_COLOR, weather.cloudiness());
+        material.base_color = lerp_color(CLOUD_NIGHT_COLOR, day, time_of_day.daylight());
+    }
+}
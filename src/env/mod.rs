@@ -1,17 +1,27 @@
 use crate::prelude::*;
 
+mod clouds;
 mod sky;
+mod weather;
 
+use clouds::*;
+pub use clouds::{CloudSettings, CloudStyle};
 use sky::*;
+pub use sky::{Moon, Sun, TimeOfDay};
+use weather::*;
+pub use weather::{Weather, WeatherKind};
 
 pub struct EnviornmentPlugin;
 
 impl Plugin for EnviornmentPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(AmbientLight {
-            brightness: 1.0,
-            color: Color::rgb(0.8, 0.9, 0.9),
+            brightness: DAY_AMBIENT_BRIGHTNESS,
+            color: DAY_AMBIENT_COLOR,
         })
+        .init_resource::<TimeOfDay>()
+        .init_resource::<Weather>()
+        .init_resource::<CloudSettings>()
         // .insert_resource(Msaa::Sample4)
         .insert_resource(AtmosphereModel::default()) // Default Atmosphere material, we can edit it to simulate another planet
         .insert_resource(CycleTimer(Timer::new(
@@ -21,8 +31,23 @@ impl Plugin for EnviornmentPlugin {
         .add_plugins((
             AtmospherePlugin, // Default AtmospherePlugin
         ))
-        .add_systems(Startup, setup_environment)
-        .add_systems(Update, daylight_cycle)
+        .add_systems(
+            Startup,
+            (setup_environment, setup_precipitation, setup_clouds),
+        )
+        .add_systems(
+            Update,
+            (
+                advance_time_of_day,
+                update_weather,
+                daylight_cycle,
+                update_ambient_light_and_fog,
+                update_precipitation,
+                update_clouds,
+                update_cloud_color,
+            )
+                .chain(),
+        )
         .insert_resource(ClearColor(Color::rgb(0.70, 0.95, 1.0)));
     }
 }
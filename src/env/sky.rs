@@ -1,34 +1,197 @@
+use super::weather::Weather;
+use crate::chunk::RenderSettings;
+use crate::player::{fog_falloff, PlayerCamera, FOG_COLOR};
 use bevy::prelude::*;
 pub use bevy_atmosphere::prelude::*;
+use std::f32::consts::TAU;
+
+/// The default length of a full day (day and night) in seconds.
+pub const DEFAULT_DAY_LENGTH: f32 = 600.0;
+/// The time of day the game starts at (noon is 0.5, see [`TimeOfDay`]).
+pub const STARTING_TIME_OF_DAY: f32 = 0.4;
+/// Maximum illuminance of the sun (at noon).
+pub const SUN_ILLUMINANCE: f32 = 2000.0;
+/// Maximum illuminance of the moon (at midnight).
+pub const MOON_ILLUMINANCE: f32 = 120.0;
+/// The color of the moon light.
+pub const MOON_COLOR: Color = Color::rgb(0.6, 0.7, 1.0);
+/// Ambient light during the day.
+pub const DAY_AMBIENT_COLOR: Color = Color::rgb(0.8, 0.9, 0.9);
+pub const DAY_AMBIENT_BRIGHTNESS: f32 = 1.0;
+/// Ambient light during the night.
+pub const NIGHT_AMBIENT_COLOR: Color = Color::rgb(0.35, 0.4, 0.6);
+pub const NIGHT_AMBIENT_BRIGHTNESS: f32 = 0.15;
+/// The color of the fog during the night (during the day it's [`FOG_COLOR`]).
+pub const NIGHT_FOG_COLOR: Color = Color::rgb(0.02, 0.03, 0.08);
+/// The color of the fog at sunrise and sunset.
+pub const TWILIGHT_FOG_COLOR: Color = Color::rgb(0.85, 0.55, 0.4);
+/// The color of the fog in bad weather (it's blended in according to [`Weather::cloudiness`]).
+pub const CLOUDY_FOG_COLOR: Color = Color::rgb(0.45, 0.48, 0.52);
+/// The intensity of the sun in the [`Nishita`] sky model (its default).
+pub const ATMOSPHERE_SUN_INTENSITY: f32 = 22.0;
+/// How bright the ambient light gets during a lightning flash.
+pub const LIGHTNING_BRIGHTNESS: f32 = 2.5;
 
 // Marker for updating the position of the light, not needed unless we have multiple lights
 #[derive(Component)]
 pub struct Sun;
 
+/// Marker for the moon's directional light, it lights the world (dimly) during the night.
+#[derive(Component)]
+pub struct Moon;
+
 // Timer for updating the daylight cycle (updating the atmosphere every frame is slow, so it's better to do incremental changes)
 #[derive(Resource)]
 pub struct CycleTimer(pub Timer);
 
+/// The time of day in the world. `time` is the fraction of the day that has passed, in the range
+/// [0, 1): 0.0 is midnight, 0.25 is sunrise, 0.5 is noon and 0.75 is sunset.
+#[derive(Resource)]
+pub struct TimeOfDay {
+    pub time: f32,
+    /// Length of a full day (day and night) in seconds.
+    pub day_length: f32,
+    /// When frozen, the time of day doesn't advance.
+    pub frozen: bool,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        TimeOfDay {
+            time: STARTING_TIME_OF_DAY,
+            day_length: DEFAULT_DAY_LENGTH,
+            frozen: false,
+        }
+    }
+}
+
+impl TimeOfDay {
+    /// Set the time of day, any value is wrapped to the range [0, 1).
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time.rem_euclid(1.0);
+    }
+
+    /// Set the time of day in hours (0 - 24).
+    pub fn set_hours(&mut self, hours: f32) {
+        self.set_time(hours / 24.0);
+    }
+
+    /// The time of day in hours (0 - 24).
+    pub fn hours(&self) -> f32 {
+        self.time * 24.0
+    }
+
+    /// The angle of the sun, 0 at sunrise and PI at sunset.
+    pub fn sun_angle(&self) -> f32 {
+        (self.time - 0.25) * TAU
+    }
+
+    /// The height of the sun in the sky, in the range [-1, 1] (1 at noon, -1 at midnight).
+    pub fn sun_height(&self) -> f32 {
+        self.sun_angle().sin()
+    }
+
+    /// How much daylight there is, in the range [0, 1]. The transition between day and night is
+    /// smoothed around sunrise and sunset.
+    pub fn daylight(&self) -> f32 {
+        let t = ((self.sun_height() + 0.1) / 0.3).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /// How close the sun is to the horizon, 1 at sunrise / sunset, 0 when it's far from it.
+    pub fn twilight(&self) -> f32 {
+        (1.0 - self.sun_height().abs() / 0.25).clamp(0.0, 1.0)
+    }
+}
+
+/// Advance the time of day.
+pub fn advance_time_of_day(mut time_of_day: ResMut<TimeOfDay>, time: Res<Time>) {
+    if time_of_day.frozen || time_of_day.day_length <= 0.0 {
+        return;
+    }
+    let delta = time.delta_seconds() / time_of_day.day_length;
+    let new_time = time_of_day.time + delta;
+    time_of_day.set_time(new_time);
+}
+
 // We can edit the Atmosphere resource and it will be updated automatically
 pub fn daylight_cycle(
     mut atmosphere: AtmosphereMut<Nishita>,
-    mut query: Query<(&mut Transform, &mut DirectionalLight), With<Sun>>,
+    mut sun: Query<(&mut Transform, &mut DirectionalLight), (With<Sun>, Without<Moon>)>,
+    mut moon: Query<(&mut Transform, &mut DirectionalLight), (With<Moon>, Without<Sun>)>,
     mut timer: ResMut<CycleTimer>,
+    time_of_day: Res<TimeOfDay>,
+    weather: Res<Weather>,
     time: Res<Time>,
 ) {
     timer.0.tick(time.delta());
 
-    if timer.0.finished() {
-        let t: f32 = 1.0;
+    // Always update the sky when the time of day was changed manually
+    if timer.0.finished() || (time_of_day.is_changed() && time_of_day.frozen) {
+        let t = time_of_day.sun_angle();
         atmosphere.sun_position = Vec3::new(0., t.sin(), t.cos());
+        atmosphere.sun_intensity = ATMOSPHERE_SUN_INTENSITY * weather.light_factor();
 
-        if let Some((mut light_trans, mut directional)) = query.single_mut().into() {
+        if let Ok((mut light_trans, mut directional)) = sun.get_single_mut() {
             light_trans.rotation = Quat::from_rotation_x(-t);
-            directional.illuminance = t.sin().max(0.0).powf(2.0) * 2000.0;
+            directional.illuminance =
+                t.sin().max(0.0).powf(2.0) * SUN_ILLUMINANCE * weather.light_factor();
+        }
+        // The moon is always on the opposite side of the sun
+        if let Ok((mut light_trans, mut directional)) = moon.get_single_mut() {
+            light_trans.rotation = Quat::from_rotation_x(-t - std::f32::consts::PI);
+            directional.illuminance =
+                (-t.sin()).max(0.0).powf(2.0) * MOON_ILLUMINANCE * weather.light_factor();
         }
     }
 }
 
+/// Update the ambient light and the fog according to the time of day and the weather.
+pub fn update_ambient_light_and_fog(
+    time_of_day: Res<TimeOfDay>,
+    weather: Res<Weather>,
+    render_settings: Res<RenderSettings>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut fog: Query<&mut FogSettings, With<PlayerCamera>>,
+) {
+    let daylight = time_of_day.daylight();
+    let flash = weather.lightning_flash();
+    ambient_light.color = lerp_color(NIGHT_AMBIENT_COLOR, DAY_AMBIENT_COLOR, daylight);
+    ambient_light.brightness = lerp(NIGHT_AMBIENT_BRIGHTNESS, DAY_AMBIENT_BRIGHTNESS, daylight)
+        * weather.light_factor()
+        + flash * LIGHTNING_BRIGHTNESS;
+
+    for mut fog in fog.iter_mut() {
+        let clear = lerp_color(FOG_COLOR, TWILIGHT_FOG_COLOR, time_of_day.twilight() * 0.5);
+        let day = lerp_color(clear, CLOUDY_FOG_COLOR, weather.cloudiness());
+        let color = lerp_color(NIGHT_FOG_COLOR, day, daylight * weather.light_factor());
+        fog.color = lerp_color(color, Color::WHITE, flash * 0.5);
+        // The fog follows the render distance, so the edge of the world is always hidden.
+        fog.falloff = fog_falloff(
+            render_settings.render_distance,
+            weather.fog_distance_factor(),
+        );
+    }
+}
+
+/// Linear interpolation between two values.
+pub fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+/// Linear interpolation between two colors (in linear space).
+pub fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let from = from.as_rgba_linear();
+    let to = to.as_rgba_linear();
+    Color::rgba_linear(
+        lerp(from.r(), to.r(), t),
+        lerp(from.g(), to.g(), t),
+        lerp(from.b(), to.b(), t),
+        lerp(from.a(), to.a(), t),
+    )
+    .as_rgba()
+}
+
 // Simple environment
 pub fn setup_environment(mut commands: Commands) {
     // Our Sun
@@ -38,4 +201,16 @@ pub fn setup_environment(mut commands: Commands) {
         },
         Sun, // Marks the light as Sun
     ));
+    // The moon
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: MOON_COLOR,
+                illuminance: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Moon,
+    ));
 }
@@ -0,0 +1,341 @@
+use crate::chunk::{ChunkMap, HeightMap, CHUNK_DIMS};
+use crate::player::PlayerCamera;
+use crate::prelude::*;
+use bevy::pbr::NotShadowCaster;
+use rand::prelude::*;
+
+/// Maximum amount of precipitation particles (at full intensity).
+pub const MAX_PRECIPITATION_PARTICLES: usize = 2000;
+/// The horizontal radius around the camera in which precipitation particles are spawned.
+pub const PRECIPITATION_RADIUS: f32 = 20.0;
+/// How high above the camera precipitation particles are spawned.
+pub const PRECIPITATION_HEIGHT: f32 = 16.0;
+/// How far below the camera a precipitation particle can fall before it's respawned.
+pub const PRECIPITATION_DEPTH: f32 = 12.0;
+/// The velocity of a rain drop.
+pub const RAIN_VELOCITY: Vec3 = Vec3::new(0.5, -18.0, 0.3);
+/// The velocity of a snow flake.
+pub const SNOW_VELOCITY: Vec3 = Vec3::new(0.3, -2.0, 0.2);
+/// How long (in seconds) it takes to transition from one weather to another.
+pub const WEATHER_TRANSITION_TIME: f32 = 10.0;
+/// The range of durations (in seconds) a weather lasts before changing.
+pub const WEATHER_DURATION: std::ops::Range<f32> = 120.0..480.0;
+/// The range of time (in seconds) between two lightning strikes during a storm.
+pub const LIGHTNING_INTERVAL: std::ops::Range<f32> = 3.0..15.0;
+/// How fast (per second) the flash of a lightning strike fades.
+pub const LIGHTNING_FADE_SPEED: f32 = 4.0;
+
+/// All of the possible weathers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Snow,
+    Storm,
+}
+
+impl WeatherKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clear" => Some(Self::Clear),
+            "rain" => Some(Self::Rain),
+            "snow" => Some(Self::Snow),
+            "storm" | "thunder" => Some(Self::Storm),
+            _ => None,
+        }
+    }
+
+    /// How much the weather covers the sky, in the range [0, 1].
+    pub fn cloudiness(&self) -> f32 {
+        match self {
+            Self::Clear => 0.0,
+            Self::Snow => 0.4,
+            Self::Rain => 0.5,
+            Self::Storm => 1.0,
+        }
+    }
+
+    /// Randomly pick the weather that comes after this one.
+    fn pick_next(&self, rng: &mut impl Rng) -> Self {
+        let options: &[(Self, u32)] = match self {
+            Self::Clear => &[(Self::Rain, 5), (Self::Snow, 2), (Self::Storm, 1)],
+            Self::Rain => &[(Self::Clear, 6), (Self::Storm, 3)],
+            Self::Snow => &[(Self::Clear, 1)],
+            Self::Storm => &[(Self::Rain, 1), (Self::Clear, 1)],
+        };
+        options
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .map(|(kind, _)| *kind)
+            .unwrap_or(Self::Clear)
+    }
+}
+
+/// The weather in the world. The weather changes every once in a while, the transitions between
+/// two weathers are smooth: the current weather fades out, and then the next one fades in.
+#[derive(Resource)]
+pub struct Weather {
+    kind: WeatherKind,
+    next: Option<WeatherKind>,
+    /// How strong the current weather is, in the range [0, 1].
+    intensity: f32,
+    /// Time (in seconds) until the weather changes.
+    pub time_left: f32,
+    /// When frozen, the weather doesn't change on its own.
+    pub frozen: bool,
+    /// The brightness of the current lightning flash, in the range [0, 1].
+    flash: f32,
+    next_strike: f32,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Weather {
+            kind: WeatherKind::Clear,
+            next: None,
+            intensity: 1.0,
+            time_left: WEATHER_DURATION.start,
+            frozen: false,
+            flash: 0.0,
+            next_strike: LIGHTNING_INTERVAL.start,
+        }
+    }
+}
+
+impl Weather {
+    /// The current weather.
+    pub fn kind(&self) -> WeatherKind {
+        self.kind
+    }
+
+    /// The weather the world is transitioning to, if there is one.
+    pub fn next(&self) -> Option<WeatherKind> {
+        self.next
+    }
+
+    /// How strong the current weather is, in the range [0, 1].
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Change the weather (with a smooth transition). If `duration` is [`None`], it will last a
+    /// random amount of time.
+    pub fn set(&mut self, kind: WeatherKind, duration: Option<f32>) {
+        self.next = (kind != self.kind || self.next.is_some()).then_some(kind);
+        self.time_left = duration.unwrap_or_else(|| thread_rng().gen_range(WEATHER_DURATION));
+    }
+
+    /// How much the weather covers the sky, in the range [0, 1].
+    pub fn cloudiness(&self) -> f32 {
+        self.kind.cloudiness() * self.intensity
+    }
+
+    /// How much of the light (sun, moon and ambient) gets through the clouds.
+    pub fn light_factor(&self) -> f32 {
+        1.0 - 0.7 * self.cloudiness()
+    }
+
+    /// How much the weather shrinks the distance of the fog.
+    pub fn fog_distance_factor(&self) -> f32 {
+        1.0 - 0.5 * self.cloudiness()
+    }
+
+    /// The brightness of the current lightning flash, in the range [0, 1].
+    pub fn lightning_flash(&self) -> f32 {
+        self.flash
+    }
+
+    /// How many precipitation particles should be active.
+    pub fn precipitation_amount(&self) -> f32 {
+        match self.kind {
+            WeatherKind::Clear => 0.0,
+            WeatherKind::Rain | WeatherKind::Snow => self.intensity * 0.7,
+            WeatherKind::Storm => self.intensity,
+        }
+    }
+
+    fn advance(&mut self, delta: f32, rng: &mut impl Rng) {
+        match self.next {
+            Some(next) => {
+                // Fade out the current weather, then switch to the next one.
+                self.intensity -= delta / WEATHER_TRANSITION_TIME;
+                if self.intensity <= 0.0 {
+                    self.intensity = 0.0;
+                    self.kind = next;
+                    self.next = None;
+                }
+            }
+            None => self.intensity = (self.intensity + delta / WEATHER_TRANSITION_TIME).min(1.0),
+        }
+
+        if !self.frozen {
+            self.time_left -= delta;
+            if self.time_left <= 0.0 {
+                let next = self.kind.pick_next(rng);
+                self.set(next, None);
+            }
+        }
+
+        self.flash = (self.flash - delta * LIGHTNING_FADE_SPEED).max(0.0);
+        if self.kind == WeatherKind::Storm && self.intensity > 0.5 {
+            self.next_strike -= delta;
+            if self.next_strike <= 0.0 {
+                self.flash = 1.0;
+                self.next_strike = rng.gen_range(LIGHTNING_INTERVAL);
+            }
+        }
+    }
+}
+
+/// A single precipitation particle (rain drop or snow flake).
+#[derive(Component)]
+pub struct Precipitation {
+    velocity: Vec3,
+    alive: bool,
+}
+
+/// The meshes and materials of the precipitation particles.
+#[derive(Resource)]
+pub struct PrecipitationAssets {
+    rain_mesh: Handle<Mesh>,
+    rain_material: Handle<StandardMaterial>,
+    snow_mesh: Handle<Mesh>,
+    snow_material: Handle<StandardMaterial>,
+}
+
+/// Spawn the pool of precipitation particles, they are all hidden until needed.
+pub fn setup_precipitation(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let assets = PrecipitationAssets {
+        rain_mesh: meshes.add(shape::Box::new(0.02, 0.6, 0.02).into()),
+        rain_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.6, 0.7, 0.9, 0.5),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        snow_mesh: meshes.add(shape::Box::new(0.08, 0.08, 0.08).into()),
+        snow_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 1.0, 1.0, 0.9),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+    };
+    for _ in 0..MAX_PRECIPITATION_PARTICLES {
+        commands.spawn((
+            PbrBundle {
+                mesh: assets.rain_mesh.clone(),
+                material: assets.rain_material.clone(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            NotShadowCaster,
+            Precipitation {
+                velocity: Vec3::ZERO,
+                alive: false,
+            },
+        ));
+    }
+    commands.insert_resource(assets);
+}
+
+/// Advance the weather.
+pub fn update_weather(mut weather: ResMut<Weather>, time: Res<Time>) {
+    weather.advance(time.delta_seconds(), &mut thread_rng());
+}
+
+/// The height of the ground (the highest block that blocks precipitation) at a point. [`None`] if
+/// the chunk isn't loaded.
+fn ground_height(
+    point: Vec3,
+    chunk_map: &ChunkMap,
+    height_maps: &Query<&HeightMap>,
+) -> Option<f32> {
+    let global_pos = point_to_global_block_pos(point, CHUNK_DIMS);
+    let entity = chunk_map.pos_to_ent.get(&global_pos.chunk_cords)?;
+    let height_map = height_maps.get(*entity).ok()?;
+    Some(height_map.get(global_pos.pos.x, global_pos.pos.z) as f32 - 0.5)
+}
+
+/// Move the precipitation particles, and respawn the ones that hit the ground (or left the area
+/// around the camera). Particles don't spawn under roofs, so the inside of buildings stays dry.
+pub fn update_precipitation(
+    weather: Res<Weather>,
+    assets: Res<PrecipitationAssets>,
+    time: Res<Time>,
+    chunk_map: Res<ChunkMap>,
+    height_maps: Query<&HeightMap>,
+    camera: Query<&GlobalTransform, With<PlayerCamera>>,
+    mut particles: Query<(
+        &mut Precipitation,
+        &mut Transform,
+        &mut Visibility,
+        &mut Handle<Mesh>,
+        &mut Handle<StandardMaterial>,
+    )>,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    let camera_pos = camera.translation();
+    let delta = time.delta_seconds();
+    let active = (weather.precipitation_amount() * MAX_PRECIPITATION_PARTICLES as f32) as usize;
+    let (mesh, material, velocity) = match weather.kind() {
+        WeatherKind::Snow => (&assets.snow_mesh, &assets.snow_material, SNOW_VELOCITY),
+        _ => (&assets.rain_mesh, &assets.rain_material, RAIN_VELOCITY),
+    };
+    let mut rng = thread_rng();
+
+    for (i, (mut particle, mut transform, mut visibility, mut mesh_handle, mut mat_handle)) in
+        particles.iter_mut().enumerate()
+    {
+        if i >= active {
+            if particle.alive {
+                particle.alive = false;
+                *visibility = Visibility::Hidden;
+            }
+            continue;
+        }
+
+        let mut pos = transform.translation + particle.velocity * delta;
+        let offset = pos - camera_pos;
+        let out_of_range = offset.x.abs() > PRECIPITATION_RADIUS
+            || offset.z.abs() > PRECIPITATION_RADIUS
+            || offset.y < -PRECIPITATION_DEPTH;
+        let hit_ground = ground_height(pos, &chunk_map, &height_maps).is_some_and(|h| pos.y < h);
+
+        if !particle.alive || out_of_range || hit_ground {
+            // Respawn the particle at a random position above the camera.
+            pos = camera_pos
+                + Vec3::new(
+                    rng.gen_range(-PRECIPITATION_RADIUS..PRECIPITATION_RADIUS),
+                    rng.gen_range(0.0..PRECIPITATION_HEIGHT),
+                    rng.gen_range(-PRECIPITATION_RADIUS..PRECIPITATION_RADIUS),
+                );
+            // If there's a roof above the spawn point, try again next frame.
+            let covered = ground_height(pos, &chunk_map, &height_maps).is_some_and(|h| pos.y < h);
+            particle.alive = !covered;
+            particle.velocity = velocity * rng.gen_range(0.8..1.2);
+            *visibility = if covered {
+                Visibility::Hidden
+            } else {
+                Visibility::Visible
+            };
+            if *mesh_handle != *mesh {
+                *mesh_handle = mesh.clone();
+                *mat_handle = material.clone();
+            }
+        }
+
+        if weather.kind() == WeatherKind::Snow {
+            // Snow flakes flutter a bit
+            let t = time.elapsed_seconds() + i as f32;
+            pos.x += (t * 1.7).sin() * 0.5 * delta;
+            pos.z += (t * 1.3).cos() * 0.5 * delta;
+        }
+        transform.translation = pos;
+    }
+}
@@ -0,0 +1,605 @@
+//! Export the meshes of the loaded chunks (or of a region of them) to a `.glb` or an `.obj` file,
+//! to render them in other tools. The transforms of the subchunks are applied to the vertices, and
+//! each subchunk of a chunk (cube, xsprite, partial, translucent and custom) is a separate
+//! primitive (a separate object in `.obj` files). The atlas is embedded in `.glb` files, and saved
+//! next to `.obj` files (as a `.png`, with a `.mtl` that refers to it).
+//! Chunks that aren't at full detail (see [`LodLevel`](crate::chunk::LodLevel)) aren't exported.
+use crate::blocks::atlas::BLOCK_ATLAS_IMAGE;
+use crate::chunk::{
+    ChunkCords, Cords, CubeSubChunk, CustomSubChunk, PartialSubChunk, TranslucentSubChunk,
+    UnpackedMesh, XSpriteSubChunk,
+};
+use crate::prelude::*;
+use bevy::ecs::system::SystemParam;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{self, BufWriter, Cursor, Write};
+use std::path::Path;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+const GLB_JSON_CHUNK: &[u8; 4] = b"JSON";
+const GLB_BIN_CHUNK: &[u8; 4] = b"BIN\0";
+const GLTF_FLOAT: u32 = 5126;
+const GLTF_UNSIGNED_INT: u32 = 5125;
+const GLTF_ARRAY_BUFFER: u32 = 34962;
+const GLTF_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const GLTF_NEAREST: u32 = 9728;
+const GLTF_CLAMP_TO_EDGE: u32 = 33071;
+
+/// The file formats the meshes can be exported to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    Glb,
+    Obj,
+}
+
+impl ExportFormat {
+    /// The format of a file, by its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "glb" => Some(Self::Glb),
+            "obj" => Some(Self::Obj),
+            _ => None,
+        }
+    }
+}
+
+/// The type of subchunk an exported mesh comes from. Each type has its own material.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportMeshKind {
+    Cube,
+    XSprite,
+    Partial,
+    Translucent,
+    Custom,
+}
+
+impl ExportMeshKind {
+    const ALL: [ExportMeshKind; 5] = [
+        ExportMeshKind::Cube,
+        ExportMeshKind::XSprite,
+        ExportMeshKind::Partial,
+        ExportMeshKind::Translucent,
+        ExportMeshKind::Custom,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cube => "cube",
+            Self::XSprite => "xsprite",
+            Self::Partial => "partial",
+            Self::Translucent => "translucent",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+/// A mesh to export, the mesh of a subchunk in world space.
+pub struct ExportMesh {
+    pub cords: ChunkCords,
+    pub kind: ExportMeshKind,
+    /// The (unpacked) mesh of the subchunk, with the transform of the subchunk applied.
+    pub mesh: Mesh,
+}
+
+/// A rectangle of chunks (inclusive).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChunkRegion {
+    pub min: ChunkCords,
+    pub max: ChunkCords,
+}
+
+impl ChunkRegion {
+    /// The region between two corners (in any order).
+    pub fn new(a: ChunkCords, b: ChunkCords) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn contains(&self, cords: ChunkCords) -> bool {
+        cords.cmpge(self.min).all() && cords.cmple(self.max).all()
+    }
+}
+
+/// The meshes of the loaded chunks, and the atlas, to export them.
+#[derive(SystemParam)]
+pub struct LoadedChunkMeshes<'w, 's> {
+    meshes: Res<'w, Assets<Mesh>>,
+    images: Res<'w, Assets<Image>>,
+    chunks: Query<'w, 's, &'static Cords>,
+    subchunks: Query<
+        'w,
+        's,
+        (
+            &'static Parent,
+            &'static Handle<Mesh>,
+            &'static GlobalTransform,
+            Option<&'static UnpackedMesh>,
+            (
+                Has<CubeSubChunk>,
+                Has<XSpriteSubChunk>,
+                Has<PartialSubChunk>,
+                Has<TranslucentSubChunk>,
+            ),
+        ),
+        Or<(
+            With<CubeSubChunk>,
+            With<XSpriteSubChunk>,
+            With<PartialSubChunk>,
+            With<TranslucentSubChunk>,
+            With<CustomSubChunk>,
+        )>,
+    >,
+}
+
+impl LoadedChunkMeshes<'_, '_> {
+    /// The meshes of the subchunks of the loaded chunks in the region (or of all of them), ordered
+    /// by chunk.
+    pub fn collect(&self, region: Option<ChunkRegion>) -> Vec<ExportMesh> {
+        let mut meshes: Vec<ExportMesh> = self
+            .subchunks
+            .iter()
+            .filter_map(|(parent, mesh_handle, transform, unpacked, markers)| {
+                let Cords(cords) = *self.chunks.get(parent.get()).ok()?;
+                if region.is_some_and(|region| !region.contains(cords)) {
+                    return None;
+                }
+                // The packed mesh is only what is rendered, the unpacked mesh has the positions.
+                let mesh = match unpacked {
+                    Some(UnpackedMesh(mesh)) => mesh,
+                    None => self.meshes.get(mesh_handle)?,
+                };
+                let kind = match markers {
+                    (true, _, _, _) => ExportMeshKind::Cube,
+                    (_, true, _, _) => ExportMeshKind::XSprite,
+                    (_, _, true, _) => ExportMeshKind::Partial,
+                    (_, _, _, true) => ExportMeshKind::Translucent,
+                    _ => ExportMeshKind::Custom,
+                };
+                Some(ExportMesh {
+                    cords,
+                    kind,
+                    mesh: transformed_mesh(mesh, transform.compute_transform()),
+                })
+            })
+            .collect();
+        meshes.sort_by_key(|mesh| (mesh.cords.x, mesh.cords.y, mesh.kind as usize));
+        meshes
+    }
+
+    /// The atlas of the block textures, if it has been stitched.
+    pub fn atlas(&self) -> Option<&Image> {
+        self.images.get(BLOCK_ATLAS_IMAGE)
+    }
+}
+
+/// Apply a transform to the positions and normals of a mesh.
+fn transformed_mesh(mesh: &Mesh, transform: Transform) -> Mesh {
+    let mut mesh = mesh.clone();
+    if let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    {
+        for position in positions.iter_mut() {
+            *position = transform.transform_point((*position).into()).into();
+        }
+    }
+    if let Some(VertexAttributeValues::Float32x3(normals)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_NORMAL)
+    {
+        for normal in normals.iter_mut() {
+            let scaled = Vec3::from(*normal) / transform.scale;
+            *normal = (transform.rotation * scaled).normalize_or_zero().into();
+        }
+    }
+    mesh
+}
+
+/// Export the meshes to a file, in the format of its extension (see [`ExportFormat`]), with the
+/// atlas of the block textures.
+pub fn export_meshes(path: &Path, meshes: &[ExportMesh], atlas: &Image) -> io::Result<()> {
+    let png = encode_png(atlas)?;
+    match ExportFormat::from_path(path) {
+        Some(ExportFormat::Glb) => write_glb(path, meshes, &png),
+        Some(ExportFormat::Obj) => write_obj(path, meshes, &png),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "expected a .glb or an .obj file",
+        )),
+    }
+}
+
+fn encode_png(image: &Image) -> io::Result<Vec<u8>> {
+    let dynamic_image = image
+        .clone()
+        .try_into_dynamic()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    let mut png = vec![];
+    dynamic_image
+        .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(png)
+}
+
+/// The vertices of a mesh, the attributes that can be exported.
+struct Vertices<'a> {
+    positions: &'a [[f32; 3]],
+    normals: Option<&'a [[f32; 3]]>,
+    uvs: Option<&'a [[f32; 2]]>,
+    colors: Option<&'a [[f32; 4]]>,
+    indices: Vec<u32>,
+}
+
+impl<'a> Vertices<'a> {
+    /// The vertices of the mesh, `None` if it has none (or no positions).
+    fn of(mesh: &'a Mesh) -> Option<Self> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+        if positions.is_empty() {
+            return None;
+        }
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => Some(normals.as_slice()),
+            _ => None,
+        };
+        let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => Some(uvs.as_slice()),
+            _ => None,
+        };
+        let colors = match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => Some(colors.as_slice()),
+            _ => None,
+        };
+        let indices = match mesh.indices() {
+            Some(indices) => indices.iter().map(|i| i as u32).collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        Some(Self {
+            positions,
+            normals,
+            uvs,
+            colors,
+            indices,
+        })
+    }
+}
+
+fn write_obj(path: &Path, meshes: &[ExportMesh], png: &[u8]) -> io::Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let png_path = path.with_extension("png");
+    let file_name = |path: &Path| path.file_name().unwrap().to_string_lossy().into_owned();
+    std::fs::write(&png_path, png)?;
+
+    let mut mtl = BufWriter::new(File::create(&mtl_path)?);
+    for kind in ExportMeshKind::ALL {
+        writeln!(mtl, "newmtl {}", kind.name())?;
+        writeln!(mtl, "Kd 1 1 1")?;
+        writeln!(mtl, "map_Kd {}", file_name(&png_path))?;
+        writeln!(mtl, "map_d {}", file_name(&png_path))?;
+    }
+    mtl.flush()?;
+
+    let mut obj = BufWriter::new(File::create(path)?);
+    writeln!(obj, "mtllib {}", file_name(&mtl_path))?;
+    write_obj_objects(&mut obj, meshes)?;
+    obj.flush()
+}
+
+/// Write the meshes as the objects of an `.obj` file, with the materials of their kinds.
+fn write_obj_objects(obj: &mut impl Write, meshes: &[ExportMesh]) -> io::Result<()> {
+    // The indices of the vertices are global (and start from 1).
+    let mut first_vertex = 1;
+    for ExportMesh { cords, kind, mesh } in meshes {
+        let Some(vertices) = Vertices::of(mesh) else {
+            continue;
+        };
+        writeln!(obj, "o chunk_{}_{}_{}", cords.x, cords.y, kind.name())?;
+        writeln!(obj, "usemtl {}", kind.name())?;
+        for (i, [x, y, z]) in vertices.positions.iter().enumerate() {
+            match vertices.colors {
+                Some(colors) => {
+                    let [r, g, b, _] = colors[i];
+                    writeln!(obj, "v {x} {y} {z} {r} {g} {b}")?;
+                }
+                None => writeln!(obj, "v {x} {y} {z}")?,
+            }
+        }
+        // The v axis of the textures of .obj files goes up.
+        for [u, v] in vertices.uvs.unwrap_or_default() {
+            writeln!(obj, "vt {u} {}", 1.0 - v)?;
+        }
+        for [x, y, z] in vertices.normals.unwrap_or_default() {
+            writeln!(obj, "vn {x} {y} {z}")?;
+        }
+        let corner = |i: u32| {
+            let i = i + first_vertex;
+            match (vertices.uvs.is_some(), vertices.normals.is_some()) {
+                (true, true) => format!("{i}/{i}/{i}"),
+                (true, false) => format!("{i}/{i}"),
+                (false, true) => format!("{i}//{i}"),
+                (false, false) => format!("{i}"),
+            }
+        };
+        for triangle in vertices.indices.chunks_exact(3) {
+            writeln!(
+                obj,
+                "f {} {} {}",
+                corner(triangle[0]),
+                corner(triangle[1]),
+                corner(triangle[2])
+            )?;
+        }
+        first_vertex += vertices.positions.len() as u32;
+    }
+    Ok(())
+}
+
+/// The binary buffer of a `.glb` file, with its buffer views and accessors.
+#[derive(Default)]
+struct GlbBuffer {
+    bin: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl GlbBuffer {
+    /// Append a buffer view, returns its index.
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        // Buffer views are aligned to 4 bytes.
+        self.bin.resize(self.bin.len().next_multiple_of(4), 0);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.bin.extend_from_slice(bytes);
+        self.views.push(view);
+        self.views.len() - 1
+    }
+
+    /// Append an accessor of float vectors (and its buffer view), returns its index.
+    fn float_accessor<const N: usize>(&mut self, values: &[[f32; N]], with_bounds: bool) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let view = self.view(&bytes, Some(GLTF_ARRAY_BUFFER));
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": GLTF_FLOAT,
+            "count": values.len(),
+            "type": format!("VEC{N}"),
+        });
+        // The accessors of the positions must have bounds.
+        if with_bounds {
+            let bound = |f: fn(f32, f32) -> f32, init: f32| {
+                (0..N)
+                    .map(|axis| values.iter().map(|v| v[axis]).fold(init, f))
+                    .collect::<Vec<f32>>()
+            };
+            accessor["min"] = json!(bound(f32::min, f32::INFINITY));
+            accessor["max"] = json!(bound(f32::max, f32::NEG_INFINITY));
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Append an accessor of indices (and its buffer view), returns its index.
+    fn index_accessor(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.view(&bytes, Some(GLTF_ELEMENT_ARRAY_BUFFER));
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": GLTF_UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+}
+
+fn write_glb(path: &Path, meshes: &[ExportMesh], png: &[u8]) -> io::Result<()> {
+    std::fs::write(path, glb_bytes(meshes, png)?)
+}
+
+/// The `.glb` file of the meshes, with the atlas (encoded as a `.png`).
+fn glb_bytes(meshes: &[ExportMesh], png: &[u8]) -> io::Result<Vec<u8>> {
+    let mut buffer = GlbBuffer::default();
+    // A node (and a mesh) for each chunk, with a primitive for each subchunk.
+    let mut nodes: Vec<Value> = vec![];
+    let mut gltf_meshes: Vec<Value> = vec![];
+    let mut last_cords = None;
+    for ExportMesh { cords, kind, mesh } in meshes {
+        let Some(vertices) = Vertices::of(mesh) else {
+            continue;
+        };
+        if last_cords != Some(*cords) {
+            last_cords = Some(*cords);
+            let name = format!("chunk_{}_{}", cords.x, cords.y);
+            nodes.push(json!({ "name": name, "mesh": gltf_meshes.len() }));
+            gltf_meshes.push(json!({ "name": name, "primitives": [] }));
+        }
+        let mut attributes = json!({
+            "POSITION": buffer.float_accessor(vertices.positions, true),
+        });
+        if let Some(normals) = vertices.normals {
+            attributes["NORMAL"] = json!(buffer.float_accessor(normals, false));
+        }
+        if let Some(uvs) = vertices.uvs {
+            attributes["TEXCOORD_0"] = json!(buffer.float_accessor(uvs, false));
+        }
+        if let Some(colors) = vertices.colors {
+            attributes["COLOR_0"] = json!(buffer.float_accessor(colors, false));
+        }
+        let primitive = json!({
+            "attributes": attributes,
+            "indices": buffer.index_accessor(&vertices.indices),
+            "material": *kind as usize,
+        });
+        gltf_meshes.last_mut().unwrap()["primitives"]
+            .as_array_mut()
+            .unwrap()
+            .push(primitive);
+    }
+    let atlas_view = buffer.view(png, None);
+
+    // The same as the materials of the subchunks, translucent blocks are blended.
+    let material = |kind: ExportMeshKind| {
+        let mut material = json!({
+            "name": kind.name(),
+            "pbrMetallicRoughness": {
+                "baseColorTexture": { "index": 0 },
+                "metallicFactor": 0.0,
+                "roughnessFactor": 0.85,
+            },
+            "alphaMode": "MASK",
+            "alphaCutoff": 0.3,
+            "doubleSided": kind == ExportMeshKind::XSprite,
+        });
+        match kind {
+            ExportMeshKind::XSprite => material["alphaCutoff"] = json!(0.1),
+            ExportMeshKind::Translucent => {
+                material["alphaMode"] = json!("BLEND");
+                material["pbrMetallicRoughness"]["roughnessFactor"] = json!(0.3);
+                material.as_object_mut().unwrap().remove("alphaCutoff");
+            }
+            _ => {}
+        }
+        material
+    };
+    let gltf = json!({
+        "asset": { "version": "2.0", "generator": "nova_craft" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<usize>>() }],
+        "nodes": nodes,
+        "meshes": gltf_meshes,
+        "materials": ExportMeshKind::ALL.map(material),
+        "textures": [{ "source": 0, "sampler": 0 }],
+        "images": [{ "bufferView": atlas_view, "mimeType": "image/png" }],
+        "samplers": [{
+            "magFilter": GLTF_NEAREST,
+            "minFilter": GLTF_NEAREST,
+            "wrapS": GLTF_CLAMP_TO_EDGE,
+            "wrapT": GLTF_CLAMP_TO_EDGE,
+        }],
+        "buffers": [{ "byteLength": buffer.bin.len() }],
+        "bufferViews": buffer.views,
+        "accessors": buffer.accessors,
+    });
+
+    // The chunks are padded to 4 bytes, the JSON with spaces and the binary buffer with zeros.
+    let mut json = serde_json::to_vec(&gltf)?;
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut bin = buffer.bin;
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let total_length = 12 + 8 + json.len() + 8 + bin.len();
+
+    let mut glb = Vec::with_capacity(total_length);
+    glb.extend_from_slice(GLB_MAGIC);
+    glb.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    glb.extend_from_slice(&(total_length as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(GLB_JSON_CHUNK);
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(GLB_BIN_CHUNK);
+    glb.extend_from_slice(&bin);
+    Ok(glb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+    /// A mesh of a single triangle.
+    fn triangle(offset: f32) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [offset, 0.0, 0.0],
+                [offset + 1.0, 0.0, 0.0],
+                [offset, 1.0, 0.0],
+            ],
+        );
+        mesh.set_indices(Some(Indices::U32(vec![0, 1, 2])));
+        mesh
+    }
+
+    fn export_mesh(x: i32, kind: ExportMeshKind) -> ExportMesh {
+        ExportMesh {
+            cords: ChunkCords::new(x, 0),
+            kind,
+            mesh: triangle(x as f32),
+        }
+    }
+
+    #[test]
+    fn chunk_region_is_inclusive_in_any_order() {
+        let region = ChunkRegion::new(ChunkCords::new(2, -1), ChunkCords::new(-1, 3));
+        assert_eq!(region.min, ChunkCords::new(-1, -1));
+        assert_eq!(region.max, ChunkCords::new(2, 3));
+        assert!(region.contains(ChunkCords::new(-1, -1)));
+        assert!(region.contains(ChunkCords::new(2, 3)));
+        assert!(region.contains(ChunkCords::new(0, 1)));
+        assert!(!region.contains(ChunkCords::new(3, 0)));
+        assert!(!region.contains(ChunkCords::new(0, -2)));
+    }
+
+    #[test]
+    fn obj_indices_are_global_and_start_from_one() {
+        let meshes = [
+            export_mesh(0, ExportMeshKind::Cube),
+            export_mesh(0, ExportMeshKind::Translucent),
+            export_mesh(1, ExportMeshKind::Custom),
+        ];
+        let mut obj = vec![];
+        write_obj_objects(&mut obj, &meshes).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        let faces: Vec<&str> = obj.lines().filter(|line| line.starts_with("f ")).collect();
+        assert_eq!(faces, ["f 1 2 3", "f 4 5 6", "f 7 8 9"]);
+        assert!(obj.contains("o chunk_0_0_translucent\nusemtl translucent\n"));
+        assert!(obj.contains("o chunk_1_0_custom\nusemtl custom\n"));
+    }
+
+    #[test]
+    fn glb_chunks_are_padded() {
+        let meshes = [
+            export_mesh(0, ExportMeshKind::Cube),
+            export_mesh(0, ExportMeshKind::Translucent),
+        ];
+        // An odd length, to check the binary chunk is padded.
+        let glb = glb_bytes(&meshes, &[1, 2, 3, 4, 5]).unwrap();
+        let u32_at = |i: usize| u32::from_le_bytes(glb[i..i + 4].try_into().unwrap()) as usize;
+        assert_eq!(&glb[0..4], GLB_MAGIC);
+        assert_eq!(u32_at(8), glb.len());
+        assert_eq!(glb.len() % 4, 0);
+
+        let json_length = u32_at(12);
+        assert_eq!(json_length % 4, 0);
+        assert_eq!(&glb[16..20], GLB_JSON_CHUNK);
+        let bin_start = 20 + json_length;
+        let bin_length = u32_at(bin_start);
+        assert_eq!(bin_length % 4, 0);
+        assert_eq!(&glb[bin_start + 4..bin_start + 8], GLB_BIN_CHUNK);
+        assert_eq!(bin_start + 8 + bin_length, glb.len());
+
+        // The JSON is padded with spaces, which is still valid JSON.
+        let gltf: Value = serde_json::from_slice(&glb[20..bin_start]).unwrap();
+        let primitives = gltf["meshes"][0]["primitives"].as_array().unwrap();
+        assert_eq!(primitives.len(), 2);
+        let material = &gltf["materials"][primitives[1]["material"].as_u64().unwrap() as usize];
+        assert_eq!(material["alphaMode"], "BLEND");
+        assert_eq!(gltf["materials"][0]["alphaMode"], "MASK");
+    }
+}
@@ -24,7 +24,8 @@ impl Inventory {
     }
 
     pub(super) fn with_pack_slot(mut self, slot_index: usize, slot: InventorySlot) -> Inventory {
-        self.pack[(slot_index + INVENTORY_SIZE - 1) & INVENTORY_SIZE] = slot;
+        let pack_size = self.pack.len();
+        self.pack[(slot_index + pack_size - 1) % pack_size] = slot;
         self
     }
 }
@@ -198,7 +199,19 @@ impl Plugin for InventoryPlugin {
                 .with_bar_slot(2, InventorySlot::Stack(Block::GRASS, 5))
                 .with_bar_slot(3, InventorySlot::Stack(Block::GREENERY, 5))
                 .with_bar_slot(4, InventorySlot::Stack(Block::SAND, 5))
-                .with_pack_slot(1, InventorySlot::Single(Block::DIRT)),
+                .with_bar_slot(5, InventorySlot::Stack(Block::STONESLAB, 10))
+                .with_bar_slot(6, InventorySlot::Stack(Block::STONESTAIRSBACK, 10))
+                .with_bar_slot(7, InventorySlot::Stack(Block::GLASS, 20))
+                .with_bar_slot(8, InventorySlot::Stack(Block::ICE, 20))
+                .with_bar_slot(9, InventorySlot::Stack(Block::MAGMA, 20))
+                .with_pack_slot(1, InventorySlot::Single(Block::DIRT))
+                .with_pack_slot(2, InventorySlot::Stack(Block::BOULDER, 10))
+                .with_pack_slot(3, InventorySlot::Stack(Block::STONETOPSLAB, 10))
+                .with_pack_slot(4, InventorySlot::Stack(Block::STONEPOST, 10))
+                .with_pack_slot(5, InventorySlot::Stack(Block::GLASSPANE, 10))
+                .with_pack_slot(6, InventorySlot::Stack(Block::STONESTAIRSRIGHT, 10))
+                .with_pack_slot(7, InventorySlot::Stack(Block::STONESTAIRSLEFT, 10))
+                .with_pack_slot(8, InventorySlot::Stack(Block::STONESTAIRSFORWARD, 10)),
         );
         app.add_systems(PreUpdate, inventory_input);
     }
@@ -0,0 +1,38 @@
+pub mod prelude {
+    pub use crate::direction::{Direction, Direction::*};
+    pub use crate::helper_ecs_utils::*;
+    pub use bevy::prelude::*;
+    pub use bevy::render::mesh::*;
+    pub use novacraft_meshing_backend::prelude::*;
+    // Notical direction
+    pub mod notical {
+        pub use novacraft_meshing_backend::prelude::direction::*;
+    }
+    pub use crate::AssetLoadingState;
+    pub use novacraft_utils::*;
+}
+
+pub mod action;
+pub mod blocks;
+pub mod chunk;
+pub mod console;
+pub mod env;
+pub mod export;
+pub mod helper_ecs_utils;
+pub mod inventory;
+pub mod mesh_utils {
+    pub use novacraft_meshing_backend::mesh_utils::*;
+}
+pub mod player;
+pub mod terrain;
+pub mod utils;
+pub mod visuals;
+
+use prelude::*;
+
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
+pub enum AssetLoadingState {
+    #[default]
+    Loading,
+    Loaded,
+}
@@ -1,42 +1,9 @@
-pub mod prelude {
-    pub use crate::direction::{Direction, Direction::*};
-    pub use crate::helper_ecs_utils::*;
-    pub use bevy::prelude::*;
-    pub use bevy::render::mesh::*;
-    pub use novacraft_meshing_backend::prelude::*;
-    // Notical direction
-    pub mod notical {
-        pub use novacraft_meshing_backend::prelude::direction::*;
-    }
-    pub use crate::AssetLoadingState;
-    pub use novacraft_utils::*;
-}
-
-pub mod action;
-pub mod blocks;
-pub mod chunk;
-pub mod console;
-pub mod env;
-pub mod helper_ecs_utils;
-pub mod inventory;
-pub mod mesh_utils {
-    pub use novacraft_meshing_backend::mesh_utils::*;
-}
-pub mod player;
-pub mod terrain;
-pub mod utils;
-pub mod visuals;
-
 use bevy::{pbr::wireframe::WireframePlugin, window::WindowResolution};
 use bevy_xpbd_3d::prelude::*;
-use prelude::*;
-
-#[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
-pub enum AssetLoadingState {
-    #[default]
-    Loading,
-    Loaded,
-}
+use nova_craft::prelude::*;
+use nova_craft::{
+    action, blocks, chunk, console, env, helper_ecs_utils, inventory, player, terrain, visuals,
+};
 
 fn main() {
     let mut app = App::new();
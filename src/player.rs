@@ -5,8 +5,10 @@ use bevy_atmosphere::prelude::*;
 use misc_systems::*;
 use std::f32::consts::PI;
 
+use crate::blocks::{meshreg::MeshRegistry, ShapeRegistry};
 use crate::chunk::{
-    ChunkCords, ComputeChunk, CurrentChunk, CHUNK_DIMS, HEIGHT, LENGTH, RENDER_DISTANCE, WIDTH,
+    ChunkCords, ComputeChunk, CubeChild, CurrentChunk, CustomChild, PartialChild, TranslucentChild,
+    VoxelWorld, XSpriteChild, CHUNK_DIMS, HEIGHT, LENGTH, RENDER_DISTANCE, WIDTH,
 };
 use crate::{prelude::*, AssetLoadingState};
 use bevy::ecs::event::ManualEventReader;
@@ -59,11 +61,14 @@ pub const SSAO_QUALITY_LEVEL: ScreenSpaceAmbientOcclusionQualityLevel =
     ScreenSpaceAmbientOcclusionQualityLevel::High;
 /// Default fog color
 pub const FOG_COLOR: Color = Color::rgb(0.65, 0.95, 1.0);
-/// Default fog falloff
-pub const FOG_FALLOFF: FogFalloff = FogFalloff::Linear {
-    start: ((RENDER_DISTANCE - 2) * WIDTH as i32) as f32,
-    end: ((RENDER_DISTANCE + 1) * WIDTH as i32) as f32,
-};
+/// The fog falloff for the render distance (in chunks), so the edge of the world is always
+/// hidden. The distances are scaled by `factor` (the weather brings the fog closer).
+pub fn fog_falloff(render_distance: i32, factor: f32) -> FogFalloff {
+    FogFalloff::Linear {
+        start: ((render_distance - 2).max(0) * WIDTH as i32) as f32 * factor,
+        end: ((render_distance + 1) * WIDTH as i32) as f32 * factor,
+    }
+}
 /// Starting position of the player
 pub const STARTING_POS: [f32; 3] = [0.0, HEIGHT as f32 + 5.0, 0.0];
 /// Starting chunk of the player
@@ -81,8 +86,6 @@ pub const CAMERA_STARTING_POS: [f32; 3] = [0.0, 0.0 + CAMERA_HEIGHT_OFFSET, 0.0]
 /// The "reach" of the player, what is the largest distance from the player that a block can be and
 /// the player can break it / interact with it.
 pub const MAX_INTERACTION_DISTANCE: f32 = 6.0;
-/// Epsilon to offset a point by a little bit
-pub const SMALL_TRAVERSE: f32 = 0.001;
 /// Default Field of view
 pub const FOV: f32 = PI / 3.0;
 /// FOV while croching
@@ -167,7 +170,7 @@ pub enum PlayerGameMode {
 }
 
 /// This resource represents the block that the player is currently looking at ("targeting")
-/// It's updated every frame using a [`RayCaster`]
+/// It's updated every frame by [`update_target_block`]
 #[derive(Resource)]
 pub struct TargetBlock {
     pub ignore_flag: bool,
@@ -176,6 +179,8 @@ pub struct TargetBlock {
     pub block_pos: BlockPos,
     pub face_hit: Option<Face>,
     pub ray_direction: Vec3,
+    /// The selection shape of the targeted block, relative to its center.
+    pub selection_shape: VoxelBox,
 }
 
 /// Keeps track of mouse motion events, pitch, and yaw
@@ -267,6 +272,7 @@ impl Default for TargetBlock {
             block_pos: [0, 0, 0].into(),
             face_hit: None,
             ray_direction: Vec3::ONE,
+            selection_shape: VoxelBox::FULL,
         }
     }
 }
@@ -339,7 +345,7 @@ impl Default for PlayerCameraBundle {
 
             fog: FogSettings {
                 color: FOG_COLOR,
-                falloff: FOG_FALLOFF,
+                falloff: fog_falloff(RENDER_DISTANCE, 1.0),
                 ..Default::default()
             },
         }
@@ -359,64 +365,69 @@ fn setup_player(mut commands: Commands) {
         .push_children(&[camera_entity]);
 }
 
-/// Updatees the target block, the block that the player is currently looking at
+/// Updatees the target block, the block that the player is currently looking at. The blocks along
+/// the player's line of sight are walked through one by one (see [`VoxelWorld::raycast`]), so the
+/// block, and the face it was hit on, are exact, and chunks don't need colliders to be targeted.
 fn update_target_block(
     mut target_block: ResMut<TargetBlock>,
     camera_rotation_transform: Query<&Transform, With<PlayerCamera>>,
     camera_position_transform: Query<&Transform, With<PhysicalPlayer>>,
-    spatial_query: SpatialQuery,
+    voxel_world: VoxelWorld,
+    subchunks: Query<(
+        &CubeChild,
+        &XSpriteChild,
+        &PartialChild,
+        &TranslucentChild,
+        &CustomChild,
+    )>,
+    mreg: Res<MeshRegistry>,
 ) {
-    // let tran = camera_query.get_single().unwrap();
-    if let (Ok(rot), Ok(pos)) = (
+    let (Ok(rot), Ok(pos)) = (
         camera_rotation_transform.get_single(),
         camera_position_transform.get_single(),
-    ) {
-        let forward = rot.forward();
-        let pos = pos.translation + rot.translation;
-        if let Some(ray_hit) = spatial_query.cast_ray(
-            pos,
-            forward,
-            MAX_INTERACTION_DISTANCE,
-            false,
-            SpatialQueryFilter::new()
-                .with_masks([RigidLayer::Ground, RigidLayer::GroundNonCollidable]),
-        ) {
-            let face = {
-                let mut to_return = None;
-                if ray_hit.normal == Vec3::Y {
-                    to_return = Some(Face::Top);
-                }
-                if ray_hit.normal == Vec3::NEG_Y {
-                    to_return = Some(Face::Bottom);
-                }
-                if ray_hit.normal == Vec3::X {
-                    to_return = Some(Face::Right);
-                }
-                if ray_hit.normal == Vec3::NEG_X {
-                    to_return = Some(Face::Left);
-                }
-                if ray_hit.normal == Vec3::Z {
-                    to_return = Some(Face::Back);
-                }
-                if ray_hit.normal == Vec3::NEG_Z {
-                    to_return = Some(Face::Forward);
-                }
-                to_return
-            };
-            let impact_point = pos + ray_hit.time_of_impact * forward + SMALL_TRAVERSE * forward;
-            let global_pos = point_to_global_block_pos(impact_point, CHUNK_DIMS);
-            *target_block = TargetBlock {
-                ignore_flag: false,
-                target_entity: ray_hit.entity,
-                chunk_cords: global_pos.chunk_cords,
-                block_pos: global_pos.pos,
-                face_hit: face,
-                ray_direction: forward,
-            };
-        } else {
-            target_block.ignore_flag = true;
-        }
-    }
+    ) else {
+        return;
+    };
+    let forward = rot.forward();
+    let pos = pos.translation + rot.translation;
+    let Some(hit) = voxel_world.raycast(pos, forward, MAX_INTERACTION_DISTANCE) else {
+        target_block.ignore_flag = true;
+        return;
+    };
+    let global_pos = VoxelWorld::to_global(hit.pos);
+    // The subchunk that has the block in its mesh.
+    let Some((
+        CubeChild(cube_child),
+        XSpriteChild(xsprite_child),
+        PartialChild(partial_child),
+        TranslucentChild(translucent_child),
+        CustomChild(custom_child),
+    )) = voxel_world
+        .chunk_entity(global_pos.chunk_cords)
+        .and_then(|entity| subchunks.get(entity).ok())
+    else {
+        target_block.ignore_flag = true;
+        return;
+    };
+    let target_entity = match mreg.get_mesh(&hit.block) {
+        VoxelMesh::XSprite(_) => *xsprite_child,
+        VoxelMesh::Partial(_) => *partial_child,
+        VoxelMesh::Translucent(_) => *translucent_child,
+        VoxelMesh::CustomMesh(_) => *custom_child,
+        _ => *cube_child,
+    };
+    *target_block = TargetBlock {
+        ignore_flag: false,
+        target_entity,
+        chunk_cords: global_pos.chunk_cords,
+        block_pos: global_pos.pos,
+        face_hit: hit.face,
+        ray_direction: forward,
+        selection_shape: ShapeRegistry::get()
+            .get_shape(&hit.block)
+            .selection
+            .unwrap_or(VoxelBox::FULL),
+    };
 }
 
 pub struct PlayerPlugin;
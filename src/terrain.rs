@@ -1,14 +1,26 @@
 use crate::blocks::Block;
 use crate::chunk::{ChunkCords, CHUNK_DIMS, CHUNK_TOTAL_BLOCKS_USIZE, HEIGHT, LENGTH, WIDTH};
-use noise::NoiseFn;
+use noise::{NoiseFn, Perlin};
+use novacraft_meshing_backend::prelude::TintMap;
 use novacraft_utils::pos_to_index;
 use rand::prelude::*;
 pub const NOISE_SEED: usize = 10;
 pub const NOISE_SEED_SQRD: usize = NOISE_SEED * NOISE_SEED;
 pub const NOISE_FACTOR_CONT: f64 = 0.014;
 pub const NOISE_FACTOR_SCALE: f64 = 1.7;
+/// How fast the climate (temperature and humidity) changes across the world.
+pub const CLIMATE_NOISE_FACTOR: f64 = 0.0035;
+const TEMPERATURE_NOISE_SEED: u32 = 4111;
+const HUMIDITY_NOISE_SEED: u32 = 4703;
 
-use crate::prelude::{Plugin, Resource};
+/// The tint of grass and foliage in each corner of the climate, in sRGB:
+/// `[[cold & dry, cold & humid], [hot & dry, hot & humid]]`.
+const CLIMATE_TINTS: [[[f32; 3]; 2]; 2] = [
+    [[0.50, 0.70, 0.42], [0.32, 0.72, 0.38]],
+    [[0.76, 0.80, 0.28], [0.24, 0.86, 0.12]],
+];
+
+use crate::prelude::{Color, Plugin, Resource};
 #[derive(Resource)]
 pub struct TerrainConfig {
     pub noise_seed: usize,
@@ -64,14 +76,19 @@ pub fn generate_flat_chunk(sea_level: u32) -> [Block; CHUNK_TOTAL_BLOCKS_USIZE]
     chunk
 }
 
-// Generate chunk from noise
+// Generate chunk from noise, `seed` is the seed of the noise
 pub fn generate_chunk(
     cords: ChunkCords,
+    seed: u32,
     noise: &impl NoiseFn<f64, 2>,
     noise_factor_cont: f64,
     noise_factor_scale: f64,
 ) -> [Block; CHUNK_TOTAL_BLOCKS_USIZE] {
-    let mut rng = rand::thread_rng();
+    // Seeded from the cords and the seed, so a chunk is the same every time it's generated, but
+    // not in every world.
+    let cords_seed = ((cords[0] as u32 as u64) << 32) | cords[1] as u32 as u64;
+    let mut rng =
+        StdRng::seed_from_u64(cords_seed ^ (seed as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
     let mut height_map: [u32; (WIDTH * LENGTH) as usize] = [0; (WIDTH * LENGTH) as usize];
     let mut chunk = [Block::AIR; CHUNK_TOTAL_BLOCKS_USIZE];
     // First, generate a height map
@@ -114,3 +131,28 @@ pub fn generate_chunk(
     }
     chunk
 }
+
+/// The tint of grass and foliage in a chunk, from the climate noise. The tint is sampled at the
+/// corners of the columns (in world space), so neighboring chunks agree on the tint of their
+/// border, and it blends smoothly from one chunk to the next. `scale` is the amount of blocks in
+/// each voxel of the grid the tint map is for (1, unless the chunk is downsampled, see
+/// [`LodLevel`](crate::chunk::LodLevel)).
+pub fn generate_tint_map(cords: ChunkCords, scale: u32) -> TintMap {
+    let temperature = Perlin::new(TEMPERATURE_NOISE_SEED);
+    let humidity = Perlin::new(HUMIDITY_NOISE_SEED);
+    TintMap::new(CHUNK_DIMS / scale, |x, z| {
+        let point = [
+            ((x * scale) as i32 + cords[0] * WIDTH as i32) as f64 * CLIMATE_NOISE_FACTOR,
+            ((z * scale) as i32 + cords[1] * LENGTH as i32) as f64 * CLIMATE_NOISE_FACTOR,
+        ];
+        // From the noise (around -1.0 - 1.0) to 0.0 - 1.0
+        let t = (temperature.get(point) * 0.7 + 0.5).clamp(0.0, 1.0) as f32;
+        let h = (humidity.get(point) * 0.7 + 0.5).clamp(0.0, 1.0) as f32;
+        let lerp = |a: [f32; 3], b: [f32; 3], f: f32| [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * f);
+        let cold = lerp(CLIMATE_TINTS[0][0], CLIMATE_TINTS[0][1], h);
+        let hot = lerp(CLIMATE_TINTS[1][0], CLIMATE_TINTS[1][1], h);
+        let [r, g, b] = lerp(cold, hot, t);
+        let [r, g, b, _] = Color::rgb(r, g, b).as_linear_rgba_f32();
+        [r, g, b]
+    })
+}
@@ -1,4 +1,5 @@
 mod block_highlight;
+mod chunk_borders;
 mod crosshair;
 mod debug_mode;
 
@@ -7,6 +8,7 @@ pub(super) use crate::prelude::*;
 #[allow(unused_imports)]
 use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use block_highlight::*;
+use chunk_borders::*;
 use crosshair::*;
 
 use self::debug_mode::*;
@@ -42,6 +44,7 @@ impl Plugin for VisualsPlugin {
         // DEBUG MODE
         //
         app.init_resource::<DebugModeSettings>();
+        app.init_resource::<FrameTimeHistory>();
         app.add_state::<DebugMode>();
         app.add_systems(Startup, setup_debug_mode);
         app.add_systems(OnEnter::<DebugMode>(DebugMode::On), show_debug_mode);
@@ -50,9 +53,16 @@ impl Plugin for VisualsPlugin {
             PreUpdate,
             (
                 toggle_debug_mode,
-                update_debug_ui.run_if(in_state::<DebugMode>(DebugMode::On)),
+                record_frame_time,
+                (debug_mode_hotkeys, update_debug_ui, update_frame_time_graph)
+                    .run_if(in_state(DebugMode::On)),
             ),
         );
+        app.add_systems(
+            PostUpdate,
+            draw_chunk_borders
+                .run_if(in_state(DebugMode::On).and_then(render_chunk_borders_enabled)),
+        );
     }
 }
 
@@ -13,21 +13,20 @@ pub(super) fn highlight_target_block(target_block: Res<TargetBlock>, mut gizmos:
     if target_block.ignore_flag == true {
         return;
     }
-    // convert to Vec3
-    let tmp = target_block.block_pos.as_vec3();
-    let block_pos = tmp
-        + Vec3::new(
-            target_block.chunk_cords[0] as f32 * WIDTH as f32,
-            0.0,
-            target_block.chunk_cords[1] as f32 * LENGTH as f32,
-        );
+    let block_pos = VoxelWorld::to_world(BlockGlobalPos::new(
+        target_block.block_pos,
+        target_block.chunk_cords,
+    ))
+    .as_vec3();
+    // Highlight the block's selection shape (which isn't always the whole block).
+    let VoxelBox { min, max } = target_block.selection_shape;
 
     // The offset is meant to move the the gizmos cuboid slightly towards the player, because there may be
     // cases where the ground is covering a small part of the cuboid. This way its bold and clear.
     let offset = target_block.ray_direction.normalize_or_zero() * (-HIGHLIGHT_SCALE / 100.0);
     gizmos.cuboid(
-        Transform::from_translation(block_pos + offset)
-            .with_scale(Vec3::from(crate::blocks::VOXEL_DIMS) * HIGHLIGHT_SCALE),
+        Transform::from_translation(block_pos + (min + max) / 2.0 + offset)
+            .with_scale((max - min) * HIGHLIGHT_SCALE),
         Color::BLACK,
     );
 }
@@ -0,0 +1,123 @@
+use super::*;
+use crate::chunk::{
+    CloseChunk, Cords, CubeChild, CurrentChunk, CustomChild, ParentChunk, PartialChild, Subchunk,
+    ToApplySL, ToConnect, ToIntroduce, ToUpdate, TranslucentChild, XSpriteChild, CHUNK_DIMS,
+    HEIGHT, LENGTH, WIDTH,
+};
+use crate::utils::chunk_distance;
+use bevy::render::primitives::Aabb;
+use bevy_xpbd_3d::prelude::Collider;
+
+/// Chunk borders are drawn for chunks up to this distance (in chunks) from the player.
+pub const CHUNK_BORDERS_DISTANCE: i32 = 2;
+
+// The colors of the chunk borders, each color represents the state of the chunk in the chunk
+// pipeline. When a chunk is in more than one state, the first one (in this order) is displayed.
+/// The chunk hasn't been connected to its adjacent chunks yet ([`ToConnect`]).
+const TO_CONNECT_COLOR: Color = Color::WHITE;
+/// The chunk hasn't been introduced to some of its adjacent chunks yet ([`ToIntroduce`]).
+const TO_INTRODUCE_COLOR: Color = Color::ORANGE;
+/// One of the subchunks is waiting to update its mesh ([`ToUpdate`]).
+const TO_UPDATE_COLOR: Color = Color::RED;
+/// The chunk is waiting for its smooth lighting to be applied ([`ToApplySL`]).
+const TO_APPLY_SL_COLOR: Color = Color::FUCHSIA;
+/// The chunk is close to the player, and its collider is ready ([`CloseChunk`]).
+const CLOSE_WITH_COLLIDER_COLOR: Color = Color::GREEN;
+/// The chunk is close to the player, but its collider is still being computed.
+const CLOSE_WITHOUT_COLLIDER_COLOR: Color = Color::YELLOW;
+/// The chunk is idle.
+const IDLE_COLOR: Color = Color::GRAY;
+/// The color of the bounds of the subchunks of the chunk the player is in.
+const SUBCHUNK_COLOR: Color = Color::CYAN;
+
+/// The center of a chunk (in world space).
+pub(super) fn chunk_center(cords: ChunkCords) -> Vec3 {
+    // Blocks are centered around their position, so the chunk starts half a block before it.
+    Vec3::new(
+        cords[0] as f32 * WIDTH as f32 + WIDTH as f32 / 2.0 - 0.5,
+        HEIGHT as f32 / 2.0 - 0.5,
+        cords[1] as f32 * LENGTH as f32 + LENGTH as f32 / 2.0 - 0.5,
+    )
+}
+
+/// Draw the borders of the chunks around the player, color-coded by their state in the chunk
+/// pipeline. The bounds of the subchunks of the chunk the player is in are drawn as well.
+pub(super) fn draw_chunk_borders(
+    current_chunk: Res<CurrentChunk>,
+    chunks: Query<
+        (
+            &Cords,
+            &CubeChild,
+            &XSpriteChild,
+            &PartialChild,
+            &TranslucentChild,
+            &CustomChild,
+            Has<ToConnect>,
+            Has<ToIntroduce>,
+            Has<ToApplySL>,
+            Has<CloseChunk>,
+            Has<Collider>,
+        ),
+        With<ParentChunk>,
+    >,
+    subchunks: Query<(Has<ToUpdate>, &Aabb, &GlobalTransform), With<Subchunk>>,
+    mut gizmos: Gizmos,
+) {
+    for (
+        Cords(cords),
+        CubeChild(cube_child),
+        XSpriteChild(xsprite_child),
+        PartialChild(partial_child),
+        TranslucentChild(translucent_child),
+        CustomChild(custom_child),
+        to_connect,
+        to_introduce,
+        to_apply_sl,
+        close,
+        has_collider,
+    ) in chunks.iter()
+    {
+        if chunk_distance(*cords, current_chunk.0) > CHUNK_BORDERS_DISTANCE {
+            continue;
+        }
+        let children = [
+            *cube_child,
+            *xsprite_child,
+            *partial_child,
+            *translucent_child,
+            *custom_child,
+        ]
+        .map(|child| subchunks.get(child).ok());
+        let to_update = children.iter().flatten().any(|(to_update, ..)| *to_update);
+
+        let color = if to_connect {
+            TO_CONNECT_COLOR
+        } else if to_introduce {
+            TO_INTRODUCE_COLOR
+        } else if to_update {
+            TO_UPDATE_COLOR
+        } else if to_apply_sl {
+            TO_APPLY_SL_COLOR
+        } else if close && has_collider {
+            CLOSE_WITH_COLLIDER_COLOR
+        } else if close {
+            CLOSE_WITHOUT_COLLIDER_COLOR
+        } else {
+            IDLE_COLOR
+        };
+        gizmos.cuboid(
+            Transform::from_translation(chunk_center(*cords)).with_scale(CHUNK_DIMS.as_vec3()),
+            color,
+        );
+
+        if *cords == current_chunk.0 {
+            for (_, aabb, transform) in children.into_iter().flatten() {
+                gizmos.cuboid(
+                    Transform::from_translation(transform.transform_point(aabb.center.into()))
+                        .with_scale(Vec3::from(aabb.half_extents) * 2.0),
+                    SUBCHUNK_COLOR,
+                );
+            }
+        }
+    }
+}
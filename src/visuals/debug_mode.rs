@@ -1,10 +1,16 @@
 // REFACTORED
 
+use std::collections::VecDeque;
+
 use bevy::pbr::wireframe::WireframeConfig;
 
 use crate::action::PhysicalPlayer;
 use crate::blocks::Block;
-use crate::chunk::{Grid, ParentChunk, Subchunk, LENGTH, WIDTH};
+use crate::chunk::{
+    ChunkQueue, ComputeChunk, Grid, IntroduceScheduler, ParentChunk, Subchunk, VoxelWorld,
+    CHUNK_DIMS,
+};
+use crate::player::PlayerCamera;
 
 use super::*;
 
@@ -24,6 +30,22 @@ pub const TEXT_BACKGROUND_COLOR: Color = Color::Rgba {
     alpha: 0.5,
 };
 
+/// While in debug mode, this key toggles the wireframe of all meshes.
+pub const TOGGLE_WIREFRAME_KEY: KeyCode = KeyCode::F1;
+/// While in debug mode, this key toggles the AABBs of all entities.
+pub const TOGGLE_AABB_KEY: KeyCode = KeyCode::F2;
+/// While in debug mode, this key toggles the chunk borders.
+pub const TOGGLE_CHUNK_BORDERS_KEY: KeyCode = KeyCode::F4;
+
+/// The amount of frames the frame-time graph displays.
+pub const FRAME_TIME_GRAPH_LENGTH: usize = 120;
+/// The width (in pixels) of each bar in the frame-time graph.
+pub const FRAME_TIME_BAR_WIDTH: f32 = 3.0;
+/// The height (in pixels) of the frame-time graph. A frame that took
+/// [`FRAME_TIME_GRAPH_MAX_MS`] or more will fill it.
+pub const FRAME_TIME_GRAPH_HEIGHT: f32 = 100.0;
+pub const FRAME_TIME_GRAPH_MAX_MS: f32 = 50.0;
+
 impl DebugMode {
     fn opposite(&self) -> Self {
         match self {
@@ -38,6 +60,7 @@ pub(super) struct DebugModeSettings {
     size: f32,
     render_wireframe: bool,
     render_aabb: bool,
+    render_chunk_borders: bool,
 }
 
 #[derive(Component)]
@@ -46,10 +69,47 @@ pub(super) struct DebugModeText(DebugText);
 #[derive(Component)]
 pub(super) struct DebugUI;
 
+/// Marker for the bars of the frame-time graph, the value is the index of the bar (0 is the
+/// oldest frame).
+#[derive(Component)]
+pub(super) struct FrameTimeBar(usize);
+
+/// The time (in milliseconds) of the last [`FRAME_TIME_GRAPH_LENGTH`] frames.
+#[derive(Resource, Default)]
+pub(super) struct FrameTimeHistory(VecDeque<f32>);
+
 pub(super) enum DebugText {
     PlayerPosition,
+    ChunkCords,
+    LocalBlockPos,
+    Facing,
     TargetBlockType,
     TargetBlockPosition,
+    LoadedChunks,
+    ChunkQueueLength,
+    ComputeChunkTasks,
+    Introduce,
+    Geometry,
+    Toggles,
+}
+
+impl DebugText {
+    fn label(&self) -> &'static str {
+        match self {
+            DebugText::PlayerPosition => "Player Position: ",
+            DebugText::ChunkCords => "Chunk: ",
+            DebugText::LocalBlockPos => "Block In Chunk: ",
+            DebugText::Facing => "Facing: ",
+            DebugText::TargetBlockType => "Target Block Type: ",
+            DebugText::TargetBlockPosition => "Target Block Position: ",
+            DebugText::LoadedChunks => "Loaded Chunks: ",
+            DebugText::ChunkQueueLength => "Chunk Queue: ",
+            DebugText::ComputeChunkTasks => "Chunk Tasks: ",
+            DebugText::Introduce => "Introduce (pending / ready / longest wait): ",
+            DebugText::Geometry => "Vertices / Triangles: ",
+            DebugText::Toggles => "",
+        }
+    }
 }
 
 impl Default for DebugModeSettings {
@@ -58,6 +118,7 @@ impl Default for DebugModeSettings {
             size: 35.0,
             render_aabb: false,
             render_wireframe: false,
+            render_chunk_borders: false,
         }
     }
 }
@@ -69,75 +130,77 @@ pub(super) fn setup_debug_mode(
 ) {
     let font_handle = asset_server.load("fonts/main_font.ttf");
 
-    // debug player position
-    let player_pos = commands
-        .spawn((
-            DebugModeText(DebugText::PlayerPosition),
-            TextBundle::from_sections([
-                TextSection::new(
-                    "Player Position: ",
-                    TextStyle {
-                        font_size: dm_settings.size,
-                        font: font_handle.clone(),
-                        ..Default::default()
-                    },
-                ),
-                TextSection::from_style(TextStyle {
-                    font_size: dm_settings.size,
-                    font: font_handle.clone(),
-                    color: Color::GOLD,
-                    ..Default::default()
-                }),
-            ])
-            .with_background_color(TEXT_BACKGROUND_COLOR),
-        ))
-        .id();
-    // debug target block position
-    let target_block_pos = commands
-        .spawn((
-            DebugModeText(DebugText::TargetBlockPosition),
-            TextBundle::from_sections([
-                TextSection::new(
-                    "Target Block Position: ",
-                    TextStyle {
+    let mut children = vec![];
+    for debug_text in [
+        DebugText::PlayerPosition,
+        DebugText::ChunkCords,
+        DebugText::LocalBlockPos,
+        DebugText::Facing,
+        DebugText::TargetBlockPosition,
+        DebugText::TargetBlockType,
+        DebugText::LoadedChunks,
+        DebugText::ChunkQueueLength,
+        DebugText::ComputeChunkTasks,
+        DebugText::Introduce,
+        DebugText::Geometry,
+        DebugText::Toggles,
+    ] {
+        let text = commands
+            .spawn((
+                TextBundle::from_sections([
+                    TextSection::new(
+                        debug_text.label(),
+                        TextStyle {
+                            font_size: dm_settings.size,
+                            font: font_handle.clone(),
+                            ..Default::default()
+                        },
+                    ),
+                    TextSection::from_style(TextStyle {
                         font_size: dm_settings.size,
                         font: font_handle.clone(),
+                        color: Color::GOLD,
                         ..Default::default()
-                    },
-                ),
-                TextSection::from_style(TextStyle {
-                    font_size: dm_settings.size,
-                    font: font_handle.clone(),
-                    color: Color::GOLD,
-                    ..Default::default()
-                }),
-            ])
-            .with_background_color(TEXT_BACKGROUND_COLOR),
-        ))
-        .id();
-    // debug target block type
-    let target_block_type = commands
-        .spawn((
-            DebugModeText(DebugText::TargetBlockType),
-            TextBundle::from_sections([
-                TextSection::new(
-                    "Target Block Type: ",
-                    TextStyle {
-                        font_size: dm_settings.size,
-                        font: font_handle.clone(),
+                    }),
+                ])
+                .with_background_color(TEXT_BACKGROUND_COLOR),
+                DebugModeText(debug_text),
+            ))
+            .id();
+        children.push(text);
+    }
+
+    // The frame-time graph, each bar is one frame
+    let graph = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::FlexEnd,
+                width: Val::Px(FRAME_TIME_BAR_WIDTH * FRAME_TIME_GRAPH_LENGTH as f32),
+                height: Val::Px(FRAME_TIME_GRAPH_HEIGHT),
+                ..Default::default()
+            },
+            background_color: TEXT_BACKGROUND_COLOR.into(),
+            ..Default::default()
+        })
+        .with_children(|graph| {
+            for i in 0..FRAME_TIME_GRAPH_LENGTH {
+                graph.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(FRAME_TIME_BAR_WIDTH),
+                            height: Val::Px(0.0),
+                            ..Default::default()
+                        },
                         ..Default::default()
                     },
-                ),
-                TextSection::from_style(TextStyle {
-                    font_size: dm_settings.size,
-                    font: font_handle,
-                    color: Color::GOLD,
-                    ..Default::default()
-                }),
-            ])
-            .with_background_color(TEXT_BACKGROUND_COLOR),
-        ))
+                    FrameTimeBar(i),
+                ));
+            }
+        })
         .id();
+    children.push(graph);
+
     // Have these ^ entities children of parent `DebugUI`
     commands
         .spawn((
@@ -151,57 +214,172 @@ pub(super) fn setup_debug_mode(
                 ..Default::default()
             },
         ))
-        .push_children(&[player_pos, target_block_pos, target_block_type]);
+        .push_children(&children);
+}
+
+/// The direction the camera is facing, as the name of the closest horizontal direction.
+fn facing_direction(forward: Vec3) -> &'static str {
+    if forward.x.abs() > forward.z.abs() {
+        if forward.x > 0.0 {
+            "East (+X)"
+        } else {
+            "West (-X)"
+        }
+    } else if forward.z > 0.0 {
+        "North (+Z)"
+    } else {
+        "South (-Z)"
+    }
 }
 
 pub(super) fn update_debug_ui(
     mut debug_ui_text: Query<(&DebugModeText, &mut Text)>,
     player_query: Query<&Transform, With<PhysicalPlayer>>,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
     target_block: Res<TargetBlock>,
     grids_query: Query<&Grid, With<ParentChunk>>,
-    parents_query: Query<&Parent, With<Subchunk>>,
+    voxel_world: VoxelWorld,
+    subchunks_query: Query<&Handle<Mesh>, With<Subchunk>>,
+    tasks_query: Query<(), With<ComputeChunk>>,
+    chunk_queue: Res<ChunkQueue>,
+    introduce_scheduler: Res<IntroduceScheduler>,
+    dm_settings: Res<DebugModeSettings>,
+    meshes: Res<Assets<Mesh>>,
 ) {
+    let player_pos = player_query.get_single().map(|t| t.translation).ok();
     for (DebugModeText(debug_text_type), mut text) in debug_ui_text.iter_mut() {
         match debug_text_type {
             DebugText::PlayerPosition => {
-                if let Ok(player_transform) = player_query.get_single() {
-                    text.sections[1].value = format!("{}", player_transform.translation.as_ivec3());
+                if let Some(player_pos) = player_pos {
+                    text.sections[1].value = format!("{}", player_pos.as_ivec3());
+                }
+            }
+            DebugText::ChunkCords => {
+                if let Some(player_pos) = player_pos {
+                    let chunk_cords = point_to_chunk_cords(player_pos, CHUNK_DIMS);
+                    text.sections[1].value = format!("{}", chunk_cords);
+                }
+            }
+            DebugText::LocalBlockPos => {
+                if let Some(player_pos) = player_pos {
+                    let global_pos = point_to_global_block_pos(player_pos, CHUNK_DIMS);
+                    text.sections[1].value = format!("{}", global_pos.pos);
+                }
+            }
+            DebugText::Facing => {
+                if let Ok(camera_transform) = camera_query.get_single() {
+                    let forward = camera_transform.forward();
+                    text.sections[1].value = format!(
+                        "{} [{:.2}, {:.2}, {:.2}]",
+                        facing_direction(forward),
+                        forward.x,
+                        forward.y,
+                        forward.z
+                    );
                 }
             }
             DebugText::TargetBlockType => {
                 if target_block.ignore_flag {
                     text.sections[1].value = format!("{:?}", Block::AIR);
-                } else if let Ok(parent) = parents_query.get(target_block.target_entity) {
-                    if let Ok(Grid(grid)) = grids_query.get(parent.get()) {
-                        text.sections[1].value = format!(
-                            "{:?}",
-                            grid.read()
-                                .unwrap()
-                                .get_block_or(target_block.block_pos, Block::AIR)
-                        );
-                    }
+                } else {
+                    let target_pos = VoxelWorld::to_world(BlockGlobalPos::new(
+                        target_block.block_pos,
+                        target_block.chunk_cords,
+                    ));
+                    text.sections[1].value =
+                        format!("{:?}", voxel_world.get_block_or(target_pos, Block::AIR));
                 }
             }
             DebugText::TargetBlockPosition => {
                 if target_block.ignore_flag {
                     text.sections[1].value = "NaN".into();
                 } else {
-                    let tmp = target_block.block_pos.as_vec3();
-                    // offset the chunk dims
-                    let block_pos = tmp
-                        + Vec3::new(
-                            target_block.chunk_cords[0] as f32 * WIDTH as f32,
-                            0.0,
-                            target_block.chunk_cords[1] as f32 * LENGTH as f32,
-                        );
-
-                    text.sections[1].value = format!("{}", block_pos.as_ivec3());
+                    let block_pos = VoxelWorld::to_world(BlockGlobalPos::new(
+                        target_block.block_pos,
+                        target_block.chunk_cords,
+                    ));
+                    text.sections[1].value = format!("{}", block_pos);
                 }
             }
+            DebugText::LoadedChunks => {
+                text.sections[1].value = format!("{}", grids_query.iter().len());
+            }
+            DebugText::ChunkQueueLength => {
+                text.sections[1].value = format!("{}", chunk_queue.len());
+            }
+            DebugText::ComputeChunkTasks => {
+                text.sections[1].value = format!("{}", tasks_query.iter().len());
+            }
+            DebugText::Introduce => {
+                let metrics = introduce_scheduler.metrics;
+                text.sections[1].value = format!(
+                    "{} / {} / {} frames",
+                    metrics.pending, metrics.ready, metrics.longest_wait
+                );
+            }
+            DebugText::Geometry => {
+                let (vertices, triangles) = subchunks_query
+                    .iter()
+                    .filter_map(|handle| meshes.get(handle))
+                    .fold((0, 0), |(vertices, triangles), mesh| {
+                        (
+                            vertices + mesh.count_vertices(),
+                            triangles + mesh.indices().map_or(0, |i| i.len() / 3),
+                        )
+                    });
+                text.sections[1].value = format!("{} / {}", vertices, triangles);
+            }
+            DebugText::Toggles => {
+                let on_off = |b: bool| if b { "on" } else { "off" };
+                text.sections[1].value = format!(
+                    "[{:?}] wireframe: {}  [{:?}] aabb: {}  [{:?}] chunk borders: {}",
+                    TOGGLE_WIREFRAME_KEY,
+                    on_off(dm_settings.render_wireframe),
+                    TOGGLE_AABB_KEY,
+                    on_off(dm_settings.render_aabb),
+                    TOGGLE_CHUNK_BORDERS_KEY,
+                    on_off(dm_settings.render_chunk_borders),
+                );
+            }
         }
     }
 }
 
+/// Record the time of every frame, for the frame-time graph.
+pub(super) fn record_frame_time(mut history: ResMut<FrameTimeHistory>, time: Res<Time<Real>>) {
+    if history.0.len() == FRAME_TIME_GRAPH_LENGTH {
+        history.0.pop_front();
+    }
+    history.0.push_back(time.delta_seconds() * 1000.0);
+}
+
+/// Update the bars of the frame-time graph. Green is 60 fps or more, yellow is 30 fps or more,
+/// red is anything slower.
+pub(super) fn update_frame_time_graph(
+    history: Res<FrameTimeHistory>,
+    mut bars: Query<(&FrameTimeBar, &mut Style, &mut BackgroundColor)>,
+) {
+    // The newest frame is always the rightmost bar
+    let offset = FRAME_TIME_GRAPH_LENGTH - history.0.len();
+    for (FrameTimeBar(i), mut style, mut color) in bars.iter_mut() {
+        let frame_time = i
+            .checked_sub(offset)
+            .and_then(|i| history.0.get(i))
+            .copied()
+            .unwrap_or(0.0);
+        let fill = (frame_time / FRAME_TIME_GRAPH_MAX_MS).min(1.0);
+        style.height = Val::Px(fill * FRAME_TIME_GRAPH_HEIGHT);
+        *color = if frame_time <= 1000.0 / 60.0 {
+            Color::GREEN
+        } else if frame_time <= 1000.0 / 30.0 {
+            Color::YELLOW
+        } else {
+            Color::RED
+        }
+        .into();
+    }
+}
+
 pub(super) fn hide_debug_mode(
     mut debug_ui: Query<&mut Visibility, With<DebugUI>>,
     mut wireframe_config: ResMut<WireframeConfig>,
@@ -223,12 +401,8 @@ pub(super) fn show_debug_mode(
     if let Ok(mut visibillity) = debug_ui.get_single_mut() {
         *visibillity = Visibility::Visible;
     }
-    if dm_settings.render_wireframe {
-        wireframe_config.global = true;
-    }
-    if dm_settings.render_aabb {
-        aabb_gizmo_config.aabb.draw_all = true;
-    }
+    wireframe_config.global = dm_settings.render_wireframe;
+    aabb_gizmo_config.aabb.draw_all = dm_settings.render_aabb;
 }
 
 pub(super) fn toggle_debug_mode(
@@ -240,3 +414,28 @@ pub(super) fn toggle_debug_mode(
         next_state_debug.set(current_state_debug.get().opposite());
     }
 }
+
+/// Run condition, whether the chunk borders should be rendered (when in debug mode).
+pub(super) fn render_chunk_borders_enabled(dm_settings: Res<DebugModeSettings>) -> bool {
+    dm_settings.render_chunk_borders
+}
+
+/// Toggle the debug mode's settings with their hotkeys (only while in debug mode).
+pub(super) fn debug_mode_hotkeys(
+    keys: Res<Input<KeyCode>>,
+    mut dm_settings: ResMut<DebugModeSettings>,
+    mut wireframe_config: ResMut<WireframeConfig>,
+    mut aabb_gizmo_config: ResMut<GizmoConfig>,
+) {
+    if keys.just_pressed(TOGGLE_WIREFRAME_KEY) {
+        dm_settings.render_wireframe = !dm_settings.render_wireframe;
+        wireframe_config.global = dm_settings.render_wireframe;
+    }
+    if keys.just_pressed(TOGGLE_AABB_KEY) {
+        dm_settings.render_aabb = !dm_settings.render_aabb;
+        aabb_gizmo_config.aabb.draw_all = dm_settings.render_aabb;
+    }
+    if keys.just_pressed(TOGGLE_CHUNK_BORDERS_KEY) {
+        dm_settings.render_chunk_borders = !dm_settings.render_chunk_borders;
+    }
+}